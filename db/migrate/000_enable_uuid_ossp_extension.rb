@@ -1,5 +0,0 @@
-class EnableUuidOsspExtension < ActiveRecord::Migration[4.2]
-  def change
-    enable_extension 'uuid-ossp'
-  end
-end
@@ -1,5 +0,0 @@
-class ChangeDescOfEntriesToText < ActiveRecord::Migration[4.2]
-  def change
-    change_column(:entries, :description, :text)
-  end
-end
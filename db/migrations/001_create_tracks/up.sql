@@ -0,0 +1,10 @@
+CREATE TABLE tracks (
+  id uuid NOT NULL DEFAULT uuid_generate_v4() PRIMARY KEY,
+  provider   character varying NOT NULL,
+  identifier character varying NOT NULL,
+  title      character varying NOT NULL,
+  url        character varying NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_tracks_on_provider_and_identifier ON tracks (provider, identifier);
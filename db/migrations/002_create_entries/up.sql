@@ -0,0 +1,7 @@
+CREATE TABLE entries (
+  id uuid NOT NULL DEFAULT uuid_generate_v4() PRIMARY KEY,
+  url character varying NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_entries_on_url ON entries (url);
@@ -0,0 +1,8 @@
+CREATE TABLE track_entries (
+  id serial PRIMARY KEY,
+  track_id uuid NOT NULL,
+  entry_id uuid NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_track_entries_on_track_id_and_entry_id ON track_entries (track_id, entry_id);
@@ -0,0 +1,5 @@
+ALTER TABLE entries
+  DROP COLUMN title,
+  DROP COLUMN description,
+  DROP COLUMN visual_url,
+  DROP COLUMN locale;
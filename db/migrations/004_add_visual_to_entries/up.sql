@@ -0,0 +1,5 @@
+ALTER TABLE entries
+  ADD COLUMN title character varying,
+  ADD COLUMN description character varying,
+  ADD COLUMN visual_url character varying,
+  ADD COLUMN locale character varying;
@@ -0,0 +1,9 @@
+ALTER TABLE tracks
+  DROP COLUMN description,
+  DROP COLUMN owner_id,
+  DROP COLUMN owner_name,
+  DROP COLUMN thumbnail_url,
+  DROP COLUMN artwork_url,
+  DROP COLUMN duration,
+  DROP COLUMN published_at,
+  DROP COLUMN state;
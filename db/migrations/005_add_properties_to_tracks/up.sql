@@ -0,0 +1,9 @@
+ALTER TABLE tracks
+  ADD COLUMN description text,
+  ADD COLUMN owner_id character varying,
+  ADD COLUMN owner_name character varying,
+  ADD COLUMN thumbnail_url character varying,
+  ADD COLUMN artwork_url character varying,
+  ADD COLUMN duration integer NOT NULL DEFAULT 0,
+  ADD COLUMN published_at timestamp NOT NULL DEFAULT NOW(),
+  ADD COLUMN state character varying NOT NULL DEFAULT 'alive';
@@ -0,0 +1 @@
+ALTER TABLE entries ALTER COLUMN description TYPE text;
@@ -0,0 +1,10 @@
+ALTER TABLE entries
+  ALTER COLUMN created_at DROP NOT NULL,
+  ALTER COLUMN created_at DROP DEFAULT,
+  ALTER COLUMN updated_at DROP NOT NULL,
+  ALTER COLUMN updated_at DROP DEFAULT;
+ALTER TABLE tracks
+  ALTER COLUMN created_at DROP NOT NULL,
+  ALTER COLUMN created_at DROP DEFAULT,
+  ALTER COLUMN updated_at DROP NOT NULL,
+  ALTER COLUMN updated_at DROP DEFAULT;
@@ -0,0 +1,17 @@
+CREATE TABLE playlists (
+  id uuid NOT NULL DEFAULT uuid_generate_v4() PRIMARY KEY,
+  provider      character varying NOT NULL,
+  identifier    character varying NOT NULL,
+  owner_id      character varying,
+  owner_name    character varying,
+  url           character varying NOT NULL,
+  title         character varying NOT NULL,
+  description   text,
+  thumbnail_url character varying,
+  artwork_url   character varying,
+  state         character varying NOT NULL DEFAULT 'alive',
+  published_at  timestamp NOT NULL DEFAULT NOW(),
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_playlists_on_provider_and_identifier ON playlists (provider, identifier);
@@ -0,0 +1,8 @@
+CREATE TABLE playlist_entries (
+  id serial PRIMARY KEY,
+  playlist_id uuid NOT NULL,
+  entry_id uuid NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_playlist_entries_on_playlist_id_and_entry_id ON playlist_entries (playlist_id, entry_id);
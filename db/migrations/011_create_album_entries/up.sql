@@ -0,0 +1,8 @@
+CREATE TABLE album_entries (
+  id serial PRIMARY KEY,
+  album_id uuid NOT NULL,
+  entry_id uuid NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_album_entries_on_album_id_and_entry_id ON album_entries (album_id, entry_id);
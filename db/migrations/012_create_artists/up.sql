@@ -0,0 +1,12 @@
+CREATE TABLE artists (
+  id uuid NOT NULL DEFAULT uuid_generate_v4() PRIMARY KEY,
+  provider      character varying NOT NULL,
+  identifier    character varying NOT NULL,
+  url           character varying NOT NULL,
+  name          character varying NOT NULL,
+  thumbnail_url character varying,
+  artwork_url   character varying,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_artists_on_provider_and_identifier ON artists (provider, identifier);
@@ -0,0 +1,8 @@
+CREATE TABLE track_artists (
+  id serial PRIMARY KEY,
+  track_id uuid NOT NULL,
+  artist_id uuid NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_track_artists_on_track_id_and_artist_id ON track_artists (track_id, artist_id);
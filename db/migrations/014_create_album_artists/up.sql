@@ -0,0 +1,8 @@
+CREATE TABLE album_artists (
+  id serial PRIMARY KEY,
+  album_id uuid NOT NULL,
+  artist_id uuid NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_album_artists_on_album_id_and_artist_id ON album_artists (album_id, artist_id);
@@ -0,0 +1,2 @@
+ALTER TABLE tracks
+  ADD COLUMN audio_url character varying;
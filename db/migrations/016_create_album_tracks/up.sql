@@ -0,0 +1,8 @@
+CREATE TABLE album_tracks (
+  id serial PRIMARY KEY,
+  track_id uuid NOT NULL,
+  album_id uuid NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_album_tracks_on_track_id_and_album_id ON album_tracks (track_id, album_id);
@@ -0,0 +1,8 @@
+CREATE TABLE playlist_tracks (
+  id serial PRIMARY KEY,
+  track_id uuid NOT NULL,
+  playlist_id uuid NOT NULL,
+  created_at timestamp,
+  updated_at timestamp
+);
+CREATE UNIQUE INDEX index_playlist_tracks_on_track_id_and_playlist_id ON playlist_tracks (track_id, playlist_id);
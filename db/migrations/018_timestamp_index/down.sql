@@ -0,0 +1,10 @@
+DROP INDEX index_entries_on_created_at;
+DROP INDEX index_entries_on_updated_at;
+DROP INDEX index_tracks_on_created_at;
+DROP INDEX index_tracks_on_updated_at;
+DROP INDEX index_albums_on_created_at;
+DROP INDEX index_albums_on_updated_at;
+DROP INDEX index_playlists_on_created_at;
+DROP INDEX index_playlists_on_updated_at;
+DROP INDEX index_artists_on_created_at;
+DROP INDEX index_artists_on_updated_at;
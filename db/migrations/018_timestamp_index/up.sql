@@ -0,0 +1,10 @@
+CREATE INDEX index_entries_on_created_at ON entries (created_at);
+CREATE INDEX index_entries_on_updated_at ON entries (updated_at);
+CREATE INDEX index_tracks_on_created_at ON tracks (created_at);
+CREATE INDEX index_tracks_on_updated_at ON tracks (updated_at);
+CREATE INDEX index_albums_on_created_at ON albums (created_at);
+CREATE INDEX index_albums_on_updated_at ON albums (updated_at);
+CREATE INDEX index_playlists_on_created_at ON playlists (created_at);
+CREATE INDEX index_playlists_on_updated_at ON playlists (updated_at);
+CREATE INDEX index_artists_on_created_at ON artists (created_at);
+CREATE INDEX index_artists_on_updated_at ON artists (updated_at);
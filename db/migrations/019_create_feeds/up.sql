@@ -0,0 +1,22 @@
+CREATE TABLE feeds (
+  id uuid NOT NULL DEFAULT uuid_generate_v4() PRIMARY KEY,
+  url          character varying NOT NULL,
+  title        character varying NOT NULL DEFAULT '',
+  description  character varying,
+  language     character varying,
+  velocity     double precision NOT NULL DEFAULT 0,
+  website      character varying,
+  state        character varying NOT NULL DEFAULT 'alive',
+  last_updated timestamp NOT NULL DEFAULT NOW(),
+  crawled      timestamp NOT NULL DEFAULT NOW(),
+  visual_url   character varying,
+  icon_url     character varying,
+  cover_url    character varying,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_feeds_on_url ON feeds (url);
+CREATE INDEX index_feeds_on_last_updated ON feeds (last_updated);
+CREATE INDEX index_feeds_on_crawled ON feeds (crawled);
+CREATE INDEX index_feeds_on_created_at ON feeds (created_at);
+CREATE INDEX index_feeds_on_updated_at ON feeds (updated_at);
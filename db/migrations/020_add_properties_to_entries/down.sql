@@ -0,0 +1,13 @@
+ALTER TABLE entries
+  DROP COLUMN summary,
+  DROP COLUMN content,
+  DROP COLUMN author,
+  DROP COLUMN crawled,
+  DROP COLUMN published,
+  DROP COLUMN updated,
+  DROP COLUMN fingerprint,
+  DROP COLUMN alternate,
+  DROP COLUMN enclosure,
+  DROP COLUMN keywords,
+  DROP COLUMN origin_id,
+  DROP COLUMN feed_id;
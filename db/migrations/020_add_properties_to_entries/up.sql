@@ -0,0 +1,15 @@
+ALTER TABLE entries
+  ADD COLUMN summary     text,
+  ADD COLUMN content     text,
+  ADD COLUMN author      character varying,
+  ADD COLUMN crawled     timestamp NOT NULL DEFAULT NOW(),
+  ADD COLUMN published   timestamp NOT NULL DEFAULT NOW(),
+  ADD COLUMN updated     timestamp DEFAULT NOW(),
+  ADD COLUMN fingerprint character varying NOT NULL DEFAULT '',
+  ADD COLUMN alternate   jsonb NOT NULL DEFAULT 'null',
+  ADD COLUMN enclosure   jsonb NOT NULL DEFAULT 'null',
+  ADD COLUMN keywords    jsonb NOT NULL DEFAULT 'null',
+  ADD COLUMN origin_id   character varying NOT NULL DEFAULT '',
+  ADD COLUMN feed_id     uuid;
+CREATE INDEX index_entries_on_published ON entries (published);
+CREATE INDEX index_entries_on_origin_id ON entries (origin_id);
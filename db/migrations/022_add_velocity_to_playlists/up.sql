@@ -0,0 +1,2 @@
+ALTER TABLE playlists
+  ADD COLUMN velocity double precision NOT NULL DEFAULT 0;
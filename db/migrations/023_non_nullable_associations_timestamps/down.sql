@@ -0,0 +1,20 @@
+ALTER TABLE playlist_tracks
+  ALTER COLUMN created_at DROP NOT NULL,
+  ALTER COLUMN created_at DROP DEFAULT,
+  ALTER COLUMN updated_at DROP NOT NULL,
+  ALTER COLUMN updated_at DROP DEFAULT;
+ALTER TABLE album_tracks
+  ALTER COLUMN created_at DROP NOT NULL,
+  ALTER COLUMN created_at DROP DEFAULT,
+  ALTER COLUMN updated_at DROP NOT NULL,
+  ALTER COLUMN updated_at DROP DEFAULT;
+ALTER TABLE track_artists
+  ALTER COLUMN created_at DROP NOT NULL,
+  ALTER COLUMN created_at DROP DEFAULT,
+  ALTER COLUMN updated_at DROP NOT NULL,
+  ALTER COLUMN updated_at DROP DEFAULT;
+ALTER TABLE album_artists
+  ALTER COLUMN created_at DROP NOT NULL,
+  ALTER COLUMN created_at DROP DEFAULT,
+  ALTER COLUMN updated_at DROP NOT NULL,
+  ALTER COLUMN updated_at DROP DEFAULT;
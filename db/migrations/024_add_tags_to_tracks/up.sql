@@ -0,0 +1,2 @@
+ALTER TABLE tracks
+  ADD COLUMN tags jsonb NOT NULL DEFAULT '[]';
@@ -0,0 +1,7 @@
+CREATE TABLE genres (
+  id uuid NOT NULL DEFAULT uuid_generate_v4() PRIMARY KEY,
+  name character varying NOT NULL,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_genres_on_name ON genres (name);
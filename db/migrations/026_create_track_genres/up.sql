@@ -0,0 +1,9 @@
+CREATE TABLE track_genres (
+  id serial PRIMARY KEY,
+  track_id uuid NOT NULL,
+  genre_id uuid NOT NULL,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_track_genres_on_track_id_and_genre_id ON track_genres (track_id, genre_id);
+CREATE INDEX index_track_genres_on_genre_id ON track_genres (genre_id);
@@ -0,0 +1,9 @@
+CREATE TABLE track_moods (
+  id serial PRIMARY KEY,
+  track_id uuid NOT NULL,
+  name     character varying NOT NULL,
+  ord      integer NOT NULL DEFAULT 0,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_track_moods_on_track_id_and_name ON track_moods (track_id, name);
@@ -0,0 +1,9 @@
+CREATE TABLE album_genres (
+  id serial PRIMARY KEY,
+  album_id uuid NOT NULL,
+  genre_id uuid NOT NULL,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_album_genres_on_album_id_and_genre_id ON album_genres (album_id, genre_id);
+CREATE INDEX index_album_genres_on_genre_id ON album_genres (genre_id);
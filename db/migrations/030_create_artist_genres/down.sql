@@ -0,0 +1 @@
+DROP TABLE artist_genres;
@@ -0,0 +1,9 @@
+CREATE TABLE artist_genres (
+  id serial PRIMARY KEY,
+  artist_id uuid NOT NULL,
+  genre_id uuid NOT NULL,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_artist_genres_on_artist_id_and_genre_id ON artist_genres (artist_id, genre_id);
+CREATE INDEX index_artist_genres_on_genre_id ON artist_genres (genre_id);
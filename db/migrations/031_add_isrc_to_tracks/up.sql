@@ -0,0 +1,3 @@
+ALTER TABLE tracks
+  ADD COLUMN isrc character varying;
+CREATE INDEX index_tracks_on_isrc ON tracks (isrc);
@@ -0,0 +1,10 @@
+CREATE TABLE track_links (
+  id serial PRIMARY KEY,
+  track_id        uuid NOT NULL,
+  linked_track_id uuid NOT NULL,
+  matched_by      character varying NOT NULL,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX index_track_links_on_track_id_and_linked_track_id ON track_links (track_id, linked_track_id);
+CREATE INDEX index_track_links_on_linked_track_id ON track_links (linked_track_id);
@@ -0,0 +1,9 @@
+CREATE TABLE canonical_artists (
+  id uuid NOT NULL DEFAULT uuid_generate_v4() PRIMARY KEY,
+  name          character varying NOT NULL,
+  thumbnail_url character varying,
+  artwork_url   character varying,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  updated_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE INDEX index_canonical_artists_on_name ON canonical_artists (name);
@@ -0,0 +1,2 @@
+ALTER TABLE artists
+  DROP COLUMN canonical_artist_id;
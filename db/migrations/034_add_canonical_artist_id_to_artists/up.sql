@@ -0,0 +1,3 @@
+ALTER TABLE artists
+  ADD COLUMN canonical_artist_id uuid;
+CREATE INDEX index_artists_on_canonical_artist_id ON artists (canonical_artist_id);
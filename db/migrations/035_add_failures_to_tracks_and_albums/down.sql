@@ -0,0 +1,11 @@
+DROP TABLE track_failures;
+ALTER TABLE albums
+  DROP COLUMN failure_count,
+  DROP COLUMN missing_count,
+  DROP COLUMN failed_at,
+  DROP COLUMN last_failure;
+ALTER TABLE tracks
+  DROP COLUMN failure_count,
+  DROP COLUMN missing_count,
+  DROP COLUMN failed_at,
+  DROP COLUMN last_failure;
@@ -0,0 +1,16 @@
+ALTER TABLE tracks ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tracks ADD COLUMN missing_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tracks ADD COLUMN failed_at     TEXT;
+ALTER TABLE tracks ADD COLUMN last_failure  TEXT;
+ALTER TABLE albums ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE albums ADD COLUMN missing_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE albums ADD COLUMN failed_at     TEXT;
+ALTER TABLE albums ADD COLUMN last_failure  TEXT;
+CREATE TABLE track_failures (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id   TEXT NOT NULL,
+  error      TEXT NOT NULL,
+  message    TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX index_track_failures_on_track_id ON track_failures (track_id);
@@ -0,0 +1,18 @@
+ALTER TABLE tracks
+  ADD COLUMN failure_count integer NOT NULL DEFAULT 0,
+  ADD COLUMN missing_count integer NOT NULL DEFAULT 0,
+  ADD COLUMN failed_at     timestamp,
+  ADD COLUMN last_failure  character varying;
+ALTER TABLE albums
+  ADD COLUMN failure_count integer NOT NULL DEFAULT 0,
+  ADD COLUMN missing_count integer NOT NULL DEFAULT 0,
+  ADD COLUMN failed_at     timestamp,
+  ADD COLUMN last_failure  character varying;
+CREATE TABLE track_failures (
+  id serial PRIMARY KEY,
+  track_id   uuid NOT NULL,
+  error      character varying NOT NULL,
+  message    text NOT NULL,
+  created_at timestamp NOT NULL DEFAULT NOW()
+);
+CREATE INDEX index_track_failures_on_track_id ON track_failures (track_id);
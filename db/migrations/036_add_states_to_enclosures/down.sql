@@ -0,0 +1,15 @@
+DROP INDEX index_playlists_on_state;
+DROP INDEX index_albums_on_state;
+DROP INDEX index_tracks_on_state;
+ALTER TABLE playlists DROP CONSTRAINT playlists_state_check;
+ALTER TABLE albums DROP CONSTRAINT albums_state_check;
+ALTER TABLE tracks DROP CONSTRAINT tracks_state_check;
+UPDATE tracks    SET state = 'alive' WHERE state = 'pending';
+UPDATE albums    SET state = 'alive' WHERE state = 'pending';
+UPDATE playlists SET state = 'alive' WHERE state = 'pending';
+UPDATE tracks    SET state = 'dead' WHERE state NOT IN ('alive', 'dead');
+UPDATE albums    SET state = 'dead' WHERE state NOT IN ('alive', 'dead');
+UPDATE playlists SET state = 'dead' WHERE state NOT IN ('alive', 'dead');
+ALTER TABLE tracks    ALTER COLUMN state SET DEFAULT 'alive';
+ALTER TABLE albums    ALTER COLUMN state SET DEFAULT 'alive';
+ALTER TABLE playlists ALTER COLUMN state SET DEFAULT 'alive';
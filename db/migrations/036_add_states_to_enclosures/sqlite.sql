@@ -0,0 +1,5 @@
+-- SQLite can't change the defaults and the constraints of the columns,
+-- so the models insert the state of a new enclosure explicitly.
+CREATE INDEX index_tracks_on_state ON tracks (state);
+CREATE INDEX index_albums_on_state ON albums (state);
+CREATE INDEX index_playlists_on_state ON playlists (state);
@@ -0,0 +1,12 @@
+ALTER TABLE tracks    ALTER COLUMN state SET DEFAULT 'pending';
+ALTER TABLE albums    ALTER COLUMN state SET DEFAULT 'pending';
+ALTER TABLE playlists ALTER COLUMN state SET DEFAULT 'pending';
+ALTER TABLE tracks ADD CONSTRAINT tracks_state_check
+  CHECK (state IN ('pending', 'alive', 'region_restricted', 'private', 'removed', 'unavailable', 'dead'));
+ALTER TABLE albums ADD CONSTRAINT albums_state_check
+  CHECK (state IN ('pending', 'alive', 'region_restricted', 'private', 'removed', 'unavailable', 'dead'));
+ALTER TABLE playlists ADD CONSTRAINT playlists_state_check
+  CHECK (state IN ('pending', 'alive', 'region_restricted', 'private', 'removed', 'unavailable', 'dead'));
+CREATE INDEX index_tracks_on_state ON tracks (state);
+CREATE INDEX index_albums_on_state ON albums (state);
+CREATE INDEX index_playlists_on_state ON playlists (state);
@@ -0,0 +1,8 @@
+CREATE TABLE search_tokens (
+  item_type TEXT NOT NULL,
+  item_id   TEXT NOT NULL,
+  token     TEXT NOT NULL,
+  weight    REAL NOT NULL,
+  PRIMARY KEY (item_type, item_id, token)
+);
+CREATE INDEX index_search_tokens_on_token_and_item_type ON search_tokens (token, item_type);
@@ -0,0 +1,8 @@
+CREATE TABLE search_tokens (
+  item_type character varying NOT NULL,
+  item_id   uuid NOT NULL,
+  token     character varying NOT NULL,
+  weight    double precision NOT NULL,
+  PRIMARY KEY (item_type, item_id, token)
+);
+CREATE INDEX index_search_tokens_on_token_and_item_type ON search_tokens (token, item_type);
@@ -0,0 +1,10 @@
+CREATE TABLE api_keys (
+  id         TEXT NOT NULL PRIMARY KEY,
+  name       TEXT NOT NULL,
+  prefix     TEXT NOT NULL,
+  key_hash   TEXT NOT NULL,
+  scope      TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  revoked_at TEXT
+);
+CREATE UNIQUE INDEX index_api_keys_on_key_hash ON api_keys (key_hash);
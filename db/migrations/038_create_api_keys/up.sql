@@ -0,0 +1,10 @@
+CREATE TABLE api_keys (
+  id uuid NOT NULL PRIMARY KEY,
+  name       character varying NOT NULL,
+  prefix     character varying NOT NULL,
+  key_hash   character varying NOT NULL,
+  scope      character varying NOT NULL,
+  created_at timestamp NOT NULL DEFAULT NOW(),
+  revoked_at timestamp
+);
+CREATE UNIQUE INDEX index_api_keys_on_key_hash ON api_keys (key_hash);
@@ -0,0 +1,2 @@
+DROP INDEX index_tracks_on_linked_at;
+ALTER TABLE tracks DROP COLUMN linked_at;
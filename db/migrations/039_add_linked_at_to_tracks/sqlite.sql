@@ -0,0 +1,2 @@
+ALTER TABLE tracks ADD COLUMN linked_at TEXT;
+CREATE INDEX index_tracks_on_linked_at ON tracks (linked_at);
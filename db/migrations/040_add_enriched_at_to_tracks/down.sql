@@ -0,0 +1,2 @@
+DROP INDEX index_tracks_on_enriched_at;
+ALTER TABLE tracks DROP COLUMN enriched_at;
@@ -0,0 +1,2 @@
+ALTER TABLE tracks ADD COLUMN enriched_at timestamp;
+CREATE INDEX index_tracks_on_enriched_at ON tracks (enriched_at);
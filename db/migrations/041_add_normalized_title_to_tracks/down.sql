@@ -0,0 +1,2 @@
+DROP INDEX index_tracks_on_normalized_title;
+ALTER TABLE tracks DROP COLUMN normalized_title;
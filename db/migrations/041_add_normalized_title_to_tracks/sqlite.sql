@@ -0,0 +1,2 @@
+ALTER TABLE tracks ADD COLUMN normalized_title TEXT;
+CREATE INDEX index_tracks_on_normalized_title ON tracks (normalized_title);
@@ -0,0 +1,7 @@
+ALTER TABLE tracks ADD COLUMN normalized_title text;
+UPDATE tracks SET normalized_title = trim(regexp_replace(
+    regexp_replace(
+        regexp_replace(lower(title), '[\(\[【][^\)\]】]*[\)\]】]', ' ', 'g'),
+        '\s(feat|ft|featuring)\.?\s.*$', ''),
+    '[^[:alnum:]]+', ' ', 'g'));
+CREATE INDEX index_tracks_on_normalized_title ON tracks (normalized_title);
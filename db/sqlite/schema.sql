@@ -0,0 +1,311 @@
+-- The schema of SQLite databases, which is the same as db/migrations up to 034.
+-- Uuids, timestamps and json are stored as text.
+
+CREATE TABLE tracks (
+  id            TEXT NOT NULL PRIMARY KEY DEFAULT (lower(
+                  hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+                  substr(hex(randomblob(2)), 2) || '-' ||
+                  substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+                  hex(randomblob(6)))),
+  provider      TEXT NOT NULL,
+  identifier    TEXT NOT NULL,
+  title         TEXT NOT NULL,
+  url           TEXT NOT NULL,
+  description   TEXT,
+  owner_id      TEXT,
+  owner_name    TEXT,
+  thumbnail_url TEXT,
+  artwork_url   TEXT,
+  audio_url     TEXT,
+  duration      INTEGER NOT NULL DEFAULT 0,
+  published_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  state         TEXT NOT NULL DEFAULT 'alive',
+  tags          TEXT NOT NULL DEFAULT '[]',
+  isrc          TEXT,
+  created_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_tracks_on_provider_and_identifier ON tracks (provider, identifier);
+CREATE INDEX index_tracks_on_isrc ON tracks (isrc);
+CREATE INDEX index_tracks_on_created_at ON tracks (created_at);
+CREATE INDEX index_tracks_on_updated_at ON tracks (updated_at);
+
+CREATE TABLE entries (
+  id          TEXT NOT NULL PRIMARY KEY DEFAULT (lower(
+                hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+                substr(hex(randomblob(2)), 2) || '-' ||
+                substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+                hex(randomblob(6)))),
+  url         TEXT NOT NULL,
+  title       TEXT,
+  description TEXT,
+  visual_url  TEXT,
+  locale      TEXT,
+  summary     TEXT,
+  content     TEXT,
+  text        TEXT,
+  author      TEXT,
+  crawled     TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  published   TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated     TEXT DEFAULT CURRENT_TIMESTAMP,
+  fingerprint TEXT NOT NULL DEFAULT '',
+  alternate   TEXT NOT NULL DEFAULT 'null',
+  enclosure   TEXT NOT NULL DEFAULT 'null',
+  keywords    TEXT NOT NULL DEFAULT 'null',
+  origin_id   TEXT NOT NULL DEFAULT '',
+  feed_id     TEXT,
+  created_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_entries_on_url ON entries (url);
+CREATE INDEX index_entries_on_published ON entries (published);
+CREATE INDEX index_entries_on_origin_id ON entries (origin_id);
+CREATE INDEX index_entries_on_created_at ON entries (created_at);
+CREATE INDEX index_entries_on_updated_at ON entries (updated_at);
+
+CREATE TABLE playlists (
+  id            TEXT NOT NULL PRIMARY KEY DEFAULT (lower(
+                  hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+                  substr(hex(randomblob(2)), 2) || '-' ||
+                  substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+                  hex(randomblob(6)))),
+  provider      TEXT NOT NULL,
+  identifier    TEXT NOT NULL,
+  owner_id      TEXT,
+  owner_name    TEXT,
+  url           TEXT NOT NULL,
+  title         TEXT NOT NULL,
+  description   TEXT,
+  thumbnail_url TEXT,
+  artwork_url   TEXT,
+  state         TEXT NOT NULL DEFAULT 'alive',
+  velocity      REAL NOT NULL DEFAULT 0,
+  published_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_playlists_on_provider_and_identifier ON playlists (provider, identifier);
+CREATE INDEX index_playlists_on_created_at ON playlists (created_at);
+CREATE INDEX index_playlists_on_updated_at ON playlists (updated_at);
+
+CREATE TABLE albums (
+  id            TEXT NOT NULL PRIMARY KEY DEFAULT (lower(
+                  hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+                  substr(hex(randomblob(2)), 2) || '-' ||
+                  substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+                  hex(randomblob(6)))),
+  provider      TEXT NOT NULL,
+  identifier    TEXT NOT NULL,
+  owner_id      TEXT,
+  owner_name    TEXT,
+  url           TEXT NOT NULL,
+  title         TEXT NOT NULL,
+  description   TEXT,
+  thumbnail_url TEXT,
+  artwork_url   TEXT,
+  state         TEXT NOT NULL DEFAULT 'alive',
+  published_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_albums_on_provider_and_identifier ON albums (provider, identifier);
+CREATE INDEX index_albums_on_created_at ON albums (created_at);
+CREATE INDEX index_albums_on_updated_at ON albums (updated_at);
+
+CREATE TABLE artists (
+  id                  TEXT NOT NULL PRIMARY KEY DEFAULT (lower(
+                        hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+                        substr(hex(randomblob(2)), 2) || '-' ||
+                        substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+                        hex(randomblob(6)))),
+  provider            TEXT NOT NULL,
+  identifier          TEXT NOT NULL,
+  url                 TEXT NOT NULL,
+  name                TEXT NOT NULL,
+  thumbnail_url       TEXT,
+  artwork_url         TEXT,
+  canonical_artist_id TEXT,
+  created_at          TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at          TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_artists_on_provider_and_identifier ON artists (provider, identifier);
+CREATE INDEX index_artists_on_canonical_artist_id ON artists (canonical_artist_id);
+CREATE INDEX index_artists_on_created_at ON artists (created_at);
+CREATE INDEX index_artists_on_updated_at ON artists (updated_at);
+
+CREATE TABLE canonical_artists (
+  id            TEXT NOT NULL PRIMARY KEY DEFAULT (lower(
+                  hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+                  substr(hex(randomblob(2)), 2) || '-' ||
+                  substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+                  hex(randomblob(6)))),
+  name          TEXT NOT NULL,
+  thumbnail_url TEXT,
+  artwork_url   TEXT,
+  created_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX index_canonical_artists_on_name ON canonical_artists (name);
+
+CREATE TABLE feeds (
+  id           TEXT NOT NULL PRIMARY KEY DEFAULT (lower(
+                 hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+                 substr(hex(randomblob(2)), 2) || '-' ||
+                 substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+                 hex(randomblob(6)))),
+  url          TEXT NOT NULL,
+  title        TEXT NOT NULL DEFAULT '',
+  description  TEXT,
+  language     TEXT,
+  velocity     REAL NOT NULL DEFAULT 0,
+  website      TEXT,
+  state        TEXT NOT NULL DEFAULT 'alive',
+  last_updated TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  crawled      TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  visual_url   TEXT,
+  icon_url     TEXT,
+  cover_url    TEXT,
+  created_at   TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at   TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_feeds_on_url ON feeds (url);
+CREATE INDEX index_feeds_on_last_updated ON feeds (last_updated);
+CREATE INDEX index_feeds_on_crawled ON feeds (crawled);
+CREATE INDEX index_feeds_on_created_at ON feeds (created_at);
+CREATE INDEX index_feeds_on_updated_at ON feeds (updated_at);
+
+CREATE TABLE genres (
+  id         TEXT NOT NULL PRIMARY KEY DEFAULT (lower(
+               hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+               substr(hex(randomblob(2)), 2) || '-' ||
+               substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+               hex(randomblob(6)))),
+  name       TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_genres_on_name ON genres (name);
+
+CREATE TABLE track_entries (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id   TEXT NOT NULL,
+  entry_id   TEXT NOT NULL,
+  created_at TEXT,
+  updated_at TEXT
+);
+CREATE UNIQUE INDEX index_track_entries_on_track_id_and_entry_id ON track_entries (track_id, entry_id);
+
+CREATE TABLE playlist_entries (
+  id          INTEGER PRIMARY KEY AUTOINCREMENT,
+  playlist_id TEXT NOT NULL,
+  entry_id    TEXT NOT NULL,
+  created_at  TEXT,
+  updated_at  TEXT
+);
+CREATE UNIQUE INDEX index_playlist_entries_on_playlist_id_and_entry_id ON playlist_entries (playlist_id, entry_id);
+
+CREATE TABLE album_entries (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  album_id   TEXT NOT NULL,
+  entry_id   TEXT NOT NULL,
+  created_at TEXT,
+  updated_at TEXT
+);
+CREATE UNIQUE INDEX index_album_entries_on_album_id_and_entry_id ON album_entries (album_id, entry_id);
+
+CREATE TABLE track_artists (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id   TEXT NOT NULL,
+  artist_id  TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_track_artists_on_track_id_and_artist_id ON track_artists (track_id, artist_id);
+
+CREATE TABLE album_artists (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  album_id   TEXT NOT NULL,
+  artist_id  TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_album_artists_on_album_id_and_artist_id ON album_artists (album_id, artist_id);
+
+CREATE TABLE album_tracks (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id   TEXT NOT NULL,
+  album_id   TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_album_tracks_on_track_id_and_album_id ON album_tracks (track_id, album_id);
+
+CREATE TABLE playlist_tracks (
+  id          INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id    TEXT NOT NULL,
+  playlist_id TEXT NOT NULL,
+  created_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_playlist_tracks_on_track_id_and_playlist_id ON playlist_tracks (track_id, playlist_id);
+
+CREATE TABLE track_genres (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id   TEXT NOT NULL,
+  genre_id   TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_track_genres_on_track_id_and_genre_id ON track_genres (track_id, genre_id);
+CREATE INDEX index_track_genres_on_genre_id ON track_genres (genre_id);
+
+CREATE TABLE album_genres (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  album_id   TEXT NOT NULL,
+  genre_id   TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_album_genres_on_album_id_and_genre_id ON album_genres (album_id, genre_id);
+CREATE INDEX index_album_genres_on_genre_id ON album_genres (genre_id);
+
+CREATE TABLE artist_genres (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  artist_id  TEXT NOT NULL,
+  genre_id   TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_artist_genres_on_artist_id_and_genre_id ON artist_genres (artist_id, genre_id);
+CREATE INDEX index_artist_genres_on_genre_id ON artist_genres (genre_id);
+
+CREATE TABLE track_moods (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id   TEXT NOT NULL,
+  name       TEXT NOT NULL,
+  ord        INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_track_moods_on_track_id_and_name ON track_moods (track_id, name);
+
+CREATE TABLE track_tempos (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id   TEXT NOT NULL,
+  name       TEXT NOT NULL,
+  ord        INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_track_tempos_on_track_id_and_name ON track_tempos (track_id, name);
+
+CREATE TABLE track_links (
+  id              INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id        TEXT NOT NULL,
+  linked_track_id TEXT NOT NULL,
+  matched_by      TEXT NOT NULL,
+  created_at      TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at      TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX index_track_links_on_track_id_and_linked_track_id ON track_links (track_id, linked_track_id);
+CREATE INDEX index_track_links_on_linked_track_id ON track_links (linked_track_id);
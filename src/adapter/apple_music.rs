@@ -0,0 +1,83 @@
+use apple_music;
+use error::Error;
+use model::{Track, Album, Playlist, Artist, PlaylistTrack, Provider, Connection};
+use super::{ProviderAdapter, Identifier, Enclosures, extract_identifier};
+
+pub struct AppleMusicAdapter;
+
+impl ProviderAdapter for AppleMusicAdapter {
+    fn provider(&self) -> Provider {
+        Provider::AppleMusic
+    }
+
+    fn parse_url(&self, url: &str) -> Option<Identifier> {
+        if let Some(identifier) = extract_identifier(url, apple_music::SONG_URL) {
+            return Some(Identifier::Track(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, apple_music::ALBUM_URL) {
+            return Some(Identifier::Album(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, apple_music::PLAYLIST_URL) {
+            return Some(Identifier::Playlist(identifier))
+        }
+        match apple_music::parse_url_as_album(url) {
+            Some((_, _, _, Some(song_id))) => return Some(Identifier::Track(song_id)),
+            Some((_, _, album_id, None))   => return Some(Identifier::Album(album_id)),
+            None                           => (),
+        }
+        if let Some((_, _, identifier, _)) = apple_music::parse_url_as_playlist(url) {
+            return Some(Identifier::Playlist(identifier))
+        }
+        None
+    }
+
+    fn fetch_enclosures(&self, conn: &Connection, url: &str, identifier: &Identifier) -> Result<Enclosures, Error> {
+        let country = apple_music::country(url);
+        match *identifier {
+            Identifier::Track(ref id) => apple_music::fetch_song(&country, id)
+                .map(|song| (vec![], vec![], vec![Track::from_am_song(conn, &song)])),
+            Identifier::Album(ref id) => apple_music::fetch_album(&country, id)
+                .map(|album| (vec![], vec![Album::from_am_album(conn, &album)], vec![])),
+            Identifier::Playlist(ref id) => apple_music::fetch_playlist(&country, id)
+                .map(|playlist| (vec![Playlist::from_am_playlist(conn, &playlist)], vec![], vec![])),
+            _ => Ok((vec![], vec![], vec![])),
+        }
+    }
+
+    fn fetch_track(&self, conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let country = apple_music::country(&track.url);
+        let song    = apple_music::fetch_song(&country, &track.identifier)?;
+        track.update_with_am_song(conn, &song);
+        Ok(())
+    }
+
+    fn fetch_album(&self, conn: &Connection, album: &mut Album) -> Result<(), Error> {
+        let country  = apple_music::country(&album.url);
+        let am_album = apple_music::fetch_album(&country, &album.identifier)?;
+        album.update_with_am_album(conn, &am_album);
+        Ok(())
+    }
+
+    fn fetch_playlist(&self, _conn: &Connection, playlist: &mut Playlist) -> Result<(), Error> {
+        let country     = apple_music::country(&playlist.url);
+        let am_playlist = apple_music::fetch_playlist(&country, &playlist.identifier)?;
+        playlist.update_with_am_playlist(&am_playlist);
+        Ok(())
+    }
+
+    fn fetch_artist(&self, conn: &Connection, artist: &mut Artist) -> Result<(), Error> {
+        let country   = apple_music::country(&artist.url);
+        let am_artist = apple_music::fetch_artist(&country, &artist.identifier)?;
+        artist.update_with_am_artist(conn, &am_artist);
+        Ok(())
+    }
+
+    fn fetch_playlist_tracks(&self, conn: &Connection, playlist: &mut Playlist) -> Result<Vec<PlaylistTrack>, Error> {
+        let country     = apple_music::country(&playlist.url);
+        let am_playlist = apple_music::fetch_playlist(&country, &playlist.identifier)?;
+        let songs       = am_playlist.get_songs();
+        let song_ids    = songs.iter().map(|song| song.id.clone()).collect::<Vec<String>>();
+        let songs       = apple_music::fetch_songs(&country, song_ids).unwrap_or(vec![]);
+        Ok(playlist.add_tracks(conn, songs.iter().map(|song| Track::from_am_song(conn, song)).collect()))
+    }
+}
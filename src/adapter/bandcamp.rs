@@ -0,0 +1,60 @@
+use bandcamp;
+use error::Error;
+use model::{Track, Album, Provider, Connection};
+use super::{ProviderAdapter, Identifier, Enclosures};
+
+/// Bandcamp identifiers are the page (or embedded player) urls,
+/// since the numeric ids are only known after the page is parsed.
+pub struct BandcampAdapter;
+
+impl ProviderAdapter for BandcampAdapter {
+    fn provider(&self) -> Provider {
+        Provider::Bandcamp
+    }
+
+    fn parse_url(&self, url: &str) -> Option<Identifier> {
+        let (kind, url) = match bandcamp::parse_embed_url(url) {
+            Some((kind, id)) => {
+                let url = bandcamp::embed_url(&kind, &id);
+                (kind, url)
+            },
+            None => bandcamp::parse_page_url(url)?,
+        };
+        match kind.as_ref() {
+            "track" => Some(Identifier::Track(url)),
+            "album" => Some(Identifier::Album(url)),
+            _       => None,
+        }
+    }
+
+    fn fetch_enclosures(&self, conn: &Connection, _url: &str, identifier: &Identifier) -> Result<Enclosures, Error> {
+        match *identifier {
+            Identifier::Track(ref url) => {
+                let page   = bandcamp::fetch_page(url)?;
+                let tracks = page.tralbum.trackinfo.first()
+                    .map(|info| vec![Track::from_bc_track_info(conn, &page, info)])
+                    .unwrap_or(vec![]);
+                Ok((vec![], vec![], tracks))
+            },
+            Identifier::Album(ref url) => bandcamp::fetch_page(url)
+                .map(|page| (vec![], vec![Album::from_bc_album(conn, &page)], vec![])),
+            _ => Ok((vec![], vec![], vec![])),
+        }
+    }
+
+    fn fetch_track(&self, conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let page = bandcamp::fetch_page(&track.url)?;
+        let info = page.tralbum.trackinfo.iter()
+            .find(|info| info.identifier() == Some(track.identifier.clone()))
+            .or(page.tralbum.trackinfo.first())
+            .ok_or(Error::NotFound)?;
+        track.update_with_bc_track_info(conn, &page, info);
+        Ok(())
+    }
+
+    fn fetch_album(&self, conn: &Connection, album: &mut Album) -> Result<(), Error> {
+        let page = bandcamp::fetch_page(&album.url)?;
+        album.update_with_bc_album(conn, &page);
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use deezer;
+use error::Error;
+use model::{Track, Album, Playlist, Artist, PlaylistTrack, Provider, Connection};
+use super::{ProviderAdapter, Identifier, Enclosures, extract_identifier};
+
+pub struct DeezerAdapter;
+
+impl ProviderAdapter for DeezerAdapter {
+    fn provider(&self) -> Provider {
+        Provider::Deezer
+    }
+
+    fn parse_url(&self, url: &str) -> Option<Identifier> {
+        if let Some((kind, id)) = deezer::parse_widget_url(url) {
+            return match kind.as_ref() {
+                "track"    => Some(Identifier::Track(id)),
+                "album"    => Some(Identifier::Album(id)),
+                "playlist" => Some(Identifier::Playlist(id)),
+                _          => None,
+            }
+        }
+        if let Some(identifier) = extract_identifier(url, deezer::TRACK) {
+            return Some(Identifier::Track(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, deezer::ALBUM) {
+            return Some(Identifier::Album(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, deezer::PLAYLIST) {
+            return Some(Identifier::Playlist(identifier))
+        }
+        None
+    }
+
+    fn fetch_enclosures(&self, conn: &Connection, _url: &str, identifier: &Identifier) -> Result<Enclosures, Error> {
+        match *identifier {
+            Identifier::Track(ref id)    => deezer::fetch_track(id)
+                .map(|track| (vec![], vec![], vec![Track::from_dz_track(conn, &track)])),
+            Identifier::Album(ref id)    => deezer::fetch_album(id)
+                .map(|album| (vec![], vec![Album::from_dz_album(conn, &album)], vec![])),
+            Identifier::Playlist(ref id) => deezer::fetch_playlist(id)
+                .map(|playlist| (vec![Playlist::from_dz_playlist(conn, &playlist)], vec![], vec![])),
+            _                            => Ok((vec![], vec![], vec![])),
+        }
+    }
+
+    fn pending_enclosures(&self, identifier: &Identifier, err: Error) -> Enclosures {
+        match *identifier {
+            Identifier::Track(ref id) => (vec![], vec![], Track::from_failure(Provider::Deezer, id, err).into_iter().collect()),
+            _                         => (vec![], vec![], vec![]),
+        }
+    }
+
+    fn fetch_track(&self, conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let dz_track = deezer::fetch_track(&track.identifier)?;
+        track.update_with_dz_track(conn, &dz_track);
+        Ok(())
+    }
+
+    fn fetch_album(&self, conn: &Connection, album: &mut Album) -> Result<(), Error> {
+        let dz_album = deezer::fetch_album(&album.identifier)?;
+        album.update_with_dz_album(conn, &dz_album);
+        Ok(())
+    }
+
+    fn fetch_playlist(&self, conn: &Connection, playlist: &mut Playlist) -> Result<(), Error> {
+        let dz_playlist = deezer::fetch_playlist(&playlist.identifier)?;
+        playlist.update_with_dz_playlist(conn, &dz_playlist);
+        Ok(())
+    }
+
+    fn fetch_artist(&self, _conn: &Connection, artist: &mut Artist) -> Result<(), Error> {
+        let dz_artist = deezer::fetch_artist(&artist.identifier)?;
+        artist.update_with_dz_artist(&dz_artist);
+        Ok(())
+    }
+
+    fn fetch_playlist_tracks(&self, conn: &Connection, playlist: &mut Playlist) -> Result<Vec<PlaylistTrack>, Error> {
+        let mut items = vec![];
+        let mut page = deezer::fetch_playlist_tracks(&playlist.identifier)?;
+        items.append(&mut playlist.add_tracks(conn, page_tracks(conn, &page)));
+        while page.next.is_some() {
+            page = page.fetch_next()?;
+            items.append(&mut playlist.add_tracks(conn, page_tracks(conn, &page)));
+        }
+        Ok(items)
+    }
+}
+
+fn page_tracks(conn: &Connection, page: &deezer::Paging<deezer::Track>) -> Vec<Track> {
+    page.data.iter()
+        .filter(|t| t.readable.unwrap_or(true))
+        .map(|t| Track::from_dz_track(conn, t))
+        .collect()
+}
@@ -0,0 +1,18 @@
+use lemoned;
+use error::Error;
+use model::{Track, Provider, Connection};
+use super::ProviderAdapter;
+
+pub struct LemonedAdapter;
+
+impl ProviderAdapter for LemonedAdapter {
+    fn provider(&self) -> Provider {
+        Provider::Custom
+    }
+
+    fn fetch_track(&self, _conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let le_track = lemoned::fetch_track(&track.identifier)?;
+        track.update_with_le_track(&le_track);
+        Ok(())
+    }
+}
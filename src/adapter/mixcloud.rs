@@ -0,0 +1,44 @@
+use mixcloud;
+use error::Error;
+use model::{Track, Artist, Provider, Connection};
+use super::{ProviderAdapter, Identifier, Enclosures};
+
+/// Mixcloud cloudcasts are identified by their keys, e.g. `/spartacus/party-time/`,
+/// and users by their usernames.
+pub struct MixcloudAdapter;
+
+impl ProviderAdapter for MixcloudAdapter {
+    fn provider(&self) -> Provider {
+        Provider::Mixcloud
+    }
+
+    fn parse_url(&self, url: &str) -> Option<Identifier> {
+        let key = mixcloud::parse_embed_url(url)?;
+        match mixcloud::parse_key(&key)? {
+            (_, Some(_))     => Some(Identifier::Track(key)),
+            (username, None) => Some(Identifier::User(username)),
+        }
+    }
+
+    fn fetch_enclosures(&self, conn: &Connection, _url: &str, identifier: &Identifier) -> Result<Enclosures, Error> {
+        match *identifier {
+            Identifier::Track(ref key)     => mixcloud::fetch_cloudcast(key)
+                .map(|cloudcast| (vec![], vec![], vec![Track::from_mc_cloudcast(conn, &cloudcast)])),
+            Identifier::User(ref username) => mixcloud::fetch_user_cloudcasts(username)
+                .map(|list| (vec![], vec![], list.data.iter().map(|c| Track::from_mc_cloudcast(conn, c)).collect())),
+            _                              => Ok((vec![], vec![], vec![])),
+        }
+    }
+
+    fn fetch_track(&self, conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let cloudcast = mixcloud::fetch_cloudcast(&track.identifier)?;
+        track.update_with_mc_cloudcast(conn, &cloudcast);
+        Ok(())
+    }
+
+    fn fetch_artist(&self, _conn: &Connection, artist: &mut Artist) -> Result<(), Error> {
+        let user = mixcloud::fetch_user(&artist.identifier)?;
+        artist.update_with_mc_user(&user);
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use regex::Regex;
+use error::Error;
+use model::{Track, Album, Playlist, Artist, PlaylistTrack, Provider, Connection};
+
+pub mod apple_music;
+pub mod youtube;
+pub mod soundcloud;
+pub mod spotify;
+pub mod bandcamp;
+pub mod mixcloud;
+pub mod deezer;
+pub mod raw;
+pub mod lemoned;
+
+pub type Enclosures = (Vec<Playlist>, Vec<Album>, Vec<Track>);
+
+/// An identifier recognised in a url, before anything is fetched from the provider.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Identifier {
+    Track(String),
+    Album(String),
+    Playlist(String),
+    UserPlaylist(String, String),
+    User(String),
+}
+
+/// A music service that pink-spider can recognise in urls and fetch enclosures from.
+///
+/// Every method except `provider` has a default that does nothing,
+/// so an adapter only implements what its service supports.
+/// The fetched enclosures are saved with the connection of the caller.
+pub trait ProviderAdapter: Sync {
+    fn provider(&self) -> Provider;
+    fn parse_url(&self, _url: &str) -> Option<Identifier> {
+        None
+    }
+    /// The first failure of the provider is returned as it is, so that `resolve` can tell it.
+    fn fetch_enclosures(&self, _conn: &Connection, _url: &str, _identifier: &Identifier) -> Result<Enclosures, Error> {
+        Ok((vec![], vec![], vec![]))
+    }
+    /// The enclosures that the scraper keeps when `fetch_enclosures` fails, so that they are fetched again later.
+    fn pending_enclosures(&self, _identifier: &Identifier, _err: Error) -> Enclosures {
+        (vec![], vec![], vec![])
+    }
+    fn fetch_track(&self, _conn: &Connection, _track: &mut Track) -> Result<(), Error> {
+        Ok(())
+    }
+    fn fetch_album(&self, _conn: &Connection, _album: &mut Album) -> Result<(), Error> {
+        Ok(())
+    }
+    fn fetch_playlist(&self, _conn: &Connection, _playlist: &mut Playlist) -> Result<(), Error> {
+        Ok(())
+    }
+    fn fetch_artist(&self, _conn: &Connection, _artist: &mut Artist) -> Result<(), Error> {
+        Ok(())
+    }
+    fn fetch_playlist_tracks(&self, _conn: &Connection, _playlist: &mut Playlist) -> Result<Vec<PlaylistTrack>, Error> {
+        Ok(vec![])
+    }
+}
+
+static ADAPTERS: [&'static ProviderAdapter; 9] = [&apple_music::AppleMusicAdapter,
+                                                  &youtube::YouTubeAdapter,
+                                                  &soundcloud::SoundCloudAdapter,
+                                                  &spotify::SpotifyAdapter,
+                                                  &bandcamp::BandcampAdapter,
+                                                  &mixcloud::MixcloudAdapter,
+                                                  &deezer::DeezerAdapter,
+                                                  &lemoned::LemonedAdapter,
+                                                  &raw::RawAdapter];
+
+pub fn adapters() -> &'static [&'static ProviderAdapter] {
+    &ADAPTERS
+}
+
+pub fn find(provider: &Provider) -> Option<&'static ProviderAdapter> {
+    adapters().iter().find(|a| a.provider() == *provider).map(|a| *a)
+}
+
+pub fn recognize(url: &str) -> Option<(&'static ProviderAdapter, Identifier)> {
+    for adapter in adapters().iter() {
+        if let Some(identifier) = adapter.parse_url(url) {
+            return Some((*adapter, identifier))
+        }
+    }
+    None
+}
+
+pub fn extract_identifier(value: &str, regex_str: &str) -> Option<String> {
+    match Regex::new(regex_str) {
+        Ok(re) => match re.captures(value) {
+            Some(cap) => {
+                let strs: Vec<&str> = cap[1].split('?').collect();
+                return Some(strs[0].to_string())
+            },
+            None => None
+        },
+        Err(_) => None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{recognize, find, Identifier};
+    use Provider;
+
+    #[test]
+    fn test_recognize() {
+        let (adapter, identifier) = recognize("https://www.youtube.com/watch?v=oDuif301F-8").unwrap();
+        assert_eq!(adapter.provider(), Provider::YouTube);
+        assert_eq!(identifier, Identifier::Track("oDuif301F-8".to_string()));
+
+        let (adapter, identifier) = recognize("https://youtu.be/oDuif301F-8?t=30").unwrap();
+        assert_eq!(adapter.provider(), Provider::YouTube);
+        assert_eq!(identifier, Identifier::Track("oDuif301F-8".to_string()));
+
+        let (adapter, identifier) = recognize("https://music.apple.com/jp/album/random-access-memories/617154241").unwrap();
+        assert_eq!(adapter.provider(), Provider::AppleMusic);
+        assert_eq!(identifier, Identifier::Album("617154241".to_string()));
+
+        let (adapter, identifier) = recognize("https://itunes.apple.com/jp/album/get-lucky/id617154241?i=617154366").unwrap();
+        assert_eq!(adapter.provider(), Provider::AppleMusic);
+        assert_eq!(identifier, Identifier::Track("617154366".to_string()));
+
+        let (adapter, identifier) = recognize("https://open.spotify.com/album/4OHNH3sDzIxnmUADXzv2kT").unwrap();
+        assert_eq!(adapter.provider(), Provider::Spotify);
+        assert_eq!(identifier, Identifier::Album("4OHNH3sDzIxnmUADXzv2kT".to_string()));
+
+        let (adapter, identifier) = recognize("spotify:user:spincoaster:playlist:0OoyWSCQah4Jt28xnoJ0SJ").unwrap();
+        assert_eq!(adapter.provider(), Provider::Spotify);
+        assert_eq!(identifier, Identifier::UserPlaylist("spincoaster".to_string(),
+                                                        "0OoyWSCQah4Jt28xnoJ0SJ".to_string()));
+
+        let (adapter, identifier) = recognize("https://api.soundcloud.com/users/1234").unwrap();
+        assert_eq!(adapter.provider(), Provider::SoundCloud);
+        assert_eq!(identifier, Identifier::User("1234".to_string()));
+
+        let (adapter, identifier) = recognize("https://bandcamp.com/EmbeddedPlayer/album=2788904580/size=large/").unwrap();
+        assert_eq!(adapter.provider(), Provider::Bandcamp);
+        assert_eq!(identifier, Identifier::Album("https://bandcamp.com/EmbeddedPlayer/album=2788904580".to_string()));
+
+        let (adapter, identifier) = recognize("https://www.mixcloud.com/widget/iframe/?feed=https://www.mixcloud.com/spartacus/party-time/").unwrap();
+        assert_eq!(adapter.provider(), Provider::Mixcloud);
+        assert_eq!(identifier, Identifier::Track("/spartacus/party-time/".to_string()));
+
+        let (adapter, identifier) = recognize("https://www.deezer.com/en/album/302127").unwrap();
+        assert_eq!(adapter.provider(), Provider::Deezer);
+        assert_eq!(identifier, Identifier::Album("302127".to_string()));
+
+        let (adapter, identifier) = recognize("https://widget.deezer.com/widget/dark/playlist/1479458365").unwrap();
+        assert_eq!(adapter.provider(), Provider::Deezer);
+        assert_eq!(identifier, Identifier::Playlist("1479458365".to_string()));
+
+        let (adapter, identifier) = recognize("http://example.com/podcast/ep01.mp3").unwrap();
+        assert_eq!(adapter.provider(), Provider::Raw);
+        assert_eq!(identifier, Identifier::Track("http://example.com/podcast/ep01.mp3".to_string()));
+
+        assert!(recognize("http://example.com/").is_none());
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find(&Provider::AppleMusic).unwrap().provider(), Provider::AppleMusic);
+        assert_eq!(find(&Provider::Custom).unwrap().provider(), Provider::Custom);
+        assert_eq!(find(&Provider::Raw).unwrap().provider(), Provider::Raw);
+    }
+}
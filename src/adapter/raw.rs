@@ -0,0 +1,35 @@
+use raw;
+use error::Error;
+use model::{Track, Provider, Connection};
+use super::{ProviderAdapter, Identifier, Enclosures};
+
+/// Audio files linked directly, which are identified by their urls.
+pub struct RawAdapter;
+
+impl ProviderAdapter for RawAdapter {
+    fn provider(&self) -> Provider {
+        Provider::Raw
+    }
+
+    fn parse_url(&self, url: &str) -> Option<Identifier> {
+        if raw::is_audio_file_url(url) {
+            Some(Identifier::Track(url.to_string()))
+        } else {
+            None
+        }
+    }
+
+    fn fetch_enclosures(&self, conn: &Connection, _url: &str, identifier: &Identifier) -> Result<Enclosures, Error> {
+        match *identifier {
+            Identifier::Track(ref url) => Ok((vec![], vec![], vec![Track::from_raw_url(conn, url)])),
+            _                          => Ok((vec![], vec![], vec![])),
+        }
+    }
+
+    fn fetch_track(&self, _conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let url = track.audio_url.clone().unwrap_or(track.identifier.clone());
+        let tag = raw::fetch_tag(&url)?;
+        track.update_with_raw_tag(&url, &tag);
+        Ok(())
+    }
+}
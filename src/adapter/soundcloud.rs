@@ -0,0 +1,77 @@
+use soundcloud;
+use error::Error;
+use model::{Track, Playlist, Provider, Connection};
+use super::{ProviderAdapter, Identifier, Enclosures, extract_identifier};
+
+const EXPAND_SOUNDCLOUD_PLAYLIST: bool = true;
+
+pub struct SoundCloudAdapter;
+
+impl ProviderAdapter for SoundCloudAdapter {
+    fn provider(&self) -> Provider {
+        Provider::SoundCloud
+    }
+
+    fn parse_url(&self, url: &str) -> Option<Identifier> {
+        if let Some(identifier) = extract_identifier(url, soundcloud::TRACK) {
+            return Some(Identifier::Track(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, soundcloud::PLAYLIST) {
+            return Some(Identifier::Playlist(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, soundcloud::USER) {
+            return Some(Identifier::User(identifier))
+        }
+        None
+    }
+
+    fn fetch_enclosures(&self, conn: &Connection, _url: &str, identifier: &Identifier) -> Result<Enclosures, Error> {
+        match *identifier {
+            Identifier::Track(ref id)    => soundcloud::fetch_track(id)
+                .map(|track| (vec![], vec![], vec![Track::from_sc_track(conn, &track)])),
+            Identifier::Playlist(ref id) => fetch_playlist(conn, id),
+            Identifier::User(ref id)     => fetch_user(conn, id),
+            _                            => Ok((vec![], vec![], vec![])),
+        }
+    }
+
+    fn pending_enclosures(&self, identifier: &Identifier, err: Error) -> Enclosures {
+        match *identifier {
+            Identifier::Track(ref id) => (vec![], vec![], Track::from_failure(Provider::SoundCloud, id, err).into_iter().collect()),
+            _                         => (vec![], vec![], vec![]),
+        }
+    }
+
+    fn fetch_track(&self, conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let sc_track = soundcloud::fetch_track(&track.identifier)?;
+        track.update_with_sc_track(conn, &sc_track);
+        Ok(())
+    }
+
+    fn fetch_playlist(&self, conn: &Connection, playlist: &mut Playlist) -> Result<(), Error> {
+        let sc_playlist = soundcloud::fetch_playlist(&playlist.identifier)?;
+        playlist.update_with_sc_playlist(conn, &sc_playlist);
+        Ok(())
+    }
+}
+
+fn fetch_playlist(conn: &Connection, identifier: &str) -> Result<Enclosures, Error> {
+    let playlist = soundcloud::fetch_playlist(identifier)?;
+    let tracks = if EXPAND_SOUNDCLOUD_PLAYLIST {
+        playlist.tracks
+            .iter()
+            .map(|ref t| Track::from_sc_track(conn, t))
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+    Ok((vec![Playlist::from_sc_playlist(conn, &playlist)], vec![], tracks))
+}
+
+fn fetch_user(conn: &Connection, identifier: &str) -> Result<Enclosures, Error> {
+    let tracks = soundcloud::fetch_user_tracks(identifier)?
+        .iter()
+        .map(|ref t| Track::from_sc_track(conn, t))
+        .collect::<Vec<_>>();
+    Ok((vec![], vec![], tracks))
+}
@@ -0,0 +1,116 @@
+use spotify;
+use error::Error;
+use model::{Track, Album, Playlist, Artist, PlaylistTrack, Enclosure, Provider, Connection};
+use super::{ProviderAdapter, Identifier, Enclosures, extract_identifier};
+
+pub struct SpotifyAdapter;
+
+impl ProviderAdapter for SpotifyAdapter {
+    fn provider(&self) -> Provider {
+        Provider::Spotify
+    }
+
+    fn parse_url(&self, url: &str) -> Option<Identifier> {
+        for regex in [spotify::TRACK_URI, spotify::TRACK_OPEN, spotify::TRACK_EMBED].iter() {
+            if let Some(identifier) = extract_identifier(url, regex) {
+                return Some(Identifier::Track(identifier))
+            }
+        }
+        if let Some((uid, pid)) = extract_identifier(url, spotify::PLAYLIST_URI).and_then(
+            |uri| spotify::parse_uri_as_playlist(&uri)) {
+            return Some(Identifier::UserPlaylist(uid, pid))
+        }
+        if let Some((uid, pid)) = extract_identifier(url, spotify::PLAYLIST_OPEN).and_then(
+            |url| spotify::parse_open_url_as_playlist(&url)) {
+            return Some(Identifier::UserPlaylist(uid, pid))
+        }
+        if let Some((uid, pid)) = extract_identifier(url, spotify::PLAYLIST_EMBED).and_then(
+            |url| spotify::parse_embed_url_as_playlist(&url)) {
+            return Some(Identifier::UserPlaylist(uid, pid))
+        }
+        for regex in [spotify::ALBUM_URI, spotify::ALBUM_OPEN, spotify::ALBUM_EMBED].iter() {
+            if let Some(identifier) = extract_identifier(url, regex) {
+                return Some(Identifier::Album(identifier))
+            }
+        }
+        None
+    }
+
+    fn fetch_enclosures(&self, conn: &Connection, _url: &str, identifier: &Identifier) -> Result<Enclosures, Error> {
+        match *identifier {
+            Identifier::Track(ref id) => {
+                let track = spotify::fetch_track(id)?;
+                Ok((vec![], vec![], vec![Track::from_sp_track(conn, &track)?]))
+            },
+            Identifier::Album(ref id) => spotify::fetch_album(id)
+                .map(|album| (vec![], vec![Album::from_sp_album(conn, &album)], vec![])),
+            Identifier::UserPlaylist(ref uid, ref pid) => {
+                let mut playlist = Playlist::new(Provider::Spotify, pid.to_string());
+                playlist.set_owner_id(Some(uid.to_string())).fetch_props(conn)?;
+                Ok((vec![playlist], vec![], vec![]))
+            },
+            _ => Ok((vec![], vec![], vec![])),
+        }
+    }
+
+    fn pending_enclosures(&self, identifier: &Identifier, _err: Error) -> Enclosures {
+        match *identifier {
+            Identifier::Track(ref id) => (vec![], vec![], vec![Track::new(Provider::Spotify, id.to_string())]),
+            Identifier::Album(ref id) => (vec![], vec![Album::new(Provider::Spotify, id.to_string())], vec![]),
+            Identifier::UserPlaylist(ref uid, ref pid) => {
+                let mut playlist = Playlist::new(Provider::Spotify, pid.to_string());
+                playlist.set_owner_id(Some(uid.to_string()));
+                (vec![playlist], vec![], vec![])
+            },
+            _ => (vec![], vec![], vec![]),
+        }
+    }
+
+    fn fetch_track(&self, conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let sp_track = spotify::fetch_track(&track.identifier)?;
+        track.update_with_sp_track(conn, &sp_track);
+        Ok(())
+    }
+
+    fn fetch_album(&self, conn: &Connection, album: &mut Album) -> Result<(), Error> {
+        let sp_album = spotify::fetch_album(&album.identifier)?;
+        album.update_with_sp_album(conn, &sp_album);
+        Ok(())
+    }
+
+    fn fetch_playlist(&self, conn: &Connection, playlist: &mut Playlist) -> Result<(), Error> {
+        if let Some(owner_id) = playlist.owner_id.clone() {
+            let sp_playlist = spotify::fetch_playlist(&owner_id, &playlist.identifier)?;
+            playlist.update_with_sp_playlist(conn, &sp_playlist);
+        }
+        Ok(())
+    }
+
+    fn fetch_artist(&self, conn: &Connection, artist: &mut Artist) -> Result<(), Error> {
+        let sp_artist = spotify::fetch_artist(&artist.identifier)?;
+        artist.update_with_sp_artist(conn, &sp_artist);
+        Ok(())
+    }
+
+    fn fetch_playlist_tracks(&self, conn: &Connection, playlist: &mut Playlist) -> Result<Vec<PlaylistTrack>, Error> {
+        let mut items = vec![];
+        let owner_id = playlist.owner_id.clone().ok_or(Error::Unexpected)?;
+
+        let mut page = spotify::fetch_playlist_tracks(&owner_id, &playlist.identifier)?;
+        items.append(&mut playlist.add_tracks(conn, page_tracks(conn, &page)));
+        while page.next.is_some() {
+            page = page.fetch_next()?;
+            items.append(&mut playlist.add_tracks(conn, page_tracks(conn, &page)));
+        }
+        Ok(items)
+    }
+}
+
+fn page_tracks(conn: &Connection, page: &spotify::PagingObject<spotify::PlaylistTrack>) -> Vec<Track> {
+    page.items.iter()
+        .filter(|pt| pt.track.is_some())
+        .map(|pt| Track::from_sp_track(conn, &pt.track.clone().unwrap()))
+        .filter(|ref r| r.is_ok())
+        .map(|r| r.unwrap())
+        .collect()
+}
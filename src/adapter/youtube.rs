@@ -0,0 +1,89 @@
+use youtube;
+use error::Error;
+use model::{Track, Playlist, Artist, Provider, Connection};
+use super::{ProviderAdapter, Identifier, Enclosures, extract_identifier};
+
+const EXPAND_YOUTUBE_PLAYLIST: bool = true;
+
+pub struct YouTubeAdapter;
+
+impl ProviderAdapter for YouTubeAdapter {
+    fn provider(&self) -> Provider {
+        Provider::YouTube
+    }
+
+    fn parse_url(&self, url: &str) -> Option<Identifier> {
+        if let Some(identifier) = extract_identifier(url, youtube::WATCH) {
+            return Some(Identifier::Track(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, youtube::SHORT) {
+            return Some(Identifier::Track(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, youtube::LIST) {
+            return Some(Identifier::Playlist(identifier))
+        }
+        if let Some(identifier) = extract_identifier(url, youtube::EMBED) {
+            return Some(Identifier::Track(identifier))
+        }
+        None
+    }
+
+    fn fetch_enclosures(&self, conn: &Connection, _url: &str, identifier: &Identifier) -> Result<Enclosures, Error> {
+        match *identifier {
+            Identifier::Track(ref id)    => youtube::fetch_video(id)
+                .map(|video| (vec![], vec![], vec![Track::from_yt_video(conn, &video)])),
+            Identifier::Playlist(ref id) => fetch_playlist(conn, id),
+            _                            => Ok((vec![], vec![], vec![])),
+        }
+    }
+
+    fn pending_enclosures(&self, identifier: &Identifier, err: Error) -> Enclosures {
+        match *identifier {
+            Identifier::Track(ref id) => (vec![], vec![], Track::from_failure(Provider::YouTube, id, err).into_iter().collect()),
+            _                         => (vec![], vec![], vec![]),
+        }
+    }
+
+    fn fetch_track(&self, conn: &Connection, track: &mut Track) -> Result<(), Error> {
+        let video = youtube::fetch_video(&track.identifier)?;
+        track.update_with_yt_video(conn, &video);
+        Ok(())
+    }
+
+    fn fetch_playlist(&self, conn: &Connection, playlist: &mut Playlist) -> Result<(), Error> {
+        let items = youtube::fetch_playlist_items(&playlist.identifier)
+            .map(|res| res.items)
+            .unwrap_or(vec![]);
+        let res = youtube::fetch_playlist(&playlist.identifier)?;
+        match res.items.iter().nth(0) {
+            Some(yt_playlist) => {
+                playlist.update_with_yt_playlist(conn, yt_playlist, &items);
+                Ok(())
+            },
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn fetch_artist(&self, _conn: &Connection, artist: &mut Artist) -> Result<(), Error> {
+        let channel = youtube::fetch_channel(&artist.identifier)?;
+        artist.update_with_yt_channel(&channel);
+        Ok(())
+    }
+}
+
+fn fetch_playlist(conn: &Connection, id: &str) -> Result<Enclosures, Error> {
+    let items = youtube::fetch_playlist_items(id)
+        .map(|res| res.items)
+        .unwrap_or(vec![]);
+    let tracks = if EXPAND_YOUTUBE_PLAYLIST {
+        items.iter()
+            .map(|ref i| Track::from_yt_playlist_item(conn, i))
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+    let playlists = youtube::fetch_playlist(id)?.items.iter()
+        .map(|ref i| Playlist::from_yt_playlist(conn, i, &items))
+        .collect::<Vec<_>>();
+    Ok((playlists, vec![], tracks))
+}
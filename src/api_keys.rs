@@ -0,0 +1,52 @@
+extern crate pink_spider;
+
+use std::env;
+use std::process;
+use pink_spider::model::{ApiKey, Scope, conn};
+
+static USAGE: &'static str = "Usage: api_keys issue NAME [read|write|admin]
+       api_keys revoke PREFIX_OR_ID
+       api_keys list";
+
+pub fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = args.get(0).map(|s| s.as_str()).unwrap_or("");
+    let conn    = conn().unwrap_or_else(|e| exit(&format!("Failed to connect: {:?}", e)));
+    match command {
+        "issue" => {
+            let name  = args.get(1).unwrap_or_else(|| exit(USAGE));
+            let scope = args.get(2)
+                .map(|v| Scope::parse(v).unwrap_or_else(|| exit(USAGE)))
+                .unwrap_or(Scope::Read);
+            match ApiKey::issue(&*conn, name, scope) {
+                Ok((api_key, key)) => {
+                    println!("Issued {} key {} for {}", api_key.scope, api_key.prefix, api_key.name);
+                    println!("{}", key);
+                    println!("The key can't be shown again, so keep it now.");
+                },
+                Err(e) => exit(&format!("Failed to issue a key: {:?}", e)),
+            }
+        },
+        "revoke" => {
+            let target = args.get(1).unwrap_or_else(|| exit(USAGE));
+            match ApiKey::revoke(&*conn, target) {
+                Ok(0)     => exit(&format!("No key to revoke: {}", target)),
+                Ok(count) => println!("Revoked {} key(s)", count),
+                Err(e)    => exit(&format!("Failed to revoke: {:?}", e)),
+            }
+        },
+        "list" => match ApiKey::find_all(&*conn) {
+            Ok(api_keys) => for k in api_keys {
+                let state = if k.revoked_at.is_some() { "revoked" } else { "active" };
+                println!("{} {:5} {:7} {} {}", k.prefix, k.scope.to_string(), state, k.created_at, k.name);
+            },
+            Err(e) => exit(&format!("Failed to list keys: {:?}", e)),
+        },
+        _ => exit(USAGE),
+    }
+}
+
+fn exit(message: &str) -> ! {
+    println!("{}", message);
+    process::exit(1)
+}
@@ -4,21 +4,23 @@ use reqwest::header::{
     Bearer,
     Connection,
 };
-use std::io::Read;
 use regex::Regex;
-use serde_json;
+use serde::de::DeserializeOwned;
 use get_env;
 use url::Url;
 use queryst::parse;
 use http;
+use error::{Error, ProviderError};
+use model::{Provider, State};
 
 static BASE_URL:  &'static str = "https://api.music.apple.com/v1";
 static THUMBNAIL_SIZE: &'static str = "300";
 static ARTWORK_SIZE: &'static str = "640";
+static ROOT_GENRE:   &'static str = "Music";
 
 
-static ALBUM_LINK:    &'static str = r"itunes.apple.com/([a-zA-Z0-9_-]+)/album/([a-zA-Z0-9_-]+)/id([a-zA-Z0-9_-]+)";
-static PLAYLIST_LINK: &'static str = r"itunes.apple.com/([a-zA-Z0-9_-]+)/playlist/([^/]+)/idpl.([a-zA-Z0-9_-]+)";
+static ALBUM_LINK:    &'static str = r"(?:itunes|music).apple.com/([a-zA-Z0-9_-]+)/album/([^/]+)/(?:id)?([0-9]+)";
+static PLAYLIST_LINK: &'static str = r"(?:itunes|music).apple.com/([a-zA-Z0-9_-]+)/playlist/([^/]+)/(?:id)?pl.([a-zA-Z0-9_-]+)";
 
 pub static SONG_URL:      &'static str = r"tools.applemusic.com/embed/v1/song/([a-zA-Z0-9_-]+)";
 pub static ALBUM_URL:     &'static str = r"tools.applemusic.com/embed/v1/album/([a-zA-Z0-9_-]+)";
@@ -148,6 +150,16 @@ pub struct AlbumAttributes {
     pub url:             String,
 }
 
+impl AlbumAttributes {
+    /// Apple Music omits the play parameters of the items that can't be played.
+    pub fn state(&self) -> State {
+        match self.play_params {
+            Some(_) => State::Alive,
+            None    => State::Unavailable,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AlbumRelations {
     pub artists: Relationship<ArtistOfAlbum>,
@@ -235,6 +247,15 @@ pub struct SongAttributes {
     pub work_name:          Option<String>,
 }
 
+impl SongAttributes {
+    pub fn state(&self) -> State {
+        match self.play_params {
+            Some(_) => State::Alive,
+            None    => State::Unavailable,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SongRelations {
     pub albums:  Relationship<PartialResource>,
@@ -351,6 +372,12 @@ pub fn country(url: &str) -> String {
     }
 }
 
+/// Apple Music puts "Music" on every item as the root genre,
+/// so it is dropped from the genres.
+pub fn genre_names(names: &Vec<String>) -> Vec<String> {
+    names.iter().filter(|n| n.as_str() != ROOT_GENRE).map(|n| n.to_string()).collect()
+}
+
 pub fn parse_url_as_album(value: &str) -> Option<(String, String, String, Option<String>)> {
     parse_url(&value, ALBUM_LINK)
 }
@@ -374,76 +401,70 @@ pub fn parse_url(value: &str, regex_str: &str) -> Option<(String, String, String
     }
 }
 
-fn fetch(path: &str) -> serde_json::Result<String> {
+fn fetch<T: DeserializeOwned>(path: &str) -> Result<T, Error> {
     let token  = DEVELOPER_TOKEN.to_string();
     let url    = format!("{}{}", BASE_URL, path);
     let mut headers = Headers::new();
     headers.set(Authorization(Bearer { token: token }));
     headers.set(Connection::close());
-    let mut res = http::client().get(&url)
-                                .headers(headers)
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    Ok(body)
+    http::get(&url)
+        .headers(headers)
+        .fetch_json(Provider::AppleMusic)
+}
+
+fn first<T: Clone>(res: Response<T>) -> Result<T, Error> {
+    res.data.first().cloned().ok_or(Error::Provider(Provider::AppleMusic, ProviderError::NotFound))
 }
 
-pub fn fetch_song(country: &str, id: &str) -> serde_json::Result<Song> {
+pub fn fetch_song(country: &str, id: &str) -> Result<Song, Error> {
     let params = "include=artists";
     let path = format!("/catalog/{}/songs/{}?{}", country, id, params);
-    let result: serde_json::Result<Response<Song>> = fetch(&path).and_then(|s| serde_json::from_str(&s));
-    result.map(|r| r.data.first().unwrap().clone())
+    fetch::<Response<Song>>(&path).and_then(first)
 }
 
-pub fn fetch_songs(country: &str, ids: Vec<String>) -> serde_json::Result<Vec<Song>> {
+pub fn fetch_songs(country: &str, ids: Vec<String>) -> Result<Vec<Song>, Error> {
     let params = "include=artists";
     let path = format!("/catalog/{}/songs?ids={}&{}", country, ids.join(","), params);
-    let result: serde_json::Result<Response<Song>> = fetch(&path).and_then(|s| serde_json::from_str(&s));
-    result.map(|r| r.data)
+    fetch::<Response<Song>>(&path).map(|r| r.data)
 }
 
-pub fn fetch_album(country: &str, id: &str) -> serde_json::Result<Album> {
+pub fn fetch_album(country: &str, id: &str) -> Result<Album, Error> {
     let params = "include=artists";
     let path = format!("/catalog/{}/albums/{}?{}", country, id, params);
-    let result: serde_json::Result<Response<Album>> = fetch(&path).and_then(|s| serde_json::from_str(&s));
-    result.map(|r| r.data.first().unwrap().clone())
+    fetch::<Response<Album>>(&path).and_then(first)
 }
 
-pub fn fetch_albums(country: &str, ids: Vec<String>) -> serde_json::Result<Vec<Album>> {
+pub fn fetch_albums(country: &str, ids: Vec<String>) -> Result<Vec<Album>, Error> {
     let params = "include=artists";
     let path = format!("/catalog/{}/albums?ids={}&{}", country, ids.join(","), params);
-    let result: serde_json::Result<Response<Album>> = fetch(&path).and_then(|s| serde_json::from_str(&s));
-    result.map(|r| r.data)
+    fetch::<Response<Album>>(&path).map(|r| r.data)
 }
 
-pub fn fetch_playlist(country: &str, id: &str) -> serde_json::Result<Playlist> {
+pub fn fetch_playlist(country: &str, id: &str) -> Result<Playlist, Error> {
     let params = "include=tracks";
     let path = format!("/catalog/{}/playlists/{}?{}", country, id, params);
-    let result: serde_json::Result<Response<Playlist>> = fetch(&path).and_then(|s| serde_json::from_str(&s));
-    result.map(|r| r.data.first().unwrap().clone())
+    fetch::<Response<Playlist>>(&path).and_then(first)
 }
 
-pub fn fetch_artist(country: &str, id: &str) -> serde_json::Result<Artist> {
+pub fn fetch_artist(country: &str, id: &str) -> Result<Artist, Error> {
     let params = "include=albums";
     let path = format!("/catalog/{}/artists/{}?{}", country, id, params);
-    let result: serde_json::Result<Response<Artist>> = fetch(&path).and_then(|s| serde_json::from_str(&s));
-    result.map(|r| r.data.first().unwrap().clone())
+    fetch::<Response<Artist>>(&path).and_then(first)
 }
 
-pub fn fetch_artists(country: &str, ids: Vec<String>) -> serde_json::Result<Vec<Artist>> {
+pub fn fetch_artists(country: &str, ids: Vec<String>) -> Result<Vec<Artist>, Error> {
     let params = "include=albums";
     let path = format!("/catalog/{}/artists?ids={}&{}", country, ids.join(","), params);
-    let result: serde_json::Result<Response<Artist>> = fetch(&path).and_then(|s| serde_json::from_str(&s));
-    result.map(|r| r.data)
+    fetch::<Response<Artist>>(&path).map(|r| r.data)
 }
 
-pub fn search_artists(country: &str, term: &str) -> serde_json::Result<Vec<Artist>> {
+pub fn search_artists(country: &str, term: &str) -> Result<Vec<Artist>, Error> {
     search(country, term, None, None, Some(vec!["artists"])).map(|res| {
         res.results.artists.map(|a| a.data).unwrap_or(vec![])
     })
 }
 
-pub fn search(country: &str, term: &str, limit: Option<i32>, offset: Option<i32>, types: Option<Vec<&str>>) -> serde_json::Result<SearchResponse> {
+pub fn search(country: &str, term: &str, limit: Option<i32>, offset: Option<i32>, types: Option<Vec<&str>>) -> Result<SearchResponse, Error> {
     let mut params = format!("term={}", term.replace(" ", "+"));
     if let Some(limit) = limit {
         params += &format!("&limit={}", limit);
@@ -455,7 +476,7 @@ pub fn search(country: &str, term: &str, limit: Option<i32>, offset: Option<i32>
         params += &format!("&types={}", types.join(","));
     }
     let path = format!("/catalog/{}/search?{}", &country, &params);
-    fetch(&path).and_then(|s| serde_json::from_str(&s))
+    fetch(&path)
 }
 
 #[cfg(test)]
@@ -0,0 +1,457 @@
+//! A minimal reader for the metadata of audio files.
+//!
+//! It understands ID3v2 (mp3), Vorbis comments (ogg and flac) and
+//! iTunes style atoms (mp4/m4a), and only needs the head of a file,
+//! so it works on a ranged download.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Tag {
+    pub title:    Option<String>,
+    pub artist:   Option<String>,
+    pub album:    Option<String>,
+    pub duration: Option<i32>,
+}
+
+const MPEG_BITRATES_V1_L3: [u32; 16] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0];
+const MPEG_BITRATES_V2_L3: [u32; 16] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0];
+const MPEG_SAMPLE_RATES:   [u32; 3]  = [44100, 48000, 32000];
+
+/// This function parses the head of an audio file.
+/// `total_len` is the size of the whole file, which is used to
+/// estimate the duration of mp3 files without a length frame.
+pub fn parse(bytes: &[u8], total_len: Option<u64>) -> Option<Tag> {
+    if bytes.starts_with(b"ID3") || is_mpeg_frame(bytes) {
+        parse_mp3(bytes, total_len)
+    } else if bytes.starts_with(b"fLaC") {
+        parse_flac(bytes)
+    } else if bytes.starts_with(b"OggS") {
+        parse_ogg(bytes, total_len)
+    } else if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+        parse_mp4(bytes)
+    } else {
+        None
+    }
+}
+
+fn read_u24_be(b: &[u8]) -> u32 {
+    (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32
+}
+
+fn read_u32_be(b: &[u8]) -> u32 {
+    (b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32
+}
+
+fn read_u64_be(b: &[u8]) -> u64 {
+    (read_u32_be(&b[0..4]) as u64) << 32 | read_u32_be(&b[4..8]) as u64
+}
+
+fn read_u32_le(b: &[u8]) -> u32 {
+    (b[3] as u32) << 24 | (b[2] as u32) << 16 | (b[1] as u32) << 8 | b[0] as u32
+}
+
+fn read_u64_le(b: &[u8]) -> u64 {
+    (read_u32_le(&b[4..8]) as u64) << 32 | read_u32_le(&b[0..4]) as u64
+}
+
+fn read_synchsafe(b: &[u8]) -> u32 {
+    (b[0] as u32 & 0x7f) << 21 | (b[1] as u32 & 0x7f) << 14 | (b[2] as u32 & 0x7f) << 7 | b[3] as u32 & 0x7f
+}
+
+fn non_empty(s: String) -> Option<String> {
+    let s = s.trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string();
+    if s.is_empty() { None } else { Some(s) }
+}
+
+// mp3
+
+fn parse_mp3(bytes: &[u8], total_len: Option<u64>) -> Option<Tag> {
+    let mut tag = Tag::default();
+    let mut offset = 0;
+    if bytes.starts_with(b"ID3") && bytes.len() >= 10 {
+        let version = bytes[3];
+        let flags   = bytes[5];
+        let size    = read_synchsafe(&bytes[6..10]) as usize;
+        let end     = ::std::cmp::min(10 + size, bytes.len());
+        let mut pos = 10;
+        if flags & 0x40 != 0 && version >= 3 && pos + 4 <= end {
+            let ext_size = if version == 4 {
+                read_synchsafe(&bytes[pos..pos + 4]) as usize
+            } else {
+                read_u32_be(&bytes[pos..pos + 4]) as usize + 4
+            };
+            pos += ext_size;
+        }
+        parse_id3_frames(&bytes[pos.min(end)..end], version, &mut tag);
+        offset = 10 + size;
+    }
+    if tag.duration.is_none() && offset < bytes.len() {
+        let audio_len = total_len.map(|len| len.saturating_sub(offset as u64));
+        tag.duration = mpeg_duration(&bytes[offset..], audio_len);
+    }
+    Some(tag)
+}
+
+fn parse_id3_frames(bytes: &[u8], version: u8, tag: &mut Tag) {
+    let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+    let mut pos = 0;
+    while pos + header_len <= bytes.len() {
+        let id = &bytes[pos..pos + id_len];
+        if id[0] == 0 {
+            break;
+        }
+        let size = match version {
+            2 => read_u24_be(&bytes[pos + 3..pos + 6]) as usize,
+            4 => read_synchsafe(&bytes[pos + 4..pos + 8]) as usize,
+            _ => read_u32_be(&bytes[pos + 4..pos + 8]) as usize,
+        };
+        let start = pos + header_len;
+        let end   = start + size;
+        if end > bytes.len() {
+            break;
+        }
+        let text = || decode_id3_text(&bytes[start..end]);
+        match id {
+            b"TIT2" | b"TT2" => tag.title  = text(),
+            b"TPE1" | b"TP1" => tag.artist = text(),
+            b"TALB" | b"TAL" => tag.album  = text(),
+            b"TLEN" | b"TLE" => tag.duration = text()
+                .and_then(|ms| ms.parse::<i64>().ok())
+                .map(|ms| (ms / 1000) as i32),
+            _ => (),
+        }
+        pos = end;
+    }
+}
+
+fn decode_id3_text(data: &[u8]) -> Option<String> {
+    if data.is_empty() {
+        return None
+    }
+    let body = &data[1..];
+    let text = match data[0] {
+        1 | 2 => {
+            let (big_endian, body) = if body.starts_with(&[0xfe, 0xff]) {
+                (true, &body[2..])
+            } else if body.starts_with(&[0xff, 0xfe]) {
+                (false, &body[2..])
+            } else {
+                (data[0] == 2, body)
+            };
+            let units = body.chunks(2).filter(|c| c.len() == 2).map(|c| if big_endian {
+                (c[0] as u16) << 8 | c[1] as u16
+            } else {
+                (c[1] as u16) << 8 | c[0] as u16
+            }).collect::<Vec<u16>>();
+            String::from_utf16_lossy(&units)
+        },
+        3 => String::from_utf8_lossy(body).into_owned(),
+        _ => body.iter().map(|&b| b as char).collect(),
+    };
+    // Multiple values are separated by null, and only the first one is used.
+    non_empty(text.split('\0').next().unwrap_or("").to_string())
+}
+
+fn is_mpeg_frame(bytes: &[u8]) -> bool {
+    bytes.len() >= 4 && bytes[0] == 0xff && bytes[1] & 0xe0 == 0xe0
+}
+
+fn mpeg_duration(bytes: &[u8], audio_len: Option<u64>) -> Option<i32> {
+    let pos = (0..bytes.len().saturating_sub(4)).find(|&i| is_mpeg_frame(&bytes[i..]))?;
+    let header   = &bytes[pos..];
+    let version  = (header[1] >> 3) & 0x03; // 3: MPEG1, 2: MPEG2, 0: MPEG2.5
+    let layer    = (header[1] >> 1) & 0x03; // 1: Layer III
+    let bitrate  = if version == 3 {
+        MPEG_BITRATES_V1_L3[(header[2] >> 4) as usize]
+    } else {
+        MPEG_BITRATES_V2_L3[(header[2] >> 4) as usize]
+    };
+    let rate_index = ((header[2] >> 2) & 0x03) as usize;
+    if layer != 1 || version == 1 || bitrate == 0 || rate_index > 2 {
+        return None
+    }
+    let sample_rate = MPEG_SAMPLE_RATES[rate_index] >> match version { 3 => 0, 2 => 1, _ => 2 };
+    let samples_per_frame = if version == 3 { 1152 } else { 576 };
+    let mono = header[3] >> 6 == 3;
+    let side_info = match (version == 3, mono) {
+        (true, false)  => 32,
+        (true, true)   => 17,
+        (false, false) => 17,
+        (false, true)  => 9,
+    };
+    // A Xing/Info header in the first frame has the number of frames of a vbr file.
+    let xing = pos + 4 + side_info;
+    if xing + 12 <= bytes.len() &&
+        (&bytes[xing..xing + 4] == b"Xing" || &bytes[xing..xing + 4] == b"Info") &&
+        read_u32_be(&bytes[xing + 4..xing + 8]) & 0x01 != 0 {
+        let frames = read_u32_be(&bytes[xing + 8..xing + 12]) as u64;
+        return Some((frames * samples_per_frame / sample_rate as u64) as i32)
+    }
+    audio_len.map(|len| (len * 8 / (bitrate as u64 * 1000)) as i32)
+}
+
+// flac
+
+fn parse_flac(bytes: &[u8]) -> Option<Tag> {
+    let mut tag = Tag::default();
+    let mut pos = 4;
+    while pos + 4 <= bytes.len() {
+        let is_last    = bytes[pos] & 0x80 != 0;
+        let block_type = bytes[pos] & 0x7f;
+        let size       = read_u24_be(&bytes[pos + 1..pos + 4]) as usize;
+        let start      = pos + 4;
+        let end        = start + size;
+        if end > bytes.len() {
+            break;
+        }
+        let block = &bytes[start..end];
+        match block_type {
+            0 if block.len() >= 18 => {
+                let sample_rate   = read_u24_be(&block[10..13]) >> 4;
+                let total_samples = (block[13] as u64 & 0x0f) << 32 | read_u32_be(&block[14..18]) as u64;
+                if sample_rate > 0 && total_samples > 0 {
+                    tag.duration = Some((total_samples / sample_rate as u64) as i32);
+                }
+            },
+            4 => parse_vorbis_comment(block, &mut tag),
+            _ => (),
+        }
+        if is_last {
+            break;
+        }
+        pos = end;
+    }
+    Some(tag)
+}
+
+fn parse_vorbis_comment(block: &[u8], tag: &mut Tag) {
+    if block.len() < 4 {
+        return;
+    }
+    let vendor_len = read_u32_le(&block[0..4]) as usize;
+    let mut pos = 4 + vendor_len;
+    if pos + 4 > block.len() {
+        return;
+    }
+    let count = read_u32_le(&block[pos..pos + 4]);
+    pos += 4;
+    for _ in 0..count {
+        if pos + 4 > block.len() {
+            break;
+        }
+        let len = read_u32_le(&block[pos..pos + 4]) as usize;
+        pos += 4;
+        if pos + len > block.len() {
+            break;
+        }
+        let comment = String::from_utf8_lossy(&block[pos..pos + len]).into_owned();
+        pos += len;
+        let mut kv = comment.splitn(2, '=');
+        let key    = kv.next().unwrap_or("").to_uppercase();
+        let value  = non_empty(kv.next().unwrap_or("").to_string());
+        match key.as_ref() {
+            "TITLE"  if tag.title.is_none()  => tag.title  = value,
+            "ARTIST" if tag.artist.is_none() => tag.artist = value,
+            "ALBUM"  if tag.album.is_none()  => tag.album  = value,
+            _ => (),
+        }
+    }
+}
+
+// ogg
+
+fn ogg_packets(bytes: &[u8], max: usize) -> Vec<Vec<u8>> {
+    let mut packets = vec![];
+    let mut packet  = vec![];
+    let mut pos     = 0;
+    while pos + 27 <= bytes.len() && &bytes[pos..pos + 4] == b"OggS" && packets.len() < max {
+        let segments = bytes[pos + 26] as usize;
+        if pos + 27 + segments > bytes.len() {
+            break;
+        }
+        let lacing = &bytes[pos + 27..pos + 27 + segments];
+        let mut data = pos + 27 + segments;
+        for &len in lacing {
+            let len = len as usize;
+            if data + len > bytes.len() {
+                return packets;
+            }
+            packet.extend_from_slice(&bytes[data..data + len]);
+            data += len;
+            if len < 255 {
+                packets.push(packet);
+                packet = vec![];
+            }
+        }
+        pos = data;
+    }
+    packets
+}
+
+fn parse_ogg(bytes: &[u8], total_len: Option<u64>) -> Option<Tag> {
+    let mut tag = Tag::default();
+    let packets = ogg_packets(bytes, 2);
+    let mut sample_rate = None;
+    for packet in packets.iter() {
+        if packet.starts_with(b"\x01vorbis") && packet.len() >= 16 {
+            sample_rate = Some(read_u32_le(&packet[12..16]) as u64);
+        } else if packet.starts_with(b"\x03vorbis") {
+            parse_vorbis_comment(&packet[7..], &mut tag);
+        } else if packet.starts_with(b"OpusHead") {
+            sample_rate = Some(48000);
+        } else if packet.starts_with(b"OpusTags") {
+            parse_vorbis_comment(&packet[8..], &mut tag);
+        }
+    }
+    // The granule position of the last page is the total number of samples,
+    // so the duration is known only when the whole file has been downloaded.
+    if total_len == Some(bytes.len() as u64) {
+        let last_page = (0..bytes.len().saturating_sub(14)).rev()
+            .find(|&i| &bytes[i..i + 4] == b"OggS");
+        if let (Some(i), Some(rate)) = (last_page, sample_rate) {
+            let granule = read_u64_le(&bytes[i + 6..i + 14]);
+            if rate > 0 {
+                tag.duration = Some((granule / rate) as i32);
+            }
+        }
+    }
+    Some(tag)
+}
+
+// mp4
+
+fn mp4_boxes(bytes: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut boxes = vec![];
+    let mut pos   = 0;
+    while pos + 8 <= bytes.len() {
+        let size = read_u32_be(&bytes[pos..pos + 4]) as usize;
+        let name = &bytes[pos + 4..pos + 8];
+        let (header, size) = match size {
+            0 => (8, bytes.len() - pos),
+            1 if pos + 16 <= bytes.len() => (16, read_u64_be(&bytes[pos + 8..pos + 16]) as usize),
+            _ => (8, size),
+        };
+        // the sizes come from the file, so a 64-bit size can overflow
+        let end = match pos.checked_add(size) {
+            Some(end) if size >= header && end <= bytes.len() => end,
+            _                                                  => break,
+        };
+        boxes.push((name, &bytes[pos + header..end]));
+        pos = end;
+    }
+    boxes
+}
+
+/// `path` is the names of the nested boxes joined, e.g. `b"moovmvhd"`.
+fn find_box<'a>(bytes: &'a [u8], path: &[u8]) -> Option<&'a [u8]> {
+    if path.len() < 4 {
+        return None
+    }
+    let (name, rest) = path.split_at(4);
+    let body = mp4_boxes(bytes).into_iter().find(|&(n, _)| n == name).map(|(_, b)| b)?;
+    if rest.is_empty() {
+        Some(body)
+    } else if name == b"meta" && body.len() >= 4 {
+        // meta is a full box, which has 4 bytes of version and flags
+        find_box(&body[4..], rest)
+    } else {
+        find_box(body, rest)
+    }
+}
+
+fn mp4_text(ilst: &[u8], name: &[u8]) -> Option<String> {
+    let mut path = name.to_vec();
+    path.extend_from_slice(b"data");
+    let data = find_box(ilst, &path)?;
+    if data.len() < 8 {
+        return None
+    }
+    non_empty(String::from_utf8_lossy(&data[8..]).into_owned())
+}
+
+fn parse_mp4(bytes: &[u8]) -> Option<Tag> {
+    let mut tag = Tag::default();
+    if let Some(mvhd) = find_box(bytes, b"moovmvhd") {
+        let (timescale, duration) = if mvhd.len() >= 32 && mvhd[0] == 1 {
+            (read_u32_be(&mvhd[20..24]) as u64, read_u64_be(&mvhd[24..32]))
+        } else if mvhd.len() >= 20 {
+            (read_u32_be(&mvhd[12..16]) as u64, read_u32_be(&mvhd[16..20]) as u64)
+        } else {
+            (0, 0)
+        };
+        if timescale > 0 {
+            tag.duration = Some((duration / timescale) as i32);
+        }
+    }
+    if let Some(ilst) = find_box(bytes, b"moovudtametailst") {
+        tag.title  = mp4_text(ilst, b"\xa9nam");
+        tag.artist = mp4_text(ilst, b"\xa9ART");
+        tag.album  = mp4_text(ilst, b"\xa9alb");
+    }
+    Some(tag)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Read;
+    use super::{parse, mp4_boxes, Tag};
+
+    fn expected(duration: i32) -> Tag {
+        Tag {
+            title:    Some("Morning Tide".to_string()),
+            artist:   Some("Rivka".to_string()),
+            album:    Some("First Light".to_string()),
+            duration: Some(duration),
+        }
+    }
+
+    #[test]
+    fn test_parse_id3v2() {
+        let mut bytes = vec![];
+        File::open("fixture/audio/id3v23.mp3").unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(parse(&bytes, Some(bytes.len() as u64)), Some(expected(214)));
+        let mut bytes = vec![];
+        File::open("fixture/audio/id3v24_utf16.mp3").unwrap().read_to_end(&mut bytes).unwrap();
+        let tag = parse(&bytes, Some(bytes.len() as u64)).unwrap();
+        assert_eq!(tag.title, Some("朝の潮".to_string()));
+        assert_eq!(tag.artist, Some("Rivka".to_string()));
+        // no TLEN frame, so the duration is read from the Xing header
+        assert_eq!(tag.duration, Some(180));
+    }
+
+    #[test]
+    fn test_parse_flac() {
+        let mut bytes = vec![];
+        File::open("fixture/audio/vorbis.flac").unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(parse(&bytes, None), Some(expected(214)));
+    }
+
+    #[test]
+    fn test_mp4_boxes_with_broken_size() {
+        let mut bytes = vec![0, 0, 0, 1];
+        bytes.extend_from_slice(b"moov");
+        bytes.extend_from_slice(&[0xff; 8]);
+        assert!(mp4_boxes(&bytes).is_empty());
+    }
+
+    #[test]
+    fn test_parse_ogg() {
+        let mut bytes = vec![];
+        File::open("fixture/audio/vorbis.ogg").unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(parse(&bytes, Some(bytes.len() as u64)), Some(expected(214)));
+        let head = &bytes[..bytes.len() - 10];
+        assert_eq!(parse(head, Some(bytes.len() as u64)).unwrap().duration, None);
+    }
+
+    #[test]
+    fn test_parse_mp4() {
+        let mut bytes = vec![];
+        File::open("fixture/audio/itunes.m4a").unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(parse(&bytes, None), Some(expected(214)));
+    }
+
+    #[test]
+    fn test_parse_unknown() {
+        assert_eq!(parse(b"<html></html>", None), None);
+    }
+}
@@ -0,0 +1,136 @@
+use iron::prelude::*;
+use iron::method::Method;
+use iron::typemap::Key;
+use iron::BeforeMiddleware;
+use error::Error;
+use model::{ApiKey, Scope, conn};
+use url::form_urlencoded;
+use get_env;
+
+/// The api key of a request, that `Authenticator` puts in the extensions of the request.
+pub struct CurrentApiKey;
+
+impl Key for CurrentApiKey {
+    type Value = ApiKey;
+}
+
+/// A middleware that checks the api keys of the requests.
+///
+/// A key is given with `Authorization: Bearer <key>` or `X-Api-Key: <key>`.
+/// The requests that only read items don't need keys if `public_reads` is true,
+/// but the keys are still checked if they are given.
+pub struct Authenticator {
+    pub public_reads: bool,
+}
+
+impl Authenticator {
+    /// `PUBLIC_READS=false` makes the reads need keys too.
+    pub fn from_env() -> Authenticator {
+        let public_reads = get_env::var("PUBLIC_READS")
+            .map(|v| v.trim() != "false")
+            .unwrap_or(true);
+        Authenticator { public_reads: public_reads }
+    }
+
+    fn authenticate(&self, req: &mut Request) -> Result<(), Error> {
+        let scope = {
+            let path  = req.url.path();
+            let force = req.url.query().map(|q| has_force(q)).unwrap_or(false);
+            match required_scope(&req.method, &path, force) {
+                Some(scope) => scope,
+                None        => return Ok(()),
+            }
+        };
+        let api_key = match request_key(req) {
+            Some(key) => ApiKey::find_by_key(&*conn()?, &key).map_err(|e| {
+                if e.is_not_found() { Error::Unauthorized } else { e }
+            })?,
+            None if self.public_reads && scope == Scope::Read => return Ok(()),
+            None => return Err(Error::Unauthorized),
+        };
+        if !api_key.scope.allows(scope) {
+            return Err(Error::Forbidden);
+        }
+        req.extensions.insert::<CurrentApiKey>(api_key);
+        Ok(())
+    }
+}
+
+impl BeforeMiddleware for Authenticator {
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        self.authenticate(req).map_err(IronError::from)
+    }
+}
+
+fn request_key(req: &Request) -> Option<String> {
+    let header = |name: &str| req.headers.get_raw(name)
+        .and_then(|values| values.first())
+        .and_then(|value| String::from_utf8(value.clone()).ok())
+        .map(|value| value.trim().to_string());
+    let key = match header("Authorization") {
+        Some(ref value) if value.starts_with("Bearer ") => value["Bearer ".len()..].trim().to_string(),
+        _                                              => header("X-Api-Key").unwrap_or_default(),
+    };
+    if key.is_empty() { None } else { Some(key) }
+}
+
+/// `playlistify?force=true` scrapes the page again even if the entry exists.
+/// The query is decoded as the handlers decode it, so `force=%74rue` is also forced.
+pub fn has_force(query: &str) -> bool {
+    form_urlencoded::parse(query.as_bytes()).any(|(k, v)| k == "force" && v == "true")
+}
+
+/// This function tells the scope that a request needs, or None if the request is public.
+///
+/// - reads, including `.mget`, need `Read`
+/// - `playlistify?force=true`, `resolve`, creating and updating items need `Write`, because they call the apis of the providers
+/// - merging artists and reviving items need `Admin`
+///
+/// The web pages and their assets are public.
+pub fn required_scope(method: &Method, path: &[&str], force: bool) -> Option<Scope> {
+    let is_api = path.first().map(|p| *p == "v1" || *p == "playlistify").unwrap_or(false);
+    if !is_api {
+        return None;
+    }
+    let last = path.last().map(|p| *p).unwrap_or("");
+    match *method {
+        Method::Get if last == "playlistify" && force => Some(Scope::Write),
+        Method::Get if last == "resolve"              => Some(Scope::Write),
+        Method::Get | Method::Head                    => Some(Scope::Read),
+        Method::Post if last == ".mget"               => Some(Scope::Read),
+        Method::Post => match last {
+            "merge" | "unmerge" | "revive" => Some(Scope::Admin),
+            _                              => Some(Scope::Write),
+        },
+        _ => Some(Scope::Admin),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(required_scope(&Method::Get, &["web", "index.html"], false), None);
+        assert_eq!(required_scope(&Method::Get, &["v1", "tracks"], false), Some(Scope::Read));
+        assert_eq!(required_scope(&Method::Post, &["v1", "tracks", ".mget"], false), Some(Scope::Read));
+        assert_eq!(required_scope(&Method::Get, &["v1", "playlistify"], false), Some(Scope::Read));
+        assert_eq!(required_scope(&Method::Get, &["v1", "playlistify"], true), Some(Scope::Write));
+        assert_eq!(required_scope(&Method::Get, &["playlistify"], true), Some(Scope::Write));
+        assert_eq!(required_scope(&Method::Get, &["v1", "resolve"], false), Some(Scope::Write));
+        assert_eq!(required_scope(&Method::Post, &["v1", "feeds"], false), Some(Scope::Write));
+        assert_eq!(required_scope(&Method::Post, &["v1", "tracks", "abc"], false), Some(Scope::Write));
+        assert_eq!(required_scope(&Method::Post, &["v1", "artists", "abc", "merge"], false), Some(Scope::Admin));
+        assert_eq!(required_scope(&Method::Post, &["v1", "albums", "abc", "revive"], false), Some(Scope::Admin));
+    }
+
+    #[test]
+    fn test_has_force() {
+        assert!(has_force("url=http%3A%2F%2Fexample.com&force=true"));
+        assert!(has_force("force=%74rue"));
+        assert!(has_force("%66orce=true"));
+        assert!(!has_force("force=false"));
+        assert!(!has_force("url=force%3Dtrue"));
+    }
+}
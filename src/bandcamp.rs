@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::default::Default;
+use reqwest::header::Connection;
+use html5ever::parse_document;
+use html5ever::rcdom::RcDom;
+use html5ever::tendril::stream::TendrilSink;
+use regex::Regex;
+use chrono::NaiveDateTime;
+use dom;
+use http;
+use error::{Error, ProviderError};
+use model::Provider;
+
+static IMAGE_BASE_URL: &'static str = "https://f4.bcbits.com/img";
+static THUMBNAIL_SIZE: &'static str = "7";
+static ARTWORK_SIZE:   &'static str = "10";
+
+pub static EMBED: &'static str = r"bandcamp.com/EmbeddedPlayer/.*?(album|track)=([0-9]+)";
+pub static PAGE:  &'static str = r"([a-zA-Z0-9-]+)\.bandcamp\.com/(album|track)/([a-zA-Z0-9_-]+)";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tralbum {
+    pub id:                 i64,
+    pub item_type:          String,
+    pub artist:             String,
+    pub art_id:             Option<i64>,
+    pub url:                Option<String>,
+    pub album_release_date: Option<String>,
+    pub current:            Current,
+    pub trackinfo:          Vec<TrackInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Current {
+    pub id:           i64,
+    pub title:        String,
+    pub about:        Option<String>,
+    pub release_date: Option<String>,
+    pub publish_date: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrackInfo {
+    pub id:         Option<i64>,
+    pub track_id:   Option<i64>,
+    pub title:      String,
+    pub artist:     Option<String>,
+    pub duration:   Option<f64>,
+    pub track_num:  Option<i32>,
+    pub title_link: Option<String>,
+    pub file:       Option<BTreeMap<String, String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Band {
+    pub id:       i64,
+    pub name:     String,
+    pub image_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PlayerData {
+    linkback: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub url:     String,
+    pub tralbum: Tralbum,
+    pub band:    Option<Band>,
+}
+
+impl TrackInfo {
+    pub fn identifier(&self) -> Option<String> {
+        self.track_id.or(self.id).map(|id| id.to_string())
+    }
+    pub fn stream_url(&self) -> Option<String> {
+        self.file.clone().and_then(|f| f.get("mp3-128").map(|url| url.to_string()))
+    }
+}
+
+impl Tralbum {
+    pub fn get_thumbnail_url(&self) -> Option<String> {
+        self.art_id.map(|id| format!("{}/a{:010}_{}.jpg", IMAGE_BASE_URL, id, THUMBNAIL_SIZE))
+    }
+    pub fn get_artwork_url(&self) -> Option<String> {
+        self.art_id.map(|id| format!("{}/a{:010}_{}.jpg", IMAGE_BASE_URL, id, ARTWORK_SIZE))
+    }
+}
+
+impl Band {
+    pub fn get_thumbnail_url(&self) -> Option<String> {
+        self.image_id.map(|id| format!("{}/{:010}_{}.jpg", IMAGE_BASE_URL, id, THUMBNAIL_SIZE))
+    }
+    pub fn get_artwork_url(&self) -> Option<String> {
+        self.image_id.map(|id| format!("{}/{:010}_{}.jpg", IMAGE_BASE_URL, id, ARTWORK_SIZE))
+    }
+}
+
+impl Page {
+    /// The url of the artist site that the page belongs to, e.g. https://artist.bandcamp.com
+    pub fn band_url(&self) -> String {
+        Regex::new(r"^(https?://[^/]+)").ok()
+            .and_then(|re| re.captures(&self.url).map(|cap| cap[1].to_string()))
+            .unwrap_or(self.url.clone())
+    }
+    pub fn track_url(&self, info: &TrackInfo) -> String {
+        match info.title_link {
+            Some(ref link) => format!("{}{}", self.band_url(), link),
+            None           => self.url.clone(),
+        }
+    }
+}
+
+pub fn parse_embed_url(url: &str) -> Option<(String, String)> {
+    Regex::new(EMBED).ok().and_then(|re| re.captures(url).map(|cap| {
+        (cap[1].to_string(), cap[2].to_string())
+    }))
+}
+
+pub fn parse_page_url(url: &str) -> Option<(String, String)> {
+    Regex::new(PAGE).ok().and_then(|re| re.captures(url).map(|cap| {
+        let kind = cap[2].to_string();
+        (kind.clone(), format!("https://{}.bandcamp.com/{}/{}", &cap[1], kind, &cap[3]))
+    }))
+}
+
+pub fn parse_date(date: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(date, "%d %b %Y %H:%M:%S GMT").ok()
+}
+
+pub fn embed_url(kind: &str, id: &str) -> String {
+    format!("https://bandcamp.com/EmbeddedPlayer/{}={}", kind, id)
+}
+
+/// This function fetches an album or track page and parses its data-tralbum.
+/// An embedded player url is resolved to the page it links back to.
+pub fn fetch_page(url: &str) -> Result<Page, Error> {
+    let url = if parse_embed_url(url).is_some() {
+        let html = fetch_html(url)?;
+        let data = find_attr(&html, "data-player-data")?;
+        let player_data: PlayerData = http::parse_json(Provider::Bandcamp, &data)?;
+        player_data.linkback
+    } else {
+        url.to_string()
+    };
+    let html = fetch_html(&url)?;
+    parse_page(&html, &url)
+}
+
+pub fn parse_page(html: &str, url: &str) -> Result<Page, Error> {
+    let tralbum = find_attr(html, "data-tralbum").and_then(|s| http::parse_json(Provider::Bandcamp, &s))?;
+    let band    = find_attr(html, "data-band").and_then(|s| http::parse_json(Provider::Bandcamp, &s)).ok();
+    Ok(Page {
+        url:     url.to_string(),
+        tralbum: tralbum,
+        band:    band,
+    })
+}
+
+fn find_attr(html: &str, attr_name: &str) -> Result<String, Error> {
+    let dom = parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())
+        .map_err(|e| Error::Provider(Provider::Bandcamp, ProviderError::Parse(e.to_string())))?;
+    dom::find_attr(attr_name, dom.document.clone())
+        .ok_or(Error::Provider(Provider::Bandcamp, ProviderError::Parse(format!("{} not found", attr_name))))
+}
+
+fn fetch_html(url: &str) -> Result<String, Error> {
+    http::get(url)
+        .header(Connection::close())
+        .fetch(Provider::Bandcamp)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Read;
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let embed = "https://bandcamp.com/EmbeddedPlayer/album=2788904580/size=large/bgcol=ffffff/";
+        assert_eq!(parse_embed_url(embed), Some(("album".to_string(), "2788904580".to_string())));
+        let embed = "https://bandcamp.com/EmbeddedPlayer/v=2/track=1375836343/size=small/";
+        assert_eq!(parse_embed_url(embed), Some(("track".to_string(), "1375836343".to_string())));
+        let page = "http://kaitomori.bandcamp.com/album/paper-boats?from=embed";
+        assert_eq!(parse_page_url(page), Some(("album".to_string(),
+                                               "https://kaitomori.bandcamp.com/album/paper-boats".to_string())));
+        assert_eq!(parse_page_url("https://bandcamp.com/"), None);
+        assert!(parse_date("29 Jun 2018 00:00:00 GMT").is_some());
+    }
+
+    #[test]
+    fn test_parse_album_page() {
+        let mut html = String::new();
+        File::open("fixture/bandcamp_album.html").unwrap().read_to_string(&mut html).unwrap();
+        let page = parse_page(&html, "https://kaitomori.bandcamp.com/album/paper-boats").unwrap();
+        assert_eq!(page.tralbum.item_type, "album");
+        assert_eq!(page.tralbum.id, 2788904580);
+        assert_eq!(page.tralbum.current.title, "Paper Boats");
+        assert_eq!(page.tralbum.artist, "Kaito Mori");
+        assert_eq!(page.tralbum.trackinfo.len(), 2);
+        assert_eq!(page.tralbum.get_artwork_url(),
+                   Some("https://f4.bcbits.com/img/a0412345678_10.jpg".to_string()));
+        let info = &page.tralbum.trackinfo[0];
+        assert_eq!(info.identifier(), Some("1375836343".to_string()));
+        assert_eq!(page.track_url(info), "https://kaitomori.bandcamp.com/track/harbor-lights");
+        assert!(info.stream_url().is_some());
+        let band = page.band.unwrap();
+        assert_eq!(band.name, "Kaito Mori");
+        assert_eq!(band.id, 3456789012);
+    }
+
+    #[test]
+    fn test_parse_track_page() {
+        let mut html = String::new();
+        File::open("fixture/bandcamp_track.html").unwrap().read_to_string(&mut html).unwrap();
+        let page = parse_page(&html, "https://kaitomori.bandcamp.com/track/harbor-lights").unwrap();
+        assert_eq!(page.tralbum.item_type, "track");
+        assert_eq!(page.tralbum.current.title, "Harbor Lights");
+        assert_eq!(page.tralbum.trackinfo.len(), 1);
+        assert_eq!(page.tralbum.trackinfo[0].duration, Some(214.5));
+    }
+
+    #[test]
+    fn test_parse_page_without_tralbum() {
+        let mut html = String::new();
+        File::open("fixture/test.html").unwrap().read_to_string(&mut html).unwrap();
+        assert!(parse_page(&html, "http://example.com/").is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use reqwest::header::Connection;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use http;
+use error::{Error, ProviderError};
+use model::{Provider, State};
+
+static BASE_URL:       &'static str = "https://api.deezer.com";
+pub static TRACK:      &'static str = r"deezer.com/(?:[a-z]{2}/)?track/([0-9]+)";
+pub static ALBUM:      &'static str = r"deezer.com/(?:[a-z]{2}/)?album/([0-9]+)";
+pub static PLAYLIST:   &'static str = r"deezer.com/(?:[a-z]{2}/)?playlist/([0-9]+)";
+pub static WIDGET:     &'static str = r"widget.deezer.com/widget/(?:[a-z]+/)?(track|album|playlist)/([0-9]+)";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Track {
+    pub id:           i64,
+    pub readable:     Option<bool>,
+    pub title:        String,
+    pub title_short:  Option<String>,
+    pub isrc:         Option<String>,
+    pub link:         String,
+    pub duration:     i32,
+    pub release_date: Option<String>,
+    pub preview:      Option<String>,
+    pub artist:       Artist,
+    pub album:        Option<Album>,
+    pub contributors: Option<Vec<Artist>>,
+}
+
+impl Track {
+    /// Deezer returns the tracks that can't be streamed as not readable.
+    pub fn state(&self) -> State {
+        match self.readable {
+            Some(false) => State::Unavailable,
+            _           => State::Alive,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Album {
+    pub id:           i64,
+    pub title:        String,
+    pub link:         Option<String>,
+    pub cover_medium: Option<String>,
+    pub cover_xl:     Option<String>,
+    pub release_date: Option<String>,
+    pub artist:       Option<Artist>,
+    pub contributors: Option<Vec<Artist>>,
+    pub genres:       Option<Paging<Genre>>,
+    pub tracks:       Option<Paging<Track>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Playlist {
+    pub id:             i64,
+    pub title:          String,
+    pub description:    Option<String>,
+    pub link:           String,
+    pub picture_medium: Option<String>,
+    pub picture_xl:     Option<String>,
+    pub creation_date:  Option<String>,
+    pub creator:        User,
+    pub nb_tracks:      Option<i32>,
+    pub tracks:         Option<Paging<Track>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Artist {
+    pub id:             i64,
+    pub name:           String,
+    pub link:           Option<String>,
+    pub picture_medium: Option<String>,
+    pub picture_xl:     Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Genre {
+    pub id:   i64,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct User {
+    pub id:   i64,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ErrorBody {
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub message:    String,
+    pub code:       i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Paging<T> {
+    pub data:  Vec<T>,
+    pub total: Option<i32>,
+    pub next:  Option<String>,
+}
+
+impl<T: DeserializeOwned> Paging<T> {
+    pub fn fetch_next(&self) -> Result<Paging<T>, Error> {
+        if let Some(ref url) = self.next {
+            fetch_url(url)
+        } else {
+            Err(Error::NotFound)
+        }
+    }
+}
+
+impl Artist {
+    pub fn get_url(&self) -> String {
+        self.link.clone().unwrap_or(format!("https://www.deezer.com/artist/{}", self.id))
+    }
+}
+
+impl Album {
+    pub fn get_url(&self) -> String {
+        self.link.clone().unwrap_or(format!("https://www.deezer.com/album/{}", self.id))
+    }
+
+    pub fn genre_names(&self) -> Vec<String> {
+        self.genres.clone().map(|g| g.data).unwrap_or(vec![]).iter()
+            .map(|g| g.name.to_string())
+            .collect()
+    }
+}
+
+/// This function parses a widget url into its kind (track, album or playlist) and id.
+pub fn parse_widget_url(url: &str) -> Option<(String, String)> {
+    Regex::new(WIDGET).ok().and_then(|re| re.captures(url).map(|cap| {
+        (cap[1].to_string(), cap[2].to_string())
+    }))
+}
+
+pub fn parse_date(date: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok().map(|d| d.and_hms(0, 0, 0))
+}
+
+/// This function fetches a track info with deezer api.
+///
+/// # Examples
+///
+/// ```no_run
+/// let track = pink_spider::deezer::fetch_track("3135556").unwrap();
+///
+/// assert_eq!(track.id, 3135556);
+/// ```
+pub fn fetch_track(id: &str) -> Result<Track, Error> {
+    let path = format!("/track/{}", id);
+    fetch(&path)
+}
+
+/// This function fetches an album info with deezer api.
+///
+/// # Examples
+///
+/// ```no_run
+/// let album = pink_spider::deezer::fetch_album("302127").unwrap();
+///
+/// assert_eq!(album.id, 302127);
+/// ```
+pub fn fetch_album(id: &str) -> Result<Album, Error> {
+    let path = format!("/album/{}", id);
+    fetch(&path)
+}
+
+pub fn fetch_playlist(id: &str) -> Result<Playlist, Error> {
+    let path = format!("/playlist/{}", id);
+    fetch(&path)
+}
+
+pub fn fetch_playlist_tracks(id: &str) -> Result<Paging<Track>, Error> {
+    let path = format!("/playlist/{}/tracks", id);
+    fetch(&path)
+}
+
+pub fn fetch_artist(id: &str) -> Result<Artist, Error> {
+    let path = format!("/artist/{}", id);
+    fetch(&path)
+}
+
+fn fetch<T: DeserializeOwned>(path: &str) -> Result<T, Error> {
+    let url = format!("{}{}", BASE_URL, path);
+    fetch_url(&url)
+}
+
+/// deezer responds the errors with 200, so the error in the body is checked first.
+fn fetch_url<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
+    let body = http::get(url)
+        .header(Connection::close())
+        .fetch(Provider::Deezer)?;
+    if let Ok(res) = http::parse_json::<ErrorResponse>(Provider::Deezer, &body) {
+        let cause = match res.error.code {
+            4   => ProviderError::RateLimited(None),
+            300 => ProviderError::Unauthorized,
+            800 => ProviderError::NotFound,
+            _   => ProviderError::Parse(res.error.message),
+        };
+        return Err(Error::Provider(Provider::Deezer, cause));
+    }
+    http::parse_json(Provider::Deezer, &body)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use error::ProviderError;
+    use http::{ReplayTransport, set_transport, reset_transport};
+    use model;
+    use model::conn;
+    use super::*;
+
+    #[test]
+    fn test_parse_widget_url() {
+        let widget = "https://widget.deezer.com/widget/dark/playlist/1479458365?tracklist=false";
+        assert_eq!(parse_widget_url(widget), Some(("playlist".to_string(), "1479458365".to_string())));
+        let widget = "https://widget.deezer.com/widget/album/302127";
+        assert_eq!(parse_widget_url(widget), Some(("album".to_string(), "302127".to_string())));
+        assert_eq!(parse_widget_url("https://www.deezer.com/en/track/3135556"), None);
+        assert!(parse_date("2001-03-07").is_some());
+    }
+
+    #[test]
+    fn test_fetch_track() {
+        set_transport(ReplayTransport);
+        let track = fetch_track("3135556").unwrap();
+        assert_eq!(track.title, "Harder, Better, Faster, Stronger");
+        assert_eq!(track.artist.name, "Daft Punk");
+        assert_eq!(track.album.clone().unwrap().id, 302127);
+        assert_eq!(track.state(), State::Alive);
+
+        match fetch_track("0") {
+            Err(Error::Provider(Provider::Deezer, ProviderError::NotFound)) => (),
+            r => panic!("unexpected result {:?}", r),
+        }
+        reset_transport();
+    }
+
+    #[test]
+    fn test_from_dz_track() {
+        set_transport(ReplayTransport);
+        let conn     = conn().unwrap();
+        let dz_track = fetch_track("3135556").unwrap();
+        let track    = model::Track::from_dz_track(&*conn, &dz_track);
+        assert_eq!(track.provider, Provider::Deezer);
+        assert_eq!(track.identifier, "3135556");
+        assert_eq!(track.title, "Harder, Better, Faster, Stronger");
+        assert_eq!(track.url, "https://www.deezer.com/track/3135556");
+        assert_eq!(track.owner_id, Some("27".to_string()));
+        assert_eq!(track.owner_name, Some("Daft Punk".to_string()));
+        assert_eq!(track.duration, 224);
+        assert_eq!(track.isrc, Some("GBDUW0000059".to_string()));
+        assert!(track.audio_url.unwrap().ends_with(".mp3"));
+        assert_eq!(track.published_at, NaiveDate::from_ymd(2001, 3, 7).and_hms(0, 0, 0));
+        assert_eq!(track.artwork_url, dz_track.album.unwrap().cover_xl);
+        let artists = track.artists.unwrap();
+        assert_eq!(artists.len(), 1);
+        assert_eq!(artists[0].provider, Provider::Deezer);
+        assert_eq!(artists[0].identifier, "27");
+        assert_eq!(artists[0].name, "Daft Punk");
+        reset_transport();
+    }
+
+    #[test]
+    fn test_from_dz_album() {
+        set_transport(ReplayTransport);
+        let conn     = conn().unwrap();
+        let dz_album = fetch_album("302127").unwrap();
+        assert_eq!(dz_album.genre_names(), vec!["Dance".to_string(), "Electro".to_string()]);
+        let album = model::Album::from_dz_album(&*conn, &dz_album);
+        assert_eq!(album.identifier, "302127");
+        assert_eq!(album.title, "Discovery");
+        assert_eq!(album.owner_name, Some("Daft Punk".to_string()));
+        assert_eq!(album.thumbnail_url, dz_album.cover_medium);
+        assert_eq!(album.published_at, NaiveDate::from_ymd(2001, 3, 7).and_hms(0, 0, 0));
+        assert_eq!(album.artists.unwrap()[0].identifier, "27");
+        let genres = album.genres.unwrap().iter().map(|g| g.name.clone()).collect::<Vec<_>>();
+        assert!(genres.contains(&"Electro".to_string()));
+        assert_eq!(album.tracks.len(), 2);
+        assert_eq!(album.tracks[0].title, "One More Time");
+        assert_eq!(album.tracks[0].artwork_url, dz_album.cover_xl);
+        // an empty preview isn't an audio url
+        assert_eq!(album.tracks[1].audio_url, None);
+        reset_transport();
+    }
+
+    #[test]
+    fn test_from_dz_playlist() {
+        set_transport(ReplayTransport);
+        let conn        = conn().unwrap();
+        let dz_playlist = fetch_playlist("1479458365").unwrap();
+        let playlist    = model::Playlist::from_dz_playlist(&*conn, &dz_playlist);
+        assert_eq!(playlist.identifier, "1479458365");
+        assert_eq!(playlist.title, "French Touch");
+        assert_eq!(playlist.owner_id, Some("917475151".to_string()));
+        assert_eq!(playlist.owner_name, Some("Deezer Electro".to_string()));
+        assert_eq!(playlist.published_at, NaiveDate::from_ymd(2015, 10, 5).and_hms(14, 19, 29));
+        // the track that isn't readable is skipped
+        assert_eq!(playlist.tracks.len(), 1);
+        assert_eq!(playlist.tracks[0].track.identifier, "3135556");
+        reset_transport();
+    }
+}
@@ -149,6 +149,18 @@ pub fn find_node(handle: Handle, tag_name: &str, nodes: &mut Vec<Rc<Node>>) {
     }
 }
 
+pub fn find_attr(attr_name: &str, handle: Handle) -> Option<String> {
+    if let Some(value) = get_attr(attr_name, handle.clone()) {
+        return Some(value)
+    }
+    for child in handle.children.borrow().iter() {
+        if let Some(value) = find_attr(attr_name, child.clone()) {
+            return Some(value)
+        }
+    }
+    None
+}
+
 pub fn has_nodes(handle: Handle, tag_names: &Vec<&'static str>) -> bool {
     for child in handle.children.borrow().iter() {
         let tag_name: &str = &get_tag_name(child.clone()).unwrap_or("".to_string());
@@ -0,0 +1,47 @@
+extern crate pink_spider;
+use pink_spider::model::{Model, Track, Album};
+use pink_spider::model::{conn};
+use std::time::Duration;
+use std::thread;
+
+/// This batch enriches the albums first, so that a request enriches all the tracks of an album.
+/// The tracks that aren't found in their albums are looked up one by one.
+pub fn main() {
+    let conn       = conn().unwrap();
+    let mut albums = Album::find_unenriched(&*conn).unwrap();
+    println!("albums len {}\n", albums.len());
+    let _ = Album::set_relations(&*conn, &mut albums);
+    for mut album in albums {
+        thread::sleep(Duration::from_millis(500));
+        match album.enrich(&*conn) {
+            Ok(tracks) => {
+                print!("album id: {} {}:{} {} tracks are enriched\n",
+                       album.id, album.provider, album.identifier, tracks.len());
+            },
+            Err(e) => {
+                print!("Failed to enrich album id: {} {}:{} {}\n", album.id, album.provider, album.identifier, album.title);
+                print!("{}\n", e);
+            },
+        }
+    }
+
+    let mut tracks = Track::find_unenriched(&*conn).unwrap();
+    println!("len {}\n", tracks.len());
+    let _ = Track::set_relations(&*conn, &mut tracks);
+    for mut track in tracks {
+        thread::sleep(Duration::from_millis(500));
+        match track.enrich(&*conn) {
+            Ok(_) => {
+                print!("track id: {} {}:{} genres: {:?} moods: {:?} tempos: {:?} is enriched\n",
+                       track.id, track.provider, track.identifier,
+                       track.genres.clone().unwrap_or(vec![]).iter().map(|g| g.name.clone()).collect::<Vec<_>>(),
+                       track.moods.clone().unwrap_or(vec![]),
+                       track.tempos.clone().unwrap_or(vec![]));
+            },
+            Err(e) => {
+                print!("Failed to enrich track id: {} {}:{} {}\n", track.id, track.provider, track.identifier, track.title);
+                print!("{}\n", e);
+            },
+        }
+    }
+}
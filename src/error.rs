@@ -8,56 +8,201 @@ use iron::mime::Mime;
 use serde_json;
 use serde::Serialize;
 use serde::Serializer;
+use serde::ser::SerializeStruct;
 use std::str::FromStr;
 use std::error;
+#[cfg(feature = "postgres")]
 use postgres;
+#[cfg(feature = "sqlite")]
+use rusqlite;
+use r2d2;
 use reqwest;
 use url;
 use params::ParamsError;
+use model::Provider;
 
 #[derive(Debug)]
 pub enum Error {
     BadRequest,
     Unprocessable,
     NotFound,
+    /// The request needs an api key, but it doesn't have a valid one.
+    Unauthorized,
+    /// The api key of the request doesn't have the scope.
+    Forbidden,
+    /// The client made too many requests. It has the seconds to wait.
+    TooManyRequests(u64),
+    #[cfg(feature = "postgres")]
     DbError(postgres::error::DbError),
+    #[cfg(feature = "postgres")]
     DbConnectError(postgres::error::Error),
+    #[cfg(feature = "sqlite")]
+    SqliteError(rusqlite::Error),
+    DbPoolError(r2d2::Error),
+    /// The database can't be used as configured, e.g. the url isn't supported.
+    Database(String),
     UrlParseError(url::ParseError),
+    Provider(Provider, ProviderError),
     Unexpected,
 }
 
+/// The cause of a failed request to a provider.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    /// The provider responded with an unexpected status.
+    Status(u16),
+    /// The provider limits the requests. It has the seconds to wait if the provider tells.
+    RateLimited(Option<u64>),
+    /// The credentials of the provider are missing or rejected.
+    Unauthorized,
+    Timeout,
+    /// The provider can't be reached.
+    Network(String),
+    /// The response of the provider can't be parsed.
+    Parse(String),
+    /// The provider doesn't have the item, e.g. the track is deleted.
+    NotFound,
+}
+
+impl ProviderError {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ProviderError::Status(_)      => "ProviderStatus",
+            ProviderError::RateLimited(_) => "ProviderRateLimited",
+            ProviderError::Unauthorized   => "ProviderUnauthorized",
+            ProviderError::Timeout        => "ProviderTimeout",
+            ProviderError::Network(_)     => "ProviderNetworkError",
+            ProviderError::Parse(_)       => "ProviderParseError",
+            ProviderError::NotFound       => "ProviderNotFound",
+        }
+    }
+}
+
+impl ProviderError {
+    /// This function describes the failure without the details from the provider or the network,
+    /// e.g. urls, so that it can be shown to the clients and saved with the items.
+    pub fn summary(&self) -> String {
+        match *self {
+            ProviderError::Network(_) => "network error".to_string(),
+            ProviderError::Parse(_)   => "parse error".to_string(),
+            ref cause                 => cause.to_string(),
+        }
+    }
+}
+
+impl Display for ProviderError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            ProviderError::Status(status)          => write!(f, "responded with status {}", status),
+            ProviderError::RateLimited(Some(secs)) => write!(f, "rate limited, retry after {} seconds", secs),
+            ProviderError::RateLimited(None)       => write!(f, "rate limited"),
+            ProviderError::Unauthorized            => write!(f, "unauthorized"),
+            ProviderError::Timeout                 => write!(f, "timed out"),
+            ProviderError::Network(ref e)          => write!(f, "network error: {}", e),
+            ProviderError::Parse(ref e)            => write!(f, "parse error: {}", e),
+            ProviderError::NotFound                => write!(f, "not found"),
+        }
+    }
+}
+
 impl Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer
     {
         match *self {
-            Error::BadRequest        => serializer.serialize_str("BadRequest"),
-            Error::Unprocessable     => serializer.serialize_str("Unprocessable"),
-            Error::NotFound          => serializer.serialize_str("NotFound"),
-            Error::DbError(_)        => serializer.serialize_str("DbError"),
-            Error::DbConnectError(_) => serializer.serialize_str("DbConnectError"),
-            Error::UrlParseError(_)  => serializer.serialize_str("UrlParseError"),
-            Error::Unexpected        => serializer.serialize_str("Unexpected"),
+            Error::Provider(ref provider, ref cause) => {
+                let mut state = serializer.serialize_struct("ProviderError", 3)?;
+                state.serialize_field("error", cause.name())?;
+                state.serialize_field("provider", provider)?;
+                state.serialize_field("message", &cause.summary())?;
+                state.end()
+            },
+            _ => serializer.serialize_str(self.name()),
         }
     }
 }
 
 impl Error {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Error::BadRequest        => "BadRequest",
+            Error::Unprocessable     => "Unprocessable",
+            Error::NotFound          => "NotFound",
+            Error::Unauthorized      => "Unauthorized",
+            Error::Forbidden         => "Forbidden",
+            Error::TooManyRequests(_) => "TooManyRequests",
+            #[cfg(feature = "postgres")]
+            Error::DbError(_)        => "DbError",
+            #[cfg(feature = "postgres")]
+            Error::DbConnectError(_) => "DbConnectError",
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(_)    => "SqliteError",
+            Error::DbPoolError(_)    => "DbPoolError",
+            Error::Database(_)       => "DatabaseError",
+            Error::UrlParseError(_)  => "UrlParseError",
+            Error::Provider(_, ref cause) => cause.name(),
+            Error::Unexpected        => "Unexpected",
+        }
+    }
+
     pub fn status(&self) -> Status {
         match *self {
             Error::BadRequest        => Status::BadRequest,
             Error::Unprocessable     => Status::UnprocessableEntity,
             Error::NotFound          => Status::NotFound,
+            Error::Unauthorized      => Status::Unauthorized,
+            Error::Forbidden         => Status::Forbidden,
+            Error::TooManyRequests(_) => Status::TooManyRequests,
+            #[cfg(feature = "postgres")]
             Error::DbError(_)        => Status::InternalServerError,
+            #[cfg(feature = "postgres")]
             Error::DbConnectError(_) => Status::InternalServerError,
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(_)    => Status::InternalServerError,
+            Error::DbPoolError(_)    => Status::ServiceUnavailable,
+            Error::Database(_)       => Status::InternalServerError,
             Error::UrlParseError(_)  => Status::InternalServerError,
+            Error::Provider(_, ref cause) => match *cause {
+                ProviderError::NotFound       => Status::NotFound,
+                ProviderError::RateLimited(_) => Status::ServiceUnavailable,
+                ProviderError::Timeout        => Status::GatewayTimeout,
+                _                             => Status::BadGateway,
+            },
             Error::Unexpected        => Status::InternalServerError,
         }
     }
 
+    /// This function describes the error for the clients. Only the errors of the providers have details.
+    pub fn summary(&self) -> String {
+        match *self {
+            Error::Provider(ref provider, ref cause) => format!("ProviderError {}: {}", provider, cause.summary()),
+            _                                        => self.name().to_string(),
+        }
+    }
+
+    /// This function tells whether the item doesn't exist, at pink-spider or at the provider.
+    /// The other errors of providers are temporary, so the items shouldn't be disabled by them.
+    pub fn is_not_found(&self) -> bool {
+        match *self {
+            Error::NotFound                              => true,
+            Error::Provider(_, ProviderError::NotFound) => true,
+            _                                            => false,
+        }
+    }
+
     pub fn as_response(&self) -> Response {
         let json_type = Header(ContentType(Mime::from_str("application/json").ok().unwrap()));
-        Response::with((self.status(), json_type, serde_json::to_string(self).unwrap()))
+        let mut response = Response::with((self.status(), json_type, serde_json::to_string(self).unwrap()));
+        match *self {
+            Error::Provider(_, ProviderError::RateLimited(Some(secs))) | Error::TooManyRequests(secs) => {
+                response.headers.set_raw("Retry-After", vec![secs.to_string().into_bytes()]);
+            },
+            _ => (),
+        }
+        if let Error::Unauthorized = *self {
+            response.headers.set_raw("WWW-Authenticate", vec![b"Bearer".to_vec()]);
+        }
+        response
     }
 }
 
@@ -68,9 +213,19 @@ impl Display for Error {
             Error::BadRequest            => write!(f, "BadRequest"),
             Error::Unprocessable         => write!(f, "Unproccesable"),
             Error::NotFound              => write!(f, "NotFound"),
+            Error::Unauthorized          => write!(f, "Unauthorized"),
+            Error::Forbidden             => write!(f, "Forbidden"),
+            Error::TooManyRequests(secs) => write!(f, "TooManyRequests, retry after {} seconds", secs),
+            #[cfg(feature = "postgres")]
             Error::DbError(ref e)        => write!(f, "DBError {}", e),
+            #[cfg(feature = "postgres")]
             Error::DbConnectError(ref e) => write!(f, "DBConnectError {}", e),
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(ref e)    => write!(f, "SqliteError {}", e),
+            Error::DbPoolError(ref e)    => write!(f, "DBPoolError {}", e),
+            Error::Database(ref e)       => write!(f, "DatabaseError {}", e),
             Error::UrlParseError(ref e)  => write!(f, "UrlParseError:  {}", e),
+            Error::Provider(ref p, ref e) => write!(f, "ProviderError {}: {}", p, e),
             Error::Unexpected            => write!(f, "UnexpectedError"),
         }
     }
@@ -82,18 +237,33 @@ impl From<url::ParseError> for Error {
     }
 }
 
+#[cfg(feature = "postgres")]
 impl From<postgres::error::DbError> for Error {
     fn from(err: postgres::error::DbError) -> Error {
         Error::DbError(err)
     }
 }
 
+#[cfg(feature = "postgres")]
 impl From<postgres::error::Error> for Error {
     fn from(err: postgres::error::Error) -> Error {
         Error::DbConnectError(err)
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Error {
+        Error::SqliteError(err)
+    }
+}
+
+impl From<r2d2::Error> for Error {
+    fn from(err: r2d2::Error) -> Error {
+        Error::DbPoolError(err)
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(_: reqwest::Error) -> Error {
         Error::BadRequest
@@ -118,15 +288,12 @@ impl From<ParseIntError> for Error {
 
 impl From<Error> for IronError {
     fn from(err: Error) -> IronError {
-        match err {
-            Error::BadRequest        => IronError::new(err, Status::BadRequest),
-            Error::Unprocessable     => IronError::new(err, Status::BadRequest),
-            Error::NotFound          => IronError::new(err, Status::NotFound),
-            Error::DbError(_)        => IronError::new(err, Status::InternalServerError),
-            Error::DbConnectError(_) => IronError::new(err, Status::InternalServerError),
-            Error::UrlParseError(_)  => IronError::new(err, Status::InternalServerError),
-            Error::Unexpected        => IronError::new(err, Status::InternalServerError),
-        }
+        let status = match err {
+            Error::Unprocessable => Status::BadRequest,
+            _                    => err.status(),
+        };
+        let response = err.as_response().set(status);
+        IronError { error: Box::new(err), response: response }
     }
 }
 
@@ -135,3 +302,36 @@ impl From<serde_json::Error> for Error {
         Error::Unexpected
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_provider_error() {
+        let err = Error::Provider(Provider::Spotify, ProviderError::NotFound);
+        assert_eq!(err.status(), Status::NotFound);
+        assert!(err.is_not_found());
+        assert_eq!(serde_json::to_string(&err).unwrap(),
+                   r#"{"error":"ProviderNotFound","provider":"Spotify","message":"not found"}"#);
+
+        let err = Error::Provider(Provider::YouTube, ProviderError::RateLimited(Some(30)));
+        assert_eq!(err.status(), Status::ServiceUnavailable);
+        assert!(!err.is_not_found());
+        assert_eq!(err.as_response().headers.get_raw("Retry-After"), Some(&[b"30".to_vec()][..]));
+
+        let err = Error::Provider(Provider::SoundCloud, ProviderError::Network("https://api.soundcloud.com/?client_id=secret".to_string()));
+        assert_eq!(serde_json::to_string(&err).unwrap(),
+                   r#"{"error":"ProviderNetworkError","provider":"SoundCloud","message":"network error"}"#);
+        assert_eq!(err.summary(), "ProviderError SoundCloud: network error");
+
+        let err = Error::TooManyRequests(12);
+        assert_eq!(err.status(), Status::TooManyRequests);
+        assert_eq!(err.as_response().headers.get_raw("Retry-After"), Some(&[b"12".to_vec()][..]));
+
+        let err = Error::Provider(Provider::SoundCloud, ProviderError::Timeout);
+        assert_eq!(err.status(), Status::GatewayTimeout);
+        let err = Error::Provider(Provider::Deezer, ProviderError::Status(500));
+        assert_eq!(err.status(), Status::BadGateway);
+    }
+}
@@ -1,4 +1,3 @@
-use std::io::Read;
 use std::str::FromStr;
 use get_env;
 use http;
@@ -126,39 +125,39 @@ impl SelectDetail {
 
 #[derive(Debug, Clone)]
 pub struct Track {
-    id:       String,
-    title:    String,
-    artists:  Vec<Artist>,
-    genres:   Vec<Genre>,
-    moods:    Vec<String>,
-    tempos:   Vec<String>,
+    pub id:       String,
+    pub title:    String,
+    pub artists:  Vec<Artist>,
+    pub genres:   Vec<Genre>,
+    pub moods:    Vec<String>,
+    pub tempos:   Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Album {
-    id:       String,
-    date:     String,
-    title:    String,
-    url:      String,
-    pkg_lang: String,
-    artists:  Vec<Artist>,
-    genres:   Vec<Genre>,
-    tracks:   Vec<Track>,
+    pub id:       String,
+    pub date:     String,
+    pub title:    String,
+    pub url:      String,
+    pub pkg_lang: String,
+    pub artists:  Vec<Artist>,
+    pub genres:   Vec<Genre>,
+    pub tracks:   Vec<Track>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Artist {
-    name:    String,
-    types:   Vec<String>,
-    origins: Vec<String>,
-    eras:    Vec<String>,
+    pub name:    String,
+    pub types:   Vec<String>,
+    pub origins: Vec<String>,
+    pub eras:    Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Genre {
-    id:   String,
-    num:  String,
-    name: String,
+    pub id:   String,
+    pub num:  String,
+    pub name: String,
 }
 
 #[derive(Debug, Clone)]
@@ -166,6 +165,22 @@ pub struct Response {
     pub albums: Vec<Album>,
 }
 
+impl Track {
+    pub fn genre_names(&self) -> Vec<String> {
+        self.genres.iter().map(|g| g.name.to_string()).collect()
+    }
+}
+
+impl Album {
+    pub fn genre_names(&self) -> Vec<String> {
+        self.genres.iter().map(|g| g.name.to_string()).collect()
+    }
+    pub fn find_track(&self, title: &str) -> Option<&Track> {
+        let title = title.trim().to_lowercase();
+        self.tracks.iter().find(|t| t.title.trim().to_lowercase() == title)
+    }
+}
+
 pub fn qual_name(name: &str) -> QualName {
     QualName::new(None, ns!(), LocalName::from(name))
 }
@@ -264,12 +279,46 @@ pub fn build_queries(a: Auth, q: Query) -> String {
 pub fn send(query: Query) -> Result<String, Error> {
     let queries = build_queries(auth(), query);
     let url = format!("https://c{}.web.cddbp.net/webapi/xml/1.0/", CLIENT_ID.to_string());
-    let mut res = http::client().post(&url)
-                                .body(queries)
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    Ok(body)
+    http::post(&url)
+        .body(&queries)
+        .send()
+        .map(|res| res.text())
+        .map_err(|_| Error::BadRequest)
+}
+
+fn search_query(texts: Vec<Text>) -> Query {
+    Query {
+        cmd:     "ALBUM_SEARCH".to_string(),
+        mode:    "SINGLE_BEST".to_string(),
+        texts:   texts,
+        range:   None,
+        options: vec![
+            QueryOption::SelectExtended(vec![SelectExtended::Mood, SelectExtended::Tempo]),
+            QueryOption::SelectDetail(vec![SelectDetail::Genre3Level,
+                                           SelectDetail::Mood2Level,
+                                           SelectDetail::Tempo3Level]),
+        ],
+    }
+}
+
+fn text(target_type: &str, value: &str) -> Text {
+    Text {
+        target_type: target_type.to_string(),
+        value:       value.to_string(),
+    }
+}
+
+/// This function searches the best album that has the track of the artist.
+/// The matched track is the first track of the album.
+pub fn search_track(artist: &str, title: &str) -> Result<Option<Album>, Error> {
+    let query = search_query(vec![text("ARTIST", artist), text("TRACK_TITLE", title)]);
+    parse(send(query)?).map(|res| res.albums.into_iter().next())
+}
+
+/// This function searches the best album with the title of the artist.
+pub fn search_album(artist: &str, title: &str) -> Result<Option<Album>, Error> {
+    let query = search_query(vec![text("ARTIST", artist), text("ALBUM_TITLE", title)]);
+    parse(send(query)?).map(|res| res.albums.into_iter().next())
 }
 
 pub fn parse(res: String) -> Result<Response, Error> {
@@ -456,3 +505,40 @@ pub fn get_attr(attr_name: &str, attrs: &Vec<Attribute>) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Read;
+    use super::parse;
+
+    #[test]
+    fn test_parse_album_search() {
+        let mut xml = String::new();
+        File::open("fixture/gracenote_album_search.xml").unwrap().read_to_string(&mut xml).unwrap();
+        let res = parse(xml).unwrap();
+        assert_eq!(res.albums.len(), 1);
+        let album = &res.albums[0];
+        assert_eq!(album.title, "Discovery");
+        assert_eq!(album.date, "2001");
+        assert_eq!(album.artists[0].name, "Daft Punk");
+        assert_eq!(album.artists[0].origins, vec!["Europe", "France"]);
+        assert_eq!(album.genre_names(), vec!["Electronica", "Electronica Mainstream", "House"]);
+        assert_eq!(album.tracks.len(), 2);
+
+        let track = album.find_track("one more time").unwrap();
+        assert_eq!(track.id, "11430918-2F7C0B9E4D6A13C58E0F27B9A4D6C1E5");
+        assert_eq!(track.moods, vec!["Excited", "Euphoric Energy"]);
+        assert_eq!(track.tempos, vec!["Fast Tempo", "Medium Fast", "120s"]);
+        assert!(track.genre_names().is_empty());
+        assert!(album.find_track("Unknown").is_none());
+    }
+
+    #[test]
+    fn test_parse_no_match() {
+        let mut xml = String::new();
+        File::open("fixture/gracenote_no_match.xml").unwrap().read_to_string(&mut xml).unwrap();
+        let res = parse(xml).unwrap();
+        assert!(res.albums.is_empty());
+    }
+}
@@ -1,8 +1,431 @@
 use reqwest;
+use reqwest::Method;
+use reqwest::header::{
+    Header,
+    Headers,
+    ContentType,
+    ContentLength,
+    ContentRange,
+    ContentRangeSpec,
+    Range,
+    ByteRangeSpec,
+};
+use std::io::{Read, Write};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::cell::RefCell;
 use std::time::Duration;
+use serde_json;
+use serde::de::DeserializeOwned;
+use url::Url;
+use regex::Regex;
+use get_env;
+use error::{Error, ProviderError};
+use model::Provider;
 
-pub fn client() -> reqwest::Client {
+static FIXTURE_DIR: &'static str = "fixture/http";
+
+/// The query parameters that are credentials. They are removed from the urls
+/// of the fixtures, so the fixtures can be committed and replayed with any keys.
+static SECRET_PARAMS: [&'static str; 6] = ["key",
+                                           "api_key",
+                                           "client_id",
+                                           "client_secret",
+                                           "access_token",
+                                           "token"];
+
+lazy_static! {
+    static ref SECRET_PARAM_RE: Regex = {
+        Regex::new(&format!(r"\b({})=[^&\s]*", SECRET_PARAMS.join("|"))).unwrap()
+    };
+}
+
+fn client() -> reqwest::Client {
     reqwest::Client::builder()
         .timeout(Duration::new(30, 0))
         .build().unwrap()
 }
+
+pub fn get(url: &str) -> Request {
+    Request::new(Method::Get, url)
+}
+
+pub fn post(url: &str) -> Request {
+    Request::new(Method::Post, url)
+}
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method:  Method,
+    pub url:     String,
+    pub headers: Headers,
+    pub body:    Option<String>,
+}
+
+impl Request {
+    pub fn new(method: Method, url: &str) -> Request {
+        Request {
+            method:  method,
+            url:     url.to_string(),
+            headers: Headers::new(),
+            body:    None,
+        }
+    }
+
+    pub fn header<H: Header>(mut self, header: H) -> Request {
+        self.headers.set(header);
+        self
+    }
+
+    pub fn headers(mut self, headers: Headers) -> Request {
+        self.headers.extend(headers.iter());
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Request {
+        self.body = Some(body.to_string());
+        self
+    }
+
+    /// This function sends the request with the transport of the current thread.
+    pub fn send(&self) -> Result<Response, ProviderError> {
+        transport().send(self)
+    }
+
+    /// This function sends the request to the provider and returns the body.
+    /// The failures, including the statuses except 2xx, are errors of the provider.
+    pub fn fetch(&self, provider: Provider) -> Result<String, Error> {
+        let res = self.send().map_err(|e| Error::Provider(provider, e))?;
+        check_status(provider, res.status, res.retry_after)?;
+        Ok(res.text())
+    }
+
+    pub fn fetch_json<T: DeserializeOwned>(&self, provider: Provider) -> Result<T, Error> {
+        self.fetch(provider).and_then(|body| parse_json(provider, &body))
+    }
+
+    /// The key of the fixture of this request. Credentials and headers aren't included.
+    fn fixture_key(&self) -> String {
+        let url = match Url::parse(&self.url) {
+            Ok(mut url) => {
+                let pairs = url.query_pairs()
+                    .filter(|&(ref k, _)| !SECRET_PARAMS.contains(&&**k))
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect::<Vec<(String, String)>>();
+                if pairs.is_empty() {
+                    url.set_query(None);
+                } else {
+                    url.query_pairs_mut().clear().extend_pairs(pairs);
+                }
+                url.to_string()
+            },
+            Err(_) => self.url.clone(),
+        };
+        format!("{} {} {}", self.method, url, self.body.clone().unwrap_or_default())
+    }
+
+    fn fixture_path(&self) -> PathBuf {
+        let key  = self.fixture_key();
+        let name = key.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .take(80)
+            .collect::<String>();
+        PathBuf::from(FIXTURE_DIR).join(format!("{}_{:016x}.json", name, fnv1a(&key)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status:         u16,
+    pub content_type:   Option<String>,
+    pub retry_after:    Option<u64>,
+    /// The length of the whole content. It's the instance length for the partial responses.
+    pub content_length: Option<u64>,
+    pub body:           Vec<u8>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// The http layer of the provider clients. The default transport is chosen
+/// with `HTTP_FIXTURE`:
+///
+/// - unset: requests are sent to the providers
+/// - `record`: requests are sent and the responses are saved under fixture/http
+/// - `replay`: the saved responses are returned, and nothing is sent
+///
+/// Tests can also provide their own transport with `set_transport`.
+pub trait Transport {
+    fn send(&self, request: &Request) -> Result<Response, ProviderError>;
+}
+
+pub struct HttpTransport;
+
+impl Transport for HttpTransport {
+    fn send(&self, request: &Request) -> Result<Response, ProviderError> {
+        let client = client();
+        let mut builder = client.request(request.method.clone(), request.url.as_str());
+        builder.headers(request.headers.clone());
+        if let Some(ref body) = request.body {
+            builder.body(body.clone());
+        }
+        let mut res  = builder.send().map_err(|e| transport_error(&e.to_string()))?;
+        let mut body = vec![];
+        // servers that ignore Range send the whole content, so it's read up to the end of the range
+        let read = match range_end(&request.headers) {
+            Some(end) => res.by_ref().take(end).read_to_end(&mut body),
+            None      => res.read_to_end(&mut body),
+        };
+        read.map_err(|e| transport_error(&e.to_string()))?;
+        let retry_after = res.headers().get_raw("Retry-After")
+            .and_then(|v| v.one())
+            .and_then(|v| String::from_utf8_lossy(v).trim().parse::<u64>().ok());
+        let content_length = match res.headers().get::<ContentRange>() {
+            Some(&ContentRange(ContentRangeSpec::Bytes { instance_length, .. })) => instance_length,
+            _ => res.headers().get::<ContentLength>().map(|l| l.0),
+        };
+        Ok(Response {
+            status:         res.status().as_u16(),
+            content_type:   res.headers().get::<ContentType>().map(|c| c.to_string()),
+            retry_after:    retry_after,
+            content_length: content_length,
+            body:           body,
+        })
+    }
+}
+
+/// A response that is saved as json. Text bodies are saved as they are,
+/// so that the fixtures can be read and edited.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Fixture {
+    method:         String,
+    url:            String,
+    status:         u16,
+    content_type:   Option<String>,
+    retry_after:    Option<u64>,
+    content_length: Option<u64>,
+    text:           Option<String>,
+    bytes:          Option<Vec<u8>>,
+}
+
+pub struct RecordTransport;
+
+impl Transport for RecordTransport {
+    fn send(&self, request: &Request) -> Result<Response, ProviderError> {
+        let response = HttpTransport.send(request)?;
+        let text     = String::from_utf8(response.body.clone()).ok();
+        let fixture  = Fixture {
+            method:         request.method.to_string(),
+            url:            request.fixture_key(),
+            status:         response.status,
+            content_type:   response.content_type.clone(),
+            retry_after:    response.retry_after,
+            content_length: response.content_length,
+            bytes:          if text.is_some() { None } else { Some(response.body.clone()) },
+            text:           text,
+        };
+        let path = request.fixture_path();
+        let json = serde_json::to_string_pretty(&fixture)
+            .map_err(|e| ProviderError::Parse(e.to_string()))?;
+        fs::create_dir_all(FIXTURE_DIR)
+            .and_then(|_| File::create(&path))
+            .and_then(|mut f| f.write_all(json.as_bytes()))
+            .map_err(|e| ProviderError::Network(format!("failed to record {:?}: {}", path, e)))?;
+        Ok(response)
+    }
+}
+
+pub struct ReplayTransport;
+
+impl Transport for ReplayTransport {
+    fn send(&self, request: &Request) -> Result<Response, ProviderError> {
+        let path = request.fixture_path();
+        let mut json = String::new();
+        File::open(&path).and_then(|mut f| f.read_to_string(&mut json)).map_err(|_| {
+            ProviderError::Network(format!("no fixture for {}: {:?}", request.fixture_key(), path))
+        })?;
+        let fixture: Fixture = serde_json::from_str(&json)
+            .map_err(|e| ProviderError::Parse(e.to_string()))?;
+        Ok(Response {
+            status:         fixture.status,
+            content_type:   fixture.content_type,
+            retry_after:    fixture.retry_after,
+            content_length: fixture.content_length,
+            body:           fixture.text.map(|t| t.into_bytes())
+                                        .or(fixture.bytes)
+                                        .unwrap_or_default(),
+        })
+    }
+}
+
+thread_local! {
+    static TRANSPORT: RefCell<Option<Rc<Transport>>> = RefCell::new(None);
+}
+
+/// This function replaces the transport of the current thread.
+pub fn set_transport<T: Transport + 'static>(transport: T) {
+    TRANSPORT.with(|t| *t.borrow_mut() = Some(Rc::new(transport)));
+}
+
+/// This function restores the default transport of the current thread.
+pub fn reset_transport() {
+    TRANSPORT.with(|t| *t.borrow_mut() = None);
+}
+
+fn transport() -> Rc<Transport> {
+    TRANSPORT.with(|t| {
+        if let Some(ref transport) = *t.borrow() {
+            return transport.clone();
+        }
+        let transport: Rc<Transport> = match get_env::var("HTTP_FIXTURE").as_ref().map(|v| v.as_str()) {
+            Some("record") => Rc::new(RecordTransport),
+            Some("replay") => Rc::new(ReplayTransport),
+            _              => Rc::new(HttpTransport),
+        };
+        transport
+    })
+}
+
+/// This function tells the cause of a failure from the status of a response.
+pub fn check_status(provider: Provider, status: u16, retry_after: Option<u64>) -> Result<(), Error> {
+    let cause = match status {
+        200...299 => return Ok(()),
+        401 | 403 => ProviderError::Unauthorized,
+        404 | 410 => ProviderError::NotFound,
+        429       => ProviderError::RateLimited(retry_after),
+        status    => ProviderError::Status(status),
+    };
+    Err(Error::Provider(provider, cause))
+}
+
+pub fn parse_json<T: DeserializeOwned>(provider: Provider, body: &str) -> Result<T, Error> {
+    serde_json::from_str(body).map_err(|e| Error::Provider(provider, ProviderError::Parse(e.to_string())))
+}
+
+/// The errors of reqwest start with the urls, so the credentials are removed from them.
+pub fn redact(text: &str) -> String {
+    SECRET_PARAM_RE.replace_all(text, "$1=[FILTERED]").into_owned()
+}
+
+/// reqwest doesn't tell timeouts from the other io errors, so they are told by the messages.
+fn transport_error(message: &str) -> ProviderError {
+    if message.contains("timed out") {
+        ProviderError::Timeout
+    } else {
+        ProviderError::Network(redact(message))
+    }
+}
+
+/// The number of bytes to read for the byte ranges of a request, if it has ones.
+fn range_end(headers: &Headers) -> Option<u64> {
+    match headers.get::<Range>() {
+        Some(&Range::Bytes(ref specs)) => specs.iter().map(|spec| match *spec {
+            ByteRangeSpec::FromTo(_, to) => Some(to + 1),
+            _                            => None,
+        }).fold(Some(0), |end, e| match (end, e) {
+            (Some(end), Some(e)) => Some(end.max(e)),
+            _                    => None,
+        }),
+        _ => None,
+    }
+}
+
+/// FNV-1a, which is stable across rust versions unlike `DefaultHasher`.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fixture_key() {
+        let request = get("https://www.googleapis.com/youtube/v3/videos?key=secret&part=snippet&id=Wr5f6hpYxmE");
+        assert_eq!(request.fixture_key(),
+                   "GET https://www.googleapis.com/youtube/v3/videos?part=snippet&id=Wr5f6hpYxmE ");
+        let request = get("https://api.soundcloud.com/tracks/371851634?client_id=secret");
+        assert_eq!(request.fixture_key(), "GET https://api.soundcloud.com/tracks/371851634 ");
+        let request = post("https://accounts.spotify.com/api/token").body("grant_type=client_credentials");
+        assert_eq!(request.fixture_key(),
+                   "POST https://accounts.spotify.com/api/token grant_type=client_credentials");
+    }
+
+    #[test]
+    fn test_range_end() {
+        let request = get("http://example.com/ep01.mp3").header(Range::bytes(0, 1023));
+        assert_eq!(range_end(&request.headers), Some(1024));
+        assert_eq!(range_end(&get("http://example.com/ep01.mp3").headers), None);
+    }
+
+    #[test]
+    fn test_fixture_path() {
+        let request = get("https://www.googleapis.com/youtube/v3/videos?key=secret&part=snippet,status,contentDetails&id=Wr5f6hpYxmE");
+        assert!(request.fixture_path().exists());
+        let request = post("https://accounts.spotify.com/api/token").body("grant_type=client_credentials");
+        assert!(request.fixture_path().exists());
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(redact("https://api.soundcloud.com/tracks/1?client_id=secret: connection refused"),
+                   "https://api.soundcloud.com/tracks/1?client_id=[FILTERED]: connection refused");
+        assert_eq!(redact("https://www.googleapis.com/youtube/v3/videos?part=snippet&key=secret&id=abc"),
+                   "https://www.googleapis.com/youtube/v3/videos?part=snippet&key=[FILTERED]&id=abc");
+        assert_eq!(redact("https://example.com/?monkey=1"), "https://example.com/?monkey=1");
+    }
+
+    struct EchoTransport;
+
+    impl Transport for EchoTransport {
+        fn send(&self, request: &Request) -> Result<Response, ProviderError> {
+            let status = request.url.trim_left_matches("http://example.com/").parse::<u16>().unwrap_or(200);
+            Ok(Response {
+                status:         status,
+                content_type:   None,
+                retry_after:    Some(10),
+                content_length: None,
+                body:           request.url.clone().into_bytes(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_set_transport() {
+        set_transport(EchoTransport);
+        assert_eq!(get("http://example.com/").send().unwrap().text(), "http://example.com/");
+        reset_transport();
+    }
+
+    #[test]
+    fn test_fetch() {
+        set_transport(EchoTransport);
+        let fetch = |url: &str| get(url).fetch(Provider::Spotify);
+        assert_eq!(fetch("http://example.com/").unwrap(), "http://example.com/");
+        match fetch("http://example.com/404") {
+            Err(Error::Provider(Provider::Spotify, ProviderError::NotFound)) => (),
+            r => panic!("unexpected result {:?}", r),
+        }
+        match fetch("http://example.com/429") {
+            Err(Error::Provider(_, ProviderError::RateLimited(Some(10)))) => (),
+            r => panic!("unexpected result {:?}", r),
+        }
+        match fetch("http://example.com/503") {
+            Err(Error::Provider(_, ProviderError::Status(503))) => (),
+            r => panic!("unexpected result {:?}", r),
+        }
+        match parse_json::<Vec<String>>(Provider::Spotify, "{") {
+            Err(Error::Provider(_, ProviderError::Parse(_))) => (),
+            r => panic!("unexpected result {:?}", r),
+        }
+        reset_transport();
+    }
+}
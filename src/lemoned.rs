@@ -1,12 +1,13 @@
-use std::io::Read;
 use chrono::{DateTime, Utc};
 use reqwest::header::{
     Headers,
     Connection,
 };
-use serde_json;
+use serde::de::DeserializeOwned;
 use get_env;
 use http;
+use error::Error;
+use model::Provider;
 
 
 lazy_static! {
@@ -44,19 +45,16 @@ pub struct Artist {
     pub artwork_url:   Option<String>,
 }
 
-pub fn fetch_track(id: &str) -> serde_json::Result<Track> {
+pub fn fetch_track(id: &str) -> Result<Track, Error> {
     let path = format!("/tracks/{}", id);
-    fetch(&path).and_then(|s| serde_json::from_str(&s))
+    fetch(&path)
 }
 
-fn fetch(path: &str) -> serde_json::Result<String> {
+fn fetch<T: DeserializeOwned>(path: &str) -> Result<T, Error> {
     let url    = format!("{}/v1{}", *BASE_URL, path);
     let mut headers = Headers::new();
     headers.set(Connection::close());
-    let mut res = http::client().get(&url)
-                                .headers(headers)
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    Ok(body)
+    http::get(&url)
+        .headers(headers)
+        .fetch_json(Provider::Custom)
 }
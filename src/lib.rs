@@ -10,11 +10,17 @@ extern crate regex;
 extern crate string_cache;
 extern crate url;
 extern crate uuid;
+#[cfg(feature = "postgres")]
 extern crate postgres;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
+extern crate r2d2;
 extern crate chrono;
 extern crate queryst;
 extern crate toml;
 extern crate encoding;
+extern crate sha2;
+extern crate rand;
 
 #[macro_use]
 extern crate serde_derive;
@@ -35,14 +41,25 @@ pub use self::model::Album;
 pub use self::model::Provider;
 
 pub mod error;
+pub mod auth;
+pub mod rate_limit;
 pub mod scraper;
+pub mod adapter;
 pub mod rss;
 pub mod model;
+pub mod migration;
 pub mod apple_music;
 pub mod youtube;
 pub mod soundcloud;
 pub mod spotify;
+pub mod bandcamp;
+pub mod mixcloud;
+pub mod deezer;
+pub mod raw;
+pub mod audio_tag;
 pub mod gracenote;
+pub mod matcher;
+pub mod search;
 pub mod lemoned;
 pub mod get_env;
 pub mod http;
@@ -0,0 +1,30 @@
+extern crate pink_spider;
+use std::env;
+use pink_spider::model::{Model, Track};
+use pink_spider::model::{conn};
+
+static DEFAULT_LIMIT: i64 = 1000;
+
+/// This batch links the tracks that haven't been linked yet to the same songs of other providers,
+/// so that `/v1/tracks/:id/equivalents` only reads the links.
+pub fn main() {
+    let limit = env::args().nth(1)
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_LIMIT);
+    let conn = conn().unwrap();
+    let mut tracks = Track::find_unlinked(&*conn, limit).unwrap();
+    println!("len {}\n", tracks.len());
+    let _ = Track::set_relations(&*conn, &mut tracks);
+    for track in tracks {
+        match track.link_equivalents(&*conn) {
+            Ok(items) => {
+                print!("track id: {} {}:{} is linked to {} tracks\n",
+                       track.id, track.provider, track.identifier, items.len());
+            },
+            Err(e) => {
+                print!("Failed to link track id: {} {}:{}\n", track.id, track.provider, track.identifier);
+                print!("{}\n", e);
+            },
+        }
+    }
+}
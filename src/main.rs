@@ -28,9 +28,13 @@ use chrono::NaiveDateTime;
 extern crate pink_spider;
 
 use pink_spider::error::Error;
-use pink_spider::model::{Model, Feed, Entry, Track, Playlist, PlaylistTrack, Album, Artist, Enclosure, Provider, PaginatedCollection, Filter, FilterType};
+use pink_spider::auth::Authenticator;
+use pink_spider::rate_limit::{RateLimiter, ClientKey};
+use pink_spider::model::{Model, Feed, Entry, Track, Playlist, PlaylistTrack, Album, Artist, CanonicalArtist, Genre, Enclosure, Provider, PaginatedCollection, Page, Cursor, Query, Connection, conn};
 use pink_spider::get_env;
 use pink_spider::rss;
+use pink_spider::scraper;
+use pink_spider::search::{self, SearchType};
 
 const DEFAULT_PER_PAGE: i64 = 25;
 
@@ -40,44 +44,30 @@ fn to_err<E>(e: E) -> Error
 }
 
 pub fn index<'a, T: Model<'a>>(req: &mut Request) -> IronResult<Response> {
-    let (page, per_page) = pagination_params(req);
-    let items = if let Ok(q) = param_as_string(req, "query") {
-        let q = format!("%{}%", q);
-        let filter = Filter {
-            filter_type: FilterType::Contains,
-            field: T::search_prop(),
-            value: &q,
-        };
-        T::find(page, per_page, Some(filter))
-    } else if let Ok(q) = param_as_string(req, "type") {
-        if q == "active" {
-            T::find(page, per_page, Some(Filter {
-                filter_type: FilterType::GreaterThan,
-                field: "velocity",
-                value: &0.0,
-            }))
-        } else {
-            T::find(page, per_page, None)
-        }
-    } else {
-        T::find(page, per_page, None)
-    };
+    let page  = pagination_params(req)?;
+    let query = query_params::<T>(req)?;
+    let conn  = conn()?;
+    let items = T::find(&*conn, &page, &query)?;
     let body  = serde_json::to_string(&items).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
 
 pub fn index_entries(req: &mut Request) -> IronResult<Response> {
     pub fn index_entries2(req: &mut Request) -> Result<Response, Error> {
-        let (page, per_page) = pagination_params(req);
+        let page             = pagination_params(req)?;
         let url              = param_as_string(req, "feed_url");
         let newer_than       = param_as_string(req, "newer_than");
+        let conn             = conn()?;
         let entries = if let (Ok(url), Ok(newer_than)) = (url, newer_than) {
-            let feed = Feed::find_by_url(&url)?;
+            let feed = Feed::find_by_url(&*conn, &url)?;
             let newer_than = newer_than.parse::<i64>()
                 .map(|t| NaiveDateTime::from_timestamp(t, 0)).ok();
-            Entry::find_by_feed_id(feed.id, newer_than, page, per_page)
+            Entry::find_by_feed_id(&*conn, feed.id, newer_than, &page)?
         } else {
-            Entry::find(page, per_page, None)
+            let query       = query_params::<Entry>(req)?;
+            let mut entries = Entry::find(&*conn, &page, &query)?;
+            Entry::set_relations(&*conn, &mut entries.items)?;
+            entries
         };
         let body = serde_json::to_string(&entries).map_err(to_err)?;
         Ok(Response::with((status::Ok, application_json(), body)))
@@ -86,17 +76,19 @@ pub fn index_entries(req: &mut Request) -> IronResult<Response> {
 }
 
 pub fn index_entries_by_feed(req: &mut Request) -> IronResult<Response> {
-    let (page, per_page) = pagination_params(req);
+    let page             = pagination_params(req)?;
     let ref id           = req.extensions.get::<Router>().unwrap().find("id").unwrap();
-    let feed             = Feed::find_by_id(id)?;
-    let entries          = Entry::find_by_feed_id(feed.id, None, page, per_page);
+    let conn             = conn()?;
+    let feed             = Feed::find_by_id(&*conn, id)?;
+    let entries          = Entry::find_by_feed_id(&*conn, feed.id, None, &page)?;
     let body             = serde_json::to_string(&entries).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
 
 pub fn mget<'a, T: Model<'a>>(req: &mut Request) -> IronResult<Response> {
     let ids              = params_as_uuid_array(req)?;
-    let items            = T::mget(ids)?;
+    let conn             = conn()?;
+    let items            = T::mget(&*conn, ids)?;
     let body             = serde_json::to_string(&items).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
@@ -117,13 +109,9 @@ fn params_as_uuid_array(req: &mut Request) -> IronResult<Vec<Uuid>> {
 pub fn index_by_entry<'a, T: Enclosure<'a>>(req: &mut Request) -> IronResult<Response> {
     let ref entry_id = req.extensions.get::<Router>().unwrap().find("entry_id").unwrap();
     let uuid = Uuid::parse_str(entry_id).map_err(|_| Error::Unprocessable)?;
-    let items = T::find_by_entry_id(uuid);
-    let col = PaginatedCollection {
-        page:     0,
-        per_page: items.len() as i64,
-        total:    items.len() as i64,
-        items:    items,
-    };
+    let conn  = conn()?;
+    let items = T::find_by_entry_id(&*conn, uuid);
+    let col = PaginatedCollection::all(items);
     let body = serde_json::to_string(&col).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
@@ -131,23 +119,107 @@ pub fn index_by_entry<'a, T: Enclosure<'a>>(req: &mut Request) -> IronResult<Res
 pub fn index_tracks_by_playlist(req: &mut Request) -> IronResult<Response> {
     let ref id = req.extensions.get::<Router>().unwrap().find("playlist_id").unwrap();
     let uuid   = Uuid::parse_str(id).map_err(|_| Error::Unprocessable)?;
-    let map  = PlaylistTrack::find_by_playlist_ids(vec![uuid])?;
+    let conn = conn()?;
+    let map  = PlaylistTrack::find_by_playlist_ids(&*conn, vec![uuid])?;
     let items = map.get(&uuid).unwrap().clone();
-    let col = PaginatedCollection {
-        page:     0,
-        per_page: items.len() as i64,
-        total:    items.len() as i64,
-        items:    items,
+    let col = PaginatedCollection::all(items);
+    let body = serde_json::to_string(&col).map_err(to_err)?;
+    Ok(Response::with((status::Ok, application_json(), body)))
+}
+
+pub fn merge_artists(req: &mut Request) -> IronResult<Response> {
+    let ref id      = req.extensions.get::<Router>().unwrap().find("id").unwrap().to_string();
+    let ids         = params_as_uuid_array(req)?;
+    let conn        = conn()?;
+    let mut artists = vec![Artist::find_by_id(&*conn, id)?];
+    artists.append(&mut Artist::mget(&*conn, ids)?);
+    let canonical_artist = CanonicalArtist::merge(&*conn, &artists)?;
+    let body = serde_json::to_string(&canonical_artist).map_err(to_err)?;
+    Ok(Response::with((status::Ok, application_json(), body)))
+}
+
+pub fn unmerge_artist(req: &mut Request) -> IronResult<Response> {
+    let ref id     = req.extensions.get::<Router>().unwrap().find("id").unwrap();
+    let conn       = conn()?;
+    let mut artist = Artist::find_by_id(&*conn, id)?;
+    CanonicalArtist::unmerge(&*conn, &artist)?;
+    artist.canonical_artist_id = None;
+    let body = serde_json::to_string(&artist).map_err(to_err)?;
+    Ok(Response::with((status::Ok, application_json(), body)))
+}
+
+pub fn resolve(req: &mut Request) -> IronResult<Response> {
+    let url      = param_as_string(req, "url")?;
+    let conn     = conn()?;
+    let resolved = scraper::resolve(&*conn, &url)?;
+    let body     = serde_json::to_string(&resolved).map_err(to_err)?;
+    Ok(Response::with((status::Ok, application_json(), body)))
+}
+
+pub fn search_items(req: &mut Request) -> IronResult<Response> {
+    let q       = param_as_string(req, "q")?;
+    let types   = match param_as_string(req, "types") {
+        Ok(types) => SearchType::parse_list(&types)?,
+        Err(_)    => SearchType::all(),
     };
+    let page    = pagination_params(req)?;
+    let conn    = conn()?;
+    let results = search::search(&*conn, &q, &types, &page)?;
+    let body    = serde_json::to_string(&results).map_err(to_err)?;
+    Ok(Response::with((status::Ok, application_json(), body)))
+}
+
+pub fn index_equivalent_tracks(req: &mut Request) -> IronResult<Response> {
+    let ref id     = req.extensions.get::<Router>().unwrap().find("id").unwrap();
+    let conn       = conn()?;
+    let track      = Track::find_by_id(&*conn, id)?;
+    let mut items  = track.find_equivalents(&*conn)?;
+    Track::set_relations(&*conn, &mut items)?;
+    let col = PaginatedCollection::all(items);
     let body = serde_json::to_string(&col).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
 
+pub fn revive_track(req: &mut Request) -> IronResult<Response> {
+    let ref id    = req.extensions.get::<Router>().unwrap().find("id").unwrap();
+    let conn      = conn()?;
+    let mut track = Track::find_by_id(&*conn, id)?;
+    let result    = track.revive().fetch_props(&*conn);
+    track.save(&*conn)?;
+    result?;
+    let mut tracks = vec![track];
+    Track::set_relations(&*conn, &mut tracks)?;
+    let body = serde_json::to_string(&tracks[0]).map_err(to_err)?;
+    Ok(Response::with((status::Ok, application_json(), body)))
+}
+
+pub fn revive_album(req: &mut Request) -> IronResult<Response> {
+    let ref id    = req.extensions.get::<Router>().unwrap().find("id").unwrap();
+    let conn      = conn()?;
+    let mut album = Album::find_by_id(&*conn, id)?;
+    let result    = album.revive().fetch_props(&*conn);
+    album.save(&*conn)?;
+    result?;
+    let body = serde_json::to_string(&album).map_err(to_err)?;
+    Ok(Response::with((status::Ok, application_json(), body)))
+}
+
+pub fn index_tracks_by_genre(req: &mut Request) -> IronResult<Response> {
+    let page             = pagination_params(req)?;
+    let ref id           = req.extensions.get::<Router>().unwrap().find("id").unwrap();
+    let conn             = conn()?;
+    let genre            = Genre::find_by_id(&*conn, id)?;
+    let tracks           = Track::find_by_genre(&*conn, genre.id, &page)?;
+    let body             = serde_json::to_string(&tracks).map_err(to_err)?;
+    Ok(Response::with((status::Ok, application_json(), body)))
+}
+
 pub fn legacy_playlistify(req: &mut Request) -> IronResult<Response> {
     pub fn playlistify2(req: &mut Request) -> Result<Response, Error> {
         let url         = param_as_string(req, "url")?;
         let force       = param_as_string(req, "force").unwrap_or("false".to_string());
-        let mut entry   = find_or_playlistify_entry(&url, &force == "true")?;
+        let conn        = conn()?;
+        let mut entry   = find_or_playlistify_entry(&*conn, &url, &force == "true")?;
         entry.tracks    = entry.tracks
             .iter()
             .filter(|t| t.provider == Provider::YouTube || t.provider == Provider::SoundCloud)
@@ -163,28 +235,29 @@ pub fn playlistify(req: &mut Request) -> IronResult<Response> {
     pub fn playlistify2(req: &mut Request) -> Result<Response, Error> {
         let url         = param_as_string(req, "url")?;
         let force       = param_as_string(req, "force").unwrap_or("false".to_string());
-        let entry       = find_or_playlistify_entry(&url, &force == "true")?;
+        let conn        = conn()?;
+        let entry       = find_or_playlistify_entry(&*conn, &url, &force == "true")?;
         let body        = serde_json::to_string(&entry).map_err(to_err)?;
         Ok(Response::with((status::Ok, application_json(), body)))
     }
     playlistify2(req).map_err(|err| IronError::from(err))
 }
 
-pub fn find_or_playlistify_entry(url: &str, force: bool) -> Result<Entry, Error> {
-    match Entry::find_by_url(url) {
+pub fn find_or_playlistify_entry(conn: &Connection, url: &str, force: bool) -> Result<Entry, Error> {
+    match Entry::find_by_url(conn, url) {
         Ok(mut entry) => {
             println!("Get entry from database cache: {}", url);
             if force {
                 println!("Update entry: {}", url);
-                entry.playlistify()?;
+                entry.playlistify(conn)?;
                 Ok(entry)
             } else {
                 Ok(entry)
             }
         },
         Err(_) => {
-            let mut entry = Entry::create_by_url(url.to_string())?;
-            let _ = entry.playlistify();
+            let mut entry = Entry::create_by_url(conn, url.to_string())?;
+            let _ = entry.playlistify(conn);
             println!("Create new entry to database cache: {}", url);
             Ok(entry)
         },
@@ -192,19 +265,23 @@ pub fn find_or_playlistify_entry(url: &str, force: bool) -> Result<Entry, Error>
 }
 
 pub fn update_entry(req: &mut Request) -> IronResult<Response> {
-    let mut entry = Entry::find_by_id(&query_as_string(req, "id"))?;
-    entry.playlistify()?;
-    entry.save()?;
+    let conn        = conn()?;
+    let mut entries = vec![Entry::find_by_id(&*conn, &query_as_string(req, "id"))?];
+    Entry::set_relations(&*conn, &mut entries)?;
+    let mut entry = entries.remove(0);
+    entry.playlistify(&*conn)?;
+    entry.save(&*conn)?;
     let body = serde_json::to_string(&entry).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
 
 pub fn update<'a, T: Enclosure<'a>>(req: &mut Request) -> IronResult<Response> {
-    let mut enclosure = T::find_by_id(&query_as_string(req, "id"))?;
+    let conn          = conn()?;
+    let mut enclosure = T::find_by_id(&*conn, &query_as_string(req, "id"))?;
     let map = req.get_ref::<params::Params>().map_err(to_err)?;
-    let _ = enclosure.fetch_props();
+    let _ = enclosure.fetch_props(&*conn);
     enclosure.update_attributes(map);
-    enclosure.save()?;
+    enclosure.save(&*conn)?;
     let body = serde_json::to_string(&enclosure).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
@@ -213,15 +290,17 @@ pub fn show<'a, T: Enclosure<'a>>(req: &mut Request) -> IronResult<Response> {
     let provider   = req.extensions.get::<Router>().unwrap().find("provider").unwrap();
     let identifier = req.extensions.get::<Router>().unwrap().find("id").unwrap();
     let p = &Provider::new(provider.to_string());
-    let enclosure = T::find_by(p, identifier)?;
+    let conn      = conn()?;
+    let enclosure = T::find_by(&*conn, p, identifier)?;
     let body      = serde_json::to_string(&enclosure).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
 
 pub fn show_by_id<'a, T: Model<'a>>(req: &mut Request) -> IronResult<Response> {
     let ref id = req.extensions.get::<Router>().unwrap().find("id").unwrap();
-    let mut enclosures = vec![T::find_by_id(id)?];
-    T::set_relations(&mut enclosures)?;
+    let conn           = conn()?;
+    let mut enclosures = vec![T::find_by_id(&*conn, id)?];
+    T::set_relations(&*conn, &mut enclosures)?;
     let body      = serde_json::to_string(&enclosures[0]).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
@@ -232,13 +311,16 @@ pub fn create<'a, T: Enclosure<'a>>(req: &mut Request) -> IronResult<Response> {
     let owner_id       = param_as_string(req, "owner_id").ok();
     let url            = param_as_string(req, "url").ok();
     let p              = Provider::new(provider.to_string());
-    let mut enclosure  = T::find_or_create(p, identifier.to_string())?;
+    let conn           = conn()?;
+    let mut enclosure  = T::find_or_create(&*conn, p, identifier.to_string())?;
     enclosure.set_owner_id(owner_id);
     if let Some(url) = url {
         enclosure.set_url(url);
     }
-    enclosure.fetch_props()?;
-    enclosure.save()?;
+    // the failure is saved before it is returned
+    let result = enclosure.fetch_props(&*conn);
+    enclosure.save(&*conn)?;
+    result?;
     let body = serde_json::to_string(&enclosure).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
 }
@@ -252,10 +334,11 @@ pub fn create_feed_by_url(req: &mut Request) -> IronResult<Response> {
         .ok_or(IronError::from(Error::Unprocessable))?;
     println!("--------------- Creating {:?} ---------------", url);
     let rss_feed = rss::fetch(url)?;
-    let mut item = Feed::find_or_create_by_url(url.to_string())?;
+    let conn     = conn()?;
+    let mut item = Feed::find_or_create_by_url(&*conn, url.to_string())?;
     item.update_props(rss_feed);
     println!("Result {:?}  {:?}", url, item);
-    item.save()?;
+    item.save(&*conn)?;
     println!("--------------- Created {:?} ---------------", url);
     let body = serde_json::to_string(&item).map_err(to_err)?;
     Ok(Response::with((status::Ok, application_json(), body)))
@@ -274,14 +357,28 @@ fn query_as_string(req: &mut Request, key: &str) -> String {
         .find(key).unwrap().to_string();
 }
 
-fn pagination_params(req: &mut Request) -> (i64, i64) {
+fn query_params<'a, T: Model<'a>>(req: &mut Request) -> Result<Query, Error> {
+    let map = req.get_ref::<params::Params>().map_err(to_err)?;
+    Query::from_params::<T>(map)
+}
+
+/// `cursor` is the `next_cursor` of the previous page, and `page` is used without it.
+/// The total is counted for the first page unless `total=false`, or for any page with `total=true`.
+fn pagination_params(req: &mut Request) -> Result<Page, Error> {
     let page = param_as_string(req, "page")
         .and_then(|v| v.to_string().parse::<i64>().map_err(to_err))
         .unwrap_or(0);
     let per_page = param_as_string(req, "per_page")
         .and_then(|v| v.to_string().parse::<i64>().map_err(to_err))
         .unwrap_or(DEFAULT_PER_PAGE);
-    (page, per_page)
+    let mut page = match param_as_string(req, "cursor") {
+        Ok(ref cursor) if !cursor.is_empty() => Page::after(Cursor::decode(cursor)?, per_page),
+        _                                    => Page::new(page, per_page),
+    };
+    if let Ok(total) = param_as_string(req, "total") {
+        page.with_total = total == "true";
+    }
+    Ok(page)
 }
 
 fn application_json() -> Mime {
@@ -296,6 +393,8 @@ pub fn main() {
         web:                      get  "/*"                        => mount,
         legacy_playlistify:       get  "/playlistify"                    => legacy_playlistify,
         playlistify:              get  "/v1/playlistify"                 => playlistify,
+        resolve:                  get  "/v1/resolve"                     => resolve,
+        search:                   get  "/v1/search"                      => search_items,
 
         index_feeds:              get  "/v1/feeds"                       => index::<Feed>,
         show_feed:                get  "/v1/feeds/:id"                   => show_by_id::<Feed>,
@@ -313,6 +412,11 @@ pub fn main() {
         create_artist:            post "/v1/artists"                     => create::<Artist>,
         update_artist:            post "/v1/artists/:id"                 => update::<Artist>,
         index_artists:            get  "/v1/artists"                     => index::<Artist>,
+        merge_artists:            post "/v1/artists/:id/merge"           => merge_artists,
+        unmerge_artist:           post "/v1/artists/:id/unmerge"         => unmerge_artist,
+
+        show_canonical_artist:    get  "/v1/canonical_artists/:id"       => show_by_id::<CanonicalArtist>,
+        index_canonical_artists:  get  "/v1/canonical_artists"           => index::<CanonicalArtist>,
 
         show_track_by_id:         get  "/v1/tracks/:id"                  => show_by_id::<Track>,
         show_track:               get  "/v1/tracks/:provider/:id"        => show::<Track>,
@@ -322,6 +426,8 @@ pub fn main() {
         index_tracks:             get  "/v1/tracks"                      => index::<Track>,
         index_tracks_by_entry:    get  "/v1/entries/:entry_id/tracks"    => index_by_entry::<Track>,
         index_tracks_by_playlist: get  "/v1/playlists/:playlist_id/tracks"  => index_tracks_by_playlist,
+        index_equivalent_tracks:  get  "/v1/tracks/:id/equivalents"      => index_equivalent_tracks,
+        revive_track:             post "/v1/tracks/:id/revive"           => revive_track,
 
         show_playlist_by_id:      get  "/v1/playlists/:id"               => show_by_id::<Playlist>,
         show_playlist:            get  "/v1/playlists/:provider/:id"     => show::<Playlist>,
@@ -338,6 +444,10 @@ pub fn main() {
         update_album:             post "/v1/albums/:id"                  => update::<Album>,
         index_albums:             get  "/v1/albums"                      => index::<Album>,
         index_albums_by_entry:    get  "/v1/entries/:entry_id/albums"    => index_by_entry::<Album>,
+        revive_album:             post "/v1/albums/:id/revive"           => revive_album,
+
+        index_genres:             get  "/v1/genres"                      => index::<Genre>,
+        index_tracks_by_genre:    get  "/v1/genres/:id/tracks"           => index_tracks_by_genre,
     );
     let port_str = match get_env::var("PORT") {
         Some(n) => n,
@@ -352,5 +462,9 @@ pub fn main() {
     };
     println!("PORT {}", port_str);
     let ip = Ipv4Addr::new(0, 0, 0, 0);
-    Iron::new(router).http(SocketAddrV4::new(ip, port)).unwrap();
+    let mut chain = Chain::new(router);
+    chain.link_before(RateLimiter::from_env(ClientKey::Address));
+    chain.link_before(Authenticator::from_env());
+    chain.link_before(RateLimiter::from_env(ClientKey::ApiKey));
+    Iron::new(chain).http(SocketAddrV4::new(ip, port)).unwrap();
 }
@@ -0,0 +1,216 @@
+use regex::Regex;
+use model::Track;
+
+/// Durations of the same song differ a little between providers.
+static DURATION_TOLERANCE: i32 = 5;
+
+/// Words that channels and accounts append to the name of an artist,
+/// e.g. "corneliusofficial", "DaftPunkVEVO" or "Cornelius - Topic".
+static ARTIST_NAME_SUFFIXES: [&'static str; 5] = ["official",
+                                                  "vevo",
+                                                  "topic",
+                                                  "channel",
+                                                  "music"];
+
+lazy_static! {
+    static ref BRACKETS: Regex = Regex::new(r"[\(\[【][^\)\]】]*[\)\]】]").unwrap();
+    static ref FEATURING: Regex = Regex::new(r"(?i)\s(feat|ft|featuring)\.?\s.*$").unwrap();
+    static ref SEPARATOR: Regex = Regex::new(r"\s[-–—|/]\s").unwrap();
+}
+
+/// This function returns an isrc without hyphens, or None if it is not valid.
+pub fn normalize_isrc(isrc: &str) -> Option<String> {
+    let isrc = isrc.chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+    if isrc.len() == 12 {
+        Some(isrc)
+    } else {
+        None
+    }
+}
+
+/// This function lowercases a text and replaces the symbols with a space,
+/// so that "Don't Stop (Remastered)" and "dont stop" are compared loosely.
+pub fn normalize(text: &str) -> String {
+    let text = BRACKETS.replace_all(text, " ");
+    let text = FEATURING.replace_all(&text, "");
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// A title like "Artist - Title (Official Video)" is split into its parts,
+/// because video titles often contain the artist name.
+pub fn title_variants(title: &str) -> Vec<String> {
+    let mut variants = vec![normalize(title)];
+    for part in SEPARATOR.split(&BRACKETS.replace_all(title, " ")) {
+        let part = normalize(part);
+        if !variants.contains(&part) {
+            variants.push(part);
+        }
+    }
+    variants.into_iter().filter(|v| !v.is_empty()).collect()
+}
+
+pub fn artist_names(track: &Track) -> Vec<String> {
+    let mut names = track.artists.clone().unwrap_or(vec![]).iter()
+        .map(|a| normalize(&a.name))
+        .collect::<Vec<String>>();
+    if let Some(ref owner_name) = track.owner_name {
+        names.push(normalize(owner_name));
+    }
+    names.into_iter().filter(|n| !n.is_empty()).collect()
+}
+
+/// This function returns a key of an artist name without spaces and suffixes.
+pub fn artist_name_key(name: &str) -> String {
+    let mut key = normalize(name).replace(" ", "");
+    let mut stripped = true;
+    while stripped {
+        stripped = false;
+        for suffix in ARTIST_NAME_SUFFIXES.iter() {
+            if key.len() > suffix.len() && key.ends_with(suffix) {
+                let len = key.len() - suffix.len();
+                key.truncate(len);
+                stripped = true;
+            }
+        }
+    }
+    key
+}
+
+/// This function tells whether two artist names are the same except spaces, symbols and cases.
+/// The suffixes aren't stripped, because they can be a part of the names, e.g. "Roxy Music".
+pub fn is_same_artist_name(a: &str, b: &str) -> bool {
+    let key_a = normalize(a).replace(" ", "");
+    !key_a.is_empty() && key_a == normalize(b).replace(" ", "")
+}
+
+/// This function returns the similarity of two artist names from 0.0 to 1.0,
+/// the dice coefficient of the character bigrams of their keys.
+pub fn artist_name_similarity(a: &str, b: &str) -> f64 {
+    let key_a = artist_name_key(a);
+    let key_b = artist_name_key(b);
+    if key_a.is_empty() || key_b.is_empty() {
+        return 0.0;
+    }
+    if key_a == key_b {
+        return 1.0;
+    }
+    let bigrams_a = bigrams(&key_a);
+    let mut bigrams_b = bigrams(&key_b);
+    if bigrams_a.is_empty() || bigrams_b.is_empty() {
+        return 0.0;
+    }
+    let total = bigrams_a.len() + bigrams_b.len();
+    let mut matches = 0;
+    for bigram in bigrams_a.iter() {
+        if let Some(i) = bigrams_b.iter().position(|b| b == bigram) {
+            bigrams_b.remove(i);
+            matches += 1;
+        }
+    }
+    2.0 * matches as f64 / total as f64
+}
+
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let chars = text.chars().collect::<Vec<char>>();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+pub fn is_same_duration(a: i32, b: i32) -> bool {
+    a <= 0 || b <= 0 || (a - b).abs() <= DURATION_TOLERANCE
+}
+
+fn is_same_title(a: &Track, b: &Track, names: &Vec<String>) -> bool {
+    let variants = title_variants(&b.title);
+    title_variants(&a.title).iter()
+        .filter(|v| !names.contains(v))
+        .any(|v| variants.contains(v))
+}
+
+fn is_same_artist(a: &Track, b: &Track) -> bool {
+    let names_a = artist_names(a);
+    let names_b = artist_names(b);
+    let title_a = format!(" {} ", normalize(&a.title));
+    let title_b = format!(" {} ", normalize(&b.title));
+    names_a.iter().any(|n| names_b.contains(n) || title_b.contains(&format!(" {} ", n))) ||
+        names_b.iter().any(|n| title_a.contains(&format!(" {} ", n)))
+}
+
+/// This function decides if two tracks are the same song.
+/// The isrc is compared first, then the title, the artist and the duration.
+pub fn is_equivalent(a: &Track, b: &Track) -> bool {
+    if let (&Some(ref isrc_a), &Some(ref isrc_b)) = (&a.isrc, &b.isrc) {
+        if isrc_a == isrc_b {
+            return true;
+        }
+    }
+    let mut names = artist_names(a);
+    names.append(&mut artist_names(b));
+    is_same_duration(a.duration, b.duration) &&
+        is_same_title(a, b, &names) &&
+        is_same_artist(a, b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use model::{Enclosure, Provider};
+
+    fn track(provider: Provider, title: &str, owner_name: &str, duration: i32) -> Track {
+        let mut track      = Track::new(provider, title.to_string());
+        track.title        = title.to_string();
+        track.owner_name   = Some(owner_name.to_string());
+        track.duration     = duration;
+        track
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize_isrc("us-um7-13-00001"), Some("USUM71300001".to_string()));
+        assert_eq!(normalize_isrc("unknown"), None);
+        assert_eq!(normalize("Don't Stop (Remastered 2011)"), "don t stop");
+        assert_eq!(normalize("Get Lucky feat. Pharrell Williams"), "get lucky");
+        assert_eq!(title_variants("Daft Punk - Get Lucky (Official Audio)"),
+                   vec!["daft punk get lucky", "daft punk", "get lucky"]);
+    }
+
+    #[test]
+    fn test_artist_name_similarity() {
+        assert_eq!(artist_name_key("corneliusofficial"), "cornelius");
+        assert_eq!(artist_name_key("Cornelius - Topic"), "cornelius");
+        assert_eq!(artist_name_key("DaftPunkVEVO"), "daftpunk");
+        assert_eq!(artist_name_key("Music"), "music");
+        assert_eq!(artist_name_similarity("The Killers", "thekillersVEVO"), 1.0);
+        assert!(artist_name_similarity("The Killers", "The Killer") > 0.9);
+        assert!(artist_name_similarity("The Killers", "The Strokes") < 0.5);
+        assert_eq!(artist_name_similarity("Roxy Music", "Roxy"), 1.0);
+        assert!(is_same_artist_name("The Killers", "the killers"));
+        assert!(is_same_artist_name("Daft Punk", "DAFT-PUNK"));
+        assert!(!is_same_artist_name("Roxy Music", "Roxy"));
+        assert!(!is_same_artist_name("The Killers", "thekillersVEVO"));
+        assert!(!is_same_artist_name("!!!", "???"));
+    }
+
+    #[test]
+    fn test_is_equivalent() {
+        let spotify = track(Provider::Spotify, "Get Lucky - Radio Edit", "Daft Punk", 248);
+        let youtube = track(Provider::YouTube, "Daft Punk - Get Lucky (Official Audio) ft. Pharrell Williams", "DaftPunkVEVO", 0);
+        let deezer  = track(Provider::Deezer, "Get Lucky", "Daft Punk", 369);
+        let other   = track(Provider::YouTube, "Daft Punk - One More Time", "DaftPunkVEVO", 0);
+        assert!(is_equivalent(&spotify, &youtube));
+        assert!(!is_equivalent(&spotify, &deezer));
+        assert!(!is_equivalent(&spotify, &other));
+
+        let mut a = track(Provider::Spotify, "Get Lucky", "Daft Punk", 248);
+        let mut b = track(Provider::AppleMusic, "Get Lucky (feat. Pharrell Williams)", "Various Artists", 369);
+        a.isrc = normalize_isrc("USQX91300108");
+        b.isrc = normalize_isrc("USQX91300108");
+        assert!(is_equivalent(&a, &b));
+    }
+}
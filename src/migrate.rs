@@ -0,0 +1,52 @@
+extern crate pink_spider;
+
+use std::env;
+use std::process;
+use pink_spider::migration;
+use pink_spider::model::conn;
+
+static USAGE: &'static str = "Usage: migrate up [VERSION]
+       migrate down [STEPS]
+       migrate status";
+
+pub fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let conn = match conn() {
+        Ok(conn) => conn,
+        Err(e)   => exit(&format!("Failed to connect to database: {:?}", e)),
+    };
+    let command = args.get(0).map(|s| s.as_str()).unwrap_or("up");
+    let arg     = args.get(1);
+    match command {
+        "up" => {
+            let target = arg.map(|v| v.parse::<i64>().unwrap_or_else(|_| exit(USAGE)));
+            match migration::up(&*conn, target) {
+                Ok(migrations) => for m in migrations {
+                    println!("up   {}", m.name);
+                },
+                Err(e) => exit(&format!("Failed to migrate: {:?}", e)),
+            }
+        },
+        "down" => {
+            let steps = arg.map(|v| v.parse::<usize>().unwrap_or_else(|_| exit(USAGE))).unwrap_or(1);
+            match migration::down(&*conn, steps) {
+                Ok(migrations) => for m in migrations {
+                    println!("down {}", m.name);
+                },
+                Err(e) => exit(&format!("Failed to rollback: {:?}", e)),
+            }
+        },
+        "status" => match migration::status(&*conn) {
+            Ok(items) => for (m, applied) in items {
+                println!("{} {}", if applied { "up  " } else { "down" }, m.name);
+            },
+            Err(e) => exit(&format!("Failed to get status: {:?}", e)),
+        },
+        _ => exit(USAGE),
+    }
+}
+
+fn exit(message: &str) -> ! {
+    println!("{}", message);
+    process::exit(1)
+}
@@ -0,0 +1,216 @@
+use error::Error;
+use model::storage::{Backend, Connection};
+
+/// A schema migration that is embedded in the binaries from db/migrations.
+pub struct Migration {
+    pub version: i64,
+    pub name:    &'static str,
+    pub up:      &'static str,
+    pub down:    &'static str,
+    pub sqlite:  Option<&'static str>,
+}
+
+macro_rules! migration {
+    ($version:expr, $name:expr) => {
+        Migration {
+            version: $version,
+            name:    $name,
+            up:      include_str!(concat!("../db/migrations/", $name, "/up.sql")),
+            down:    include_str!(concat!("../db/migrations/", $name, "/down.sql")),
+            sqlite:  None,
+        }
+    };
+    ($version:expr, $name:expr, sqlite) => {
+        Migration {
+            sqlite: Some(include_str!(concat!("../db/migrations/", $name, "/sqlite.sql"))),
+            ..migration!($version, $name)
+        }
+    };
+}
+
+static MIGRATIONS: [Migration; 42] = [
+    migration!(0, "000_enable_uuid_ossp_extension"),
+    migration!(1, "001_create_tracks"),
+    migration!(2, "002_create_entries"),
+    migration!(3, "003_create_track_entries"),
+    migration!(4, "004_add_visual_to_entries"),
+    migration!(5, "005_add_properties_to_tracks"),
+    migration!(6, "006_change_desc_of_entries_to_text"),
+    migration!(7, "007_non_nullable_timestamps"),
+    migration!(8, "008_create_playlists"),
+    migration!(9, "009_create_playlist_entries"),
+    migration!(10, "010_create_albums"),
+    migration!(11, "011_create_album_entries"),
+    migration!(12, "012_create_artists"),
+    migration!(13, "013_create_track_artists"),
+    migration!(14, "014_create_album_artists"),
+    migration!(15, "015_add_audio_url_to_tracks"),
+    migration!(16, "016_create_album_tracks"),
+    migration!(17, "017_create_playlist_tracks"),
+    migration!(18, "018_timestamp_index"),
+    migration!(19, "019_create_feeds"),
+    migration!(20, "020_add_properties_to_entries"),
+    migration!(21, "021_add_text_to_entries"),
+    migration!(22, "022_add_velocity_to_playlists"),
+    migration!(23, "023_non_nullable_associations_timestamps"),
+    migration!(24, "024_add_tags_to_tracks"),
+    migration!(25, "025_create_genres"),
+    migration!(26, "026_create_track_genres"),
+    migration!(27, "027_create_track_moods"),
+    migration!(28, "028_create_track_tempos"),
+    migration!(29, "029_create_album_genres"),
+    migration!(30, "030_create_artist_genres"),
+    migration!(31, "031_add_isrc_to_tracks"),
+    migration!(32, "032_create_track_links"),
+    migration!(33, "033_create_canonical_artists"),
+    migration!(34, "034_add_canonical_artist_id_to_artists"),
+    migration!(35, "035_add_failures_to_tracks_and_albums", sqlite),
+    migration!(36, "036_add_states_to_enclosures", sqlite),
+    migration!(37, "037_create_search_tokens", sqlite),
+    migration!(38, "038_create_api_keys", sqlite),
+    migration!(39, "039_add_linked_at_to_tracks", sqlite),
+    migration!(40, "040_add_enriched_at_to_tracks", sqlite),
+    migration!(41, "041_add_normalized_title_to_tracks", sqlite),
+];
+
+/// SQLite databases start from this schema instead of the migrations up to
+/// SQLITE_SCHEMA_VERSION, which are written for Postgres.
+/// The later migrations need their own sqlite.sql.
+static SQLITE_SCHEMA: &'static str = include_str!("../db/sqlite/schema.sql");
+static SQLITE_SCHEMA_VERSION: i64 = 34;
+
+/// The versions are stored in the same way as ActiveRecord,
+/// so that the databases migrated with rake can be migrated with this.
+static CREATE_SCHEMA_MIGRATIONS: &'static str = "
+CREATE TABLE IF NOT EXISTS schema_migrations (
+  version character varying NOT NULL PRIMARY KEY
+)";
+
+pub fn migrations() -> &'static [Migration] {
+    &MIGRATIONS
+}
+
+pub fn applied_versions(conn: &Connection) -> Result<Vec<i64>, Error> {
+    conn.batch_execute(CREATE_SCHEMA_MIGRATIONS)?;
+    let stmt = conn.prepare("SELECT version FROM schema_migrations")?;
+    let rows = stmt.query(&[])?;
+    let mut versions = rows.iter()
+        .filter_map(|row| row.get::<String>(0).parse::<i64>().ok())
+        .collect::<Vec<i64>>();
+    versions.sort();
+    Ok(versions)
+}
+
+/// This function applies the pending migrations up to the version, or all of them.
+/// Each migration runs in its own transaction.
+pub fn up(conn: &Connection, target: Option<i64>) -> Result<Vec<&'static Migration>, Error> {
+    let mut applied = applied_versions(conn)?;
+    let mut migrated = vec![];
+    if conn.backend() == Backend::Sqlite && applied.is_empty() {
+        applied = load_sqlite_schema(conn)?;
+    }
+    for migration in migrations().iter() {
+        if applied.contains(&migration.version) {
+            continue;
+        }
+        if target.map(|v| migration.version > v).unwrap_or(false) {
+            break;
+        }
+        let sql = match conn.backend() {
+            Backend::Postgres => migration.up,
+            Backend::Sqlite   => match migration.sqlite {
+                Some(sql) => sql,
+                None      => return Err(Error::Database(format!("{} doesn't support sqlite", migration.name))),
+            },
+        };
+        let trans = conn.transaction()?;
+        trans.batch_execute(sql)?;
+        trans.execute("INSERT INTO schema_migrations (version) VALUES ($1)",
+                      &[&migration.version.to_string()])?;
+        trans.commit()?;
+        migrated.push(migration);
+    }
+    Ok(migrated)
+}
+
+fn load_sqlite_schema(conn: &Connection) -> Result<Vec<i64>, Error> {
+    let trans = conn.transaction()?;
+    trans.batch_execute(SQLITE_SCHEMA)?;
+    let versions = (0..SQLITE_SCHEMA_VERSION + 1).collect::<Vec<i64>>();
+    for version in versions.iter() {
+        trans.execute("INSERT INTO schema_migrations (version) VALUES ($1)",
+                      &[&version.to_string()])?;
+    }
+    trans.commit()?;
+    Ok(versions)
+}
+
+/// This function reverts the last applied migrations.
+/// It refuses SQLite databases because the down.sql files are written for Postgres,
+/// and the versions up to SQLITE_SCHEMA_VERSION come from a single schema file.
+/// A SQLite database is recreated instead.
+pub fn down(conn: &Connection, steps: usize) -> Result<Vec<&'static Migration>, Error> {
+    if conn.backend() == Backend::Sqlite {
+        return Err(Error::Database("migrations can't be reverted on sqlite".to_string()));
+    }
+    let applied = applied_versions(conn)?;
+    let mut reverted = vec![];
+    for migration in migrations().iter().rev() {
+        if reverted.len() >= steps {
+            break;
+        }
+        if !applied.contains(&migration.version) {
+            continue;
+        }
+        let trans = conn.transaction()?;
+        trans.batch_execute(migration.down)?;
+        trans.execute("DELETE FROM schema_migrations WHERE version = $1",
+                      &[&migration.version.to_string()])?;
+        trans.commit()?;
+        reverted.push(migration);
+    }
+    Ok(reverted)
+}
+
+pub fn status(conn: &Connection) -> Result<Vec<(&'static Migration, bool)>, Error> {
+    let applied = applied_versions(conn)?;
+    Ok(migrations().iter()
+       .map(|m| (m, applied.contains(&m.version)))
+       .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{migrations, SQLITE_SCHEMA_VERSION};
+    #[cfg(feature = "sqlite")]
+    use super::{up, down, applied_versions};
+    #[cfg(feature = "sqlite")]
+    use model::storage::connect_memory;
+    #[cfg(feature = "sqlite")]
+    use error::Error;
+
+    #[test]
+    fn test_migrations() {
+        let migrations = migrations();
+        for (i, migration) in migrations.iter().enumerate() {
+            assert_eq!(migration.version, i as i64);
+            assert!(migration.name.starts_with(&format!("{:03}_", i)));
+            assert!(!migration.up.trim().is_empty());
+            assert!(!migration.down.trim().is_empty());
+            assert!(migration.version <= SQLITE_SCHEMA_VERSION || migration.sqlite.is_some());
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_down_on_sqlite() {
+        let conn = connect_memory().unwrap();
+        up(&*conn, None).unwrap();
+        let versions = applied_versions(&*conn).unwrap();
+        match down(&*conn, 1) {
+            Err(Error::Database(_)) => (),
+            _                       => assert!(false),
+        }
+        assert_eq!(applied_versions(&*conn).unwrap(), versions);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use reqwest::header::Connection;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use http;
+use error::Error;
+use model::Provider;
+
+static BASE_URL: &'static str = "https://api.mixcloud.com";
+static THUMBNAIL_SIZE: &'static str = "large";
+static ARTWORK_SIZE:   &'static str = "extra_large";
+
+pub static EMBED: &'static str = r"mixcloud.com/widget/iframe/?\?(?:.*&)?feed=(?:https?://(?:www\.)?mixcloud\.com)?(/[^/?&]+/(?:[^/?&]+/?)?)";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cloudcast {
+    pub key:          String,
+    pub url:          String,
+    pub name:         String,
+    pub slug:         String,
+    pub description:  Option<String>,
+    pub tags:         Vec<Tag>,
+    pub user:         User,
+    pub pictures:     BTreeMap<String, String>,
+    pub audio_length: Option<i32>,
+    pub created_time: String,
+    pub updated_time: Option<String>,
+    pub play_count:   Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tag {
+    pub key:  String,
+    pub url:  String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct User {
+    pub key:      String,
+    pub url:      String,
+    pub name:     String,
+    pub username: String,
+    pub pictures: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Paging {
+    pub next:     Option<String>,
+    pub previous: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CloudcastList {
+    pub data:   Vec<Cloudcast>,
+    pub paging: Option<Paging>,
+}
+
+pub trait HasPictures {
+    fn get_pictures(&self) -> &BTreeMap<String, String>;
+    fn get_thumbnail_url(&self) -> Option<String> {
+        self.get_pictures().get(THUMBNAIL_SIZE).map(|url| url.to_string())
+    }
+    fn get_artwork_url(&self) -> Option<String> {
+        self.get_pictures().get(ARTWORK_SIZE).map(|url| url.to_string())
+    }
+}
+
+impl HasPictures for Cloudcast {
+    fn get_pictures(&self) -> &BTreeMap<String, String> {
+        &self.pictures
+    }
+}
+
+impl HasPictures for User {
+    fn get_pictures(&self) -> &BTreeMap<String, String> {
+        &self.pictures
+    }
+}
+
+impl Cloudcast {
+    pub fn tag_names(&self) -> Vec<String> {
+        self.tags.iter().map(|t| t.name.to_string()).collect()
+    }
+}
+
+/// This function parses the feed of an embedded widget url.
+/// A cloudcast feed is returned as its key, e.g. `/spartacus/party-time/`,
+/// and a user feed as `/spartacus/`.
+pub fn parse_embed_url(url: &str) -> Option<String> {
+    Regex::new(EMBED).ok()
+        .and_then(|re| re.captures(url).map(|cap| cap[1].to_string()))
+        .map(|key| if key.ends_with("/") { key } else { format!("{}/", key) })
+}
+
+/// This function splits a key into a username and an optional cloudcast slug.
+pub fn parse_key(key: &str) -> Option<(String, Option<String>)> {
+    let parts = key.split('/').filter(|s| s.len() > 0).collect::<Vec<&str>>();
+    match parts.len() {
+        1 => Some((parts[0].to_string(), None)),
+        2 => Some((parts[0].to_string(), Some(parts[1].to_string()))),
+        _ => None,
+    }
+}
+
+pub fn fetch_cloudcast(key: &str) -> Result<Cloudcast, Error> {
+    fetch(&format!("{}/{}/", BASE_URL, key.trim_matches('/')))
+}
+
+pub fn fetch_user(username: &str) -> Result<User, Error> {
+    fetch(&format!("{}/{}/", BASE_URL, username))
+}
+
+pub fn fetch_user_cloudcasts(username: &str) -> Result<CloudcastList, Error> {
+    fetch(&format!("{}/{}/cloudcasts/", BASE_URL, username))
+}
+
+fn fetch<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
+    http::get(url)
+        .header(Connection::close())
+        .fetch_json(Provider::Mixcloud)
+}
+
+#[cfg(test)]
+mod test {
+    use error::ProviderError;
+    use http::{ReplayTransport, set_transport, reset_transport};
+    use super::*;
+
+    #[test]
+    fn test_parse_embed_url() {
+        let embed = "https://www.mixcloud.com/widget/iframe/?hide_cover=1&feed=https://www.mixcloud.com/spartacus/party-time/";
+        assert_eq!(parse_embed_url(embed), Some("/spartacus/party-time/".to_string()));
+        let embed = "https://www.mixcloud.com/widget/iframe/?feed=/spartacus/party-time&light=1";
+        assert_eq!(parse_embed_url(embed), Some("/spartacus/party-time/".to_string()));
+        let embed = "https://www.mixcloud.com/widget/iframe/?feed=https://www.mixcloud.com/spartacus/";
+        assert_eq!(parse_embed_url(embed), Some("/spartacus/".to_string()));
+        assert_eq!(parse_embed_url("https://www.mixcloud.com/spartacus/party-time/"), None);
+
+        assert_eq!(parse_key("/spartacus/party-time/"),
+                   Some(("spartacus".to_string(), Some("party-time".to_string()))));
+        assert_eq!(parse_key("/spartacus/"), Some(("spartacus".to_string(), None)));
+        assert_eq!(parse_key("/"), None);
+    }
+
+    #[test]
+    fn test_fetch_cloudcast() {
+        set_transport(ReplayTransport);
+        let cloudcast = fetch_cloudcast("/spartacus/party-time/").unwrap();
+        assert_eq!(cloudcast.key, "/spartacus/party-time/");
+        assert_eq!(cloudcast.name, "Party Time");
+        assert_eq!(cloudcast.audio_length, Some(10794));
+        assert_eq!(cloudcast.tag_names(), vec!["House".to_string(), "Deep House".to_string()]);
+        assert_eq!(cloudcast.user.username, "spartacus");
+        assert_eq!(cloudcast.get_artwork_url(),
+                   Some("https://thumbnailer.mixcloud.com/unsafe/600x600/extaudio/a/b/c/d/e1f2.jpg".to_string()));
+        reset_transport();
+    }
+
+    #[test]
+    fn test_fetch_user() {
+        set_transport(ReplayTransport);
+        let user = fetch_user("spartacus").unwrap();
+        assert_eq!(user.name, "Spartacus");
+        assert!(user.get_thumbnail_url().is_some());
+
+        let list = fetch_user_cloudcasts("spartacus").unwrap();
+        assert_eq!(list.data.len(), 2);
+        assert_eq!(list.data[1].key, "/spartacus/late-night-session/");
+        assert!(list.paging.unwrap().next.is_some());
+
+        match fetch_user("unknown") {
+            Err(Error::Provider(Provider::Mixcloud, ProviderError::NotFound)) => (),
+            r => panic!("unexpected result {:?}", r),
+        }
+        reset_transport();
+    }
+}
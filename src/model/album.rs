@@ -1,19 +1,25 @@
-use postgres;
+use super::{Connection, Row};
 use uuid::Uuid;
 use std::fmt;
+use std::collections::BTreeMap;
 use chrono::{NaiveDateTime, Utc};
 
 use apple_music;
 use spotify;
+use bandcamp;
+use deezer;
+use gracenote;
+use adapter;
 use error::Error;
-use super::{conn, Model};
+use super::Model;
 use model::provider::Provider;
-use model::state::State;
+use model::state::{State, DEAD_AFTER_NOT_FOUND};
 use model::enclosure::Enclosure;
 use model::track::Track;
 use model::artist::Artist;
+use model::genre::Genre;
 
-static PROPS: [&'static str; 14]  = ["id",
+static PROPS: [&'static str; 18]  = ["id",
                                      "provider",
                                      "identifier",
                                      "owner_id",
@@ -26,7 +32,11 @@ static PROPS: [&'static str; 14]  = ["id",
                                      "published_at",
                                      "created_at",
                                      "updated_at",
-                                     "state"];
+                                     "state",
+                                     "failure_count",
+                                     "missing_count",
+                                     "failed_at",
+                                     "last_failure"];
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Album {
@@ -44,8 +54,13 @@ pub struct Album {
     pub created_at:    NaiveDateTime,
     pub updated_at:    NaiveDateTime,
     pub state:         State,
+    pub failure_count: i32,
+    pub missing_count: i32,
+    pub failed_at:     Option<NaiveDateTime>,
+    pub last_failure:  Option<String>,
     pub tracks:        Vec<Track>,
     pub artists:       Option<Vec<Artist>>,
+    pub genres:        Option<Vec<Genre>>,
 }
 
 impl PartialEq for Album {
@@ -68,7 +83,16 @@ impl<'a> Model<'a> for Album {
             .map(|&p| format!("{}{}", prefix, p))
             .collect::<Vec<String>>().join(",")
     }
-    fn row_to_item(row: postgres::rows::Row) -> Album {
+    fn filter_fields() -> &'static [&'static str] {
+        &["provider", "state", "owner_id"]
+    }
+    fn sort_fields() -> &'static [&'static str] {
+        &["updated_at", "created_at", "published_at"]
+    }
+    fn published_field() -> Option<&'static str> {
+        Some("published_at")
+    }
+    fn row_to_item(row: &Row) -> Album {
         Album {
             id:            row.get(0),
             provider:      Provider::new(row.get(1)),
@@ -84,15 +108,20 @@ impl<'a> Model<'a> for Album {
             created_at:    row.get(11),
             updated_at:    row.get(12),
             state:         State::new(row.get(13)),
+            failure_count: row.get(14),
+            missing_count: row.get(15),
+            failed_at:     row.get(16),
+            last_failure:  row.get(17),
             tracks:        vec![],
             artists:       None,
+            genres:        None,
         }
     }
-    fn create(&self) -> Result<Album, Error> {
-        let conn = conn()?;
-        let stmt = conn.prepare("INSERT INTO albums (provider, identifier, url, title)
-                                      VALUES ($1, $2, $3, $4) RETURNING id")?;
-        let rows = stmt.query(&[&self.provider.to_string(), &self.identifier, &self.url, &self.title])?;
+    fn create(&self, conn: &Connection) -> Result<Album, Error> {
+        let stmt = conn.prepare("INSERT INTO albums (provider, identifier, url, title, state)
+                                      VALUES ($1, $2, $3, $4, $5) RETURNING id")?;
+        let rows = stmt.query(&[&self.provider.to_string(), &self.identifier, &self.url, &self.title,
+                                 &self.state.to_string()])?;
         let mut album = self.clone();
         for row in rows.iter() {
             album.id = row.get(0);
@@ -100,9 +129,10 @@ impl<'a> Model<'a> for Album {
         Ok(album)
     }
 
-    fn save(&mut self) -> Result<(), Error> {
+    /// This function updates the row with the connection, which can be a transaction,
+    /// e.g. the one that `Entry::add_enclosures` opens.
+    fn save(&mut self, conn: &Connection) -> Result<(), Error> {
         self.updated_at = Utc::now().naive_utc();
-        let conn = conn()?;
         let stmt = conn.prepare("UPDATE albums SET
                                       provider      = $2,
                                       identifier    = $3,
@@ -116,7 +146,11 @@ impl<'a> Model<'a> for Album {
                                       published_at  = $11,
                                       created_at    = $12,
                                       updated_at    = $13,
-                                      state         = $14
+                                      state         = $14,
+                                      failure_count = $15,
+                                      missing_count = $16,
+                                      failed_at     = $17,
+                                      last_failure  = $18
                                       WHERE id = $1")?;
         let result = stmt.query(&[&self.id,
                                   &self.provider.to_string(),
@@ -132,6 +166,10 @@ impl<'a> Model<'a> for Album {
                                   &self.created_at,
                                   &self.updated_at,
                                   &self.state.to_string(),
+                                  &self.failure_count,
+                                  &self.missing_count,
+                                  &self.failed_at,
+                                  &self.last_failure,
         ]);
         match result {
             Ok(_)  => Ok(()),
@@ -139,10 +177,11 @@ impl<'a> Model<'a> for Album {
         }
     }
 
-    fn set_relations(albums: &mut Vec<Album>) -> Result<(), Error> {
+    fn set_relations(conn: &Connection, albums: &mut Vec<Album>) -> Result<(), Error> {
         let ids: Vec<Uuid> = albums.iter().map(|i| i.id).collect();
-        let tracks_of_album = Track::find_by_albums(&ids)?;
-        let artists_of_album = Artist::find_by_albums(&ids)?;
+        let tracks_of_album = Track::find_by_albums(conn, &ids)?;
+        let artists_of_album = Artist::find_by_albums(conn, &ids)?;
+        let genres_of_album  = Genre::find_by_albums(conn, &ids)?;
         for album in albums {
             if let Some(ref mut tracks) = tracks_of_album.get(&album.id) {
                 album.tracks = tracks.clone()
@@ -150,6 +189,9 @@ impl<'a> Model<'a> for Album {
             if let Some(ref mut artists) = artists_of_album.get(&album.id) {
                 album.artists = Some(artists.clone())
             }
+            if let Some(ref mut genres) = genres_of_album.get(&album.id) {
+                album.genres = Some(genres.clone())
+            }
         }
         Ok(())
     }
@@ -171,9 +213,14 @@ impl<'a> Enclosure<'a> for Album {
             published_at:  Utc::now().naive_utc(),
             created_at:    Utc::now().naive_utc(),
             updated_at:    Utc::now().naive_utc(),
-            state:         State::Alive,
+            state:         State::Pending,
+            failure_count: 0,
+            missing_count: 0,
+            failed_at:     None,
+            last_failure:  None,
             tracks:        vec![],
             artists:       None,
+            genres:        None,
         }
     }
     fn set_url(&mut self, url: String) -> &mut Album {
@@ -184,57 +231,59 @@ impl<'a> Enclosure<'a> for Album {
         self.owner_id = owner_id;
         self
     }
-    fn fetch_props(&mut self) -> Result<(), Error> {
-        match self.provider {
-            Provider::AppleMusic => {
-                let country = apple_music::country(&self.url);
-                match apple_music::fetch_album(&self.identifier, &country) {
-                    Ok(album) => self.update_with_am_album(&album),
-                    Err(_)    => self.disable(),
-                }
+    fn fetch_props(&mut self, conn: &Connection) -> Result<(), Error> {
+        if let Some(adapter) = adapter::find(&self.provider) {
+            match adapter.fetch_album(conn, self) {
+                Ok(_)  => { self.recover(); },
+                Err(e) => {
+                    self.record_failure(&e);
+                    return Err(e);
+                },
             }
-            Provider::Spotify => match spotify::fetch_album(&self.identifier) {
-                Ok(album) => self.update_with_sp_album(&album),
-                Err(_)    => self.disable(),
-            },
-            _ => self,
-        };
-        match self.state {
-            State::Alive => Ok(()),
-            State::Dead  => Err(Error::NotFound),
+        }
+        if self.state.is_gone() {
+            Err(Error::NotFound)
+        } else {
+            Ok(())
         }
     }
-    fn find_by_entry_id(entry_id: Uuid) -> Vec<Album> {
-        let conn = conn().unwrap();
+    fn find_by_entry_ids(conn: &Connection, entry_ids: &[Uuid]) -> Result<BTreeMap<Uuid, Vec<Album>>, Error> {
         let stmt = conn.prepare(
-            &format!("SELECT {} FROM albums p LEFT JOIN album_entries pe
+            &format!("SELECT {}, pe.entry_id FROM albums p LEFT JOIN album_entries pe
                         ON p.id = pe.album_id
-                        WHERE pe.entry_id = $1
+                        WHERE pe.entry_id = ANY($1)
                         ORDER BY p.published_at DESC",
-                     Album::props_str("p."))).unwrap();
-        let rows = stmt.query(&[&entry_id]).unwrap();
-        Album::rows_to_items(rows)
+                     Album::props_str("p.")))?;
+        let rows = stmt.query(&[&entry_ids])?;
+        let mut items: BTreeMap<Uuid, Vec<Album>> = BTreeMap::new();
+        for id in entry_ids.iter() {
+            items.insert(*id, vec![]);
+        }
+        for row in rows.iter() {
+            let id: Uuid = row.get(PROPS.len());
+            if let Some(albums) = items.get_mut(&id) {
+                albums.push(Self::row_to_item(row))
+            }
+        }
+        Ok(items)
     }
 }
 
 impl Album {
-    pub fn find_all() -> Vec<Album> {
-        let conn = conn().unwrap();
+    pub fn find_all(conn: &Connection) -> Vec<Album> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM albums ORDER BY albums.published_at DESC",
                      Album::props_str(""))).unwrap();
         let rows = stmt.query(&[]).unwrap();
         Album::rows_to_items(rows)
     }
-    fn add_track(&mut self, track: &Track) -> Result<(), Error> {
-        let conn = conn()?;
+    fn add_track(&mut self, conn: &Connection, track: &Track) -> Result<(), Error> {
         let stmt = conn.prepare("INSERT INTO album_tracks (track_id, album_id)
                                  VALUES ($1, $2)")?;
         stmt.query(&[&track.id, &self.id])?;
         Ok(())
     }
-    fn add_artist(&mut self, artist: &Artist) -> Result<(), Error> {
-        let conn = conn()?;
+    fn add_artist(&mut self, conn: &Connection, artist: &Artist) -> Result<(), Error> {
         let stmt = conn.prepare("INSERT INTO album_artists (album_id, artist_id) VALUES ($1, $2)")?;
         stmt.query(&[&self.id, &artist.id])?;
         match self.artists {
@@ -244,8 +293,23 @@ impl Album {
         }
         Ok(())
     }
-    pub fn find_by_artist(artist_id: Uuid) -> Vec<Album> {
-        let conn = conn().unwrap();
+    pub fn add_genres(&mut self, conn: &Connection, names: &Vec<String>) -> Result<(), Error> {
+        let mut genres = Genre::add_to(conn, "album", self.id, names)?;
+        let mut items  = self.genres.clone().unwrap_or(vec![]);
+        items.retain(|g| !genres.contains(g));
+        items.append(&mut genres);
+        self.genres = Some(items);
+        Ok(())
+    }
+
+    /// The genres are added while the album is updated with a response of a provider.
+    /// A failure doesn't stop the update, so it is logged here instead of being returned.
+    fn try_add_genres(&mut self, conn: &Connection, names: &Vec<String>) {
+        if let Err(e) = self.add_genres(conn, names) {
+            println!("Failed to add genres {:?} to album {}: {}", names, self.id, e);
+        }
+    }
+    pub fn find_by_artist(conn: &Connection, artist_id: Uuid) -> Vec<Album> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM albums
                       LEFT OUTER JOIN album_artists ON album_artists.album_id = albums.id
@@ -254,8 +318,7 @@ impl Album {
         let rows = stmt.query(&[&artist_id]).unwrap();
         Album::rows_to_items(rows)
     }
-    pub fn find_by_provider(provider: &Provider) -> Vec<Album> {
-        let conn = conn().unwrap();
+    pub fn find_by_provider(conn: &Connection, provider: &Provider) -> Vec<Album> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM albums WHERE albums.provider = $1
                         ORDER BY albums.created_at DESC",
@@ -263,47 +326,63 @@ impl Album {
         let rows = stmt.query(&[&(*provider).to_string()]).unwrap();
         Album::rows_to_items(rows)
     }
-    pub fn from_sp_album(album: &spotify::Album) -> Album {
-        Album::find_or_create(Provider::Spotify, (*album).id.to_string())
+    pub fn from_sp_album(conn: &Connection, album: &spotify::Album) -> Album {
+        Album::find_or_create(conn, Provider::Spotify, (*album).id.to_string())
             .unwrap()
-            .update_with_sp_album(album)
+            .update_with_sp_album(conn, album)
             .clone()
     }
 
-    pub fn from_am_album(album: &apple_music::Album) -> Album {
-        Album::find_or_create(Provider::AppleMusic, (*album).id.to_string())
+    pub fn from_am_album(conn: &Connection, album: &apple_music::Album) -> Album {
+        Album::find_or_create(conn, Provider::AppleMusic, (*album).id.to_string())
             .unwrap()
-            .update_with_am_album(album)
+            .update_with_am_album(conn, album)
             .clone()
     }
 
-    fn add_tracks(&mut self, tracks: Vec<Track>) {
+    pub fn from_bc_album(conn: &Connection, page: &bandcamp::Page) -> Album {
+        Album::find_or_create(conn, Provider::Bandcamp, page.tralbum.id.to_string())
+            .unwrap()
+            .update_with_bc_album(conn, page)
+            .clone()
+    }
+
+    pub fn from_dz_album(conn: &Connection, album: &deezer::Album) -> Album {
+        Album::find_or_create(conn, Provider::Deezer, album.id.to_string())
+            .unwrap()
+            .update_with_dz_album(conn, album)
+            .clone()
+    }
+
+    fn add_tracks(&mut self, conn: &Connection, tracks: Vec<Track>) {
         self.tracks = tracks.iter().map(|t| {
             let mut t = t.clone();
-            if let Ok(new_track) = Track::find_or_create(t.provider,
+            if let Ok(new_track) = Track::find_or_create(conn,
+                                                         t.provider,
                                                          t.identifier.to_string()) {
                 t.id      = new_track.id;
-                let _     = t.save();
-                let _     = self.add_track(&t);
+                let _     = t.save(conn);
+                let _     = self.add_track(conn, &t);
             };
             t
         }).collect::<Vec<_>>();
     }
 
-    fn add_artists(&mut self, artists: Vec<Artist>) {
+    fn add_artists(&mut self, conn: &Connection, artists: Vec<Artist>) {
         self.artists = Some(artists.iter().map(|a| {
             let mut a = a.clone();
-            if let Ok(new_artist) = Artist::find_or_create(a.provider,
+            if let Ok(new_artist) = Artist::find_or_create(conn,
+                                                           a.provider,
                                                            a.identifier.to_string()) {
                 a.id      = new_artist.id;
-                let _     = a.save();
-                let _     = self.add_artist(&a);
+                let _     = a.save(conn);
+                let _     = self.add_artist(conn, &a);
             };
             a
         }).collect::<Vec<_>>());
     }
 
-    pub fn update_with_sp_album(&mut self, album: &spotify::Album) -> &mut Album {
+    pub fn update_with_sp_album(&mut self, conn: &Connection, album: &spotify::Album) -> &mut Album {
         self.provider       = Provider::Spotify;
         self.identifier     = album.id.to_string();
         if album.artists.len() > 0 {
@@ -313,7 +392,7 @@ impl Album {
         self.url            = album.uri.clone();
         self.title          = album.name.clone();
         self.description    = None;
-        self.state          = State::Alive;
+        self.state          = album.state();
         self.published_at   = Utc::now().naive_utc();
         if album.images.len() > 0 {
             self.artwork_url   = Some(album.images[0].url.clone());
@@ -325,9 +404,12 @@ impl Album {
 
         let artist_ids = album.artists.iter().map(|a| a.id.clone()).collect::<Vec<String>>();
         let sp_artists = spotify::fetch_artists(artist_ids).unwrap_or_default();
-        let artists = sp_artists.iter().map(|ref a| Artist::from_sp_artist(a))
+        let artists = sp_artists.iter().map(|ref a| Artist::from_sp_artist(conn, a))
             .collect::<Vec<_>>();
-        self.add_artists(artists);
+        self.add_artists(conn, artists);
+        if let Some(ref genres) = album.genres {
+            self.try_add_genres(conn, genres);
+        }
 
         let track_ids = album.tracks.clone()
             .map(|t| t.items).unwrap_or(vec![]).iter()
@@ -336,16 +418,16 @@ impl Album {
             .collect();
         let sp_tracks = spotify::fetch_tracks(track_ids).unwrap_or(vec![]);
         let tracks = sp_tracks.iter()
-            .map(|ref t| Track::from_sp_track(t))
+            .map(|ref t| Track::from_sp_track(conn, t))
             .filter(|ref t| t.is_ok())
             .map(|t| t.unwrap().clone())
-            .map(|ref mut t| t.update_with_sp_album(&album).clone())
+            .map(|ref mut t| t.update_with_sp_album(conn, &album).clone())
             .collect::<Vec<_>>();
-        self.add_tracks(tracks);
+        self.add_tracks(conn, tracks);
         self
     }
 
-    pub fn update_with_am_album(&mut self, album: &apple_music::Album) -> &mut Album {
+    pub fn update_with_am_album(&mut self, conn: &Connection, album: &apple_music::Album) -> &mut Album {
         let album_artists = album.clone().relationships.map(|r| {
             r.artists.data.clone()
         });
@@ -360,14 +442,15 @@ impl Album {
             self.description   = album.attributes.editorial_notes.clone().and_then(|n| n.short.clone());
             self.thumbnail_url = Some(album.attributes.artwork.get_thumbnail_url());
             self.artwork_url   = Some(album.attributes.artwork.get_artwork_url());
-            self.state         = State::Alive;
+            self.state         = album.attributes.state();
         }
         let country = apple_music::country(&self.url);
         if let Some(album_artists) = album_artists.clone() {
             let artist_ids = album_artists.iter().map(|a| a.id.clone()).collect::<Vec<String>>();
             let artists = apple_music::fetch_artists(&country, artist_ids).unwrap_or(vec![]);
-            self.add_artists(artists.iter().map(|a| Artist::from_am_artist(a)).collect());
+            self.add_artists(conn, artists.iter().map(|a| Artist::from_am_artist(conn, a)).collect());
         }
+        self.try_add_genres(conn, &apple_music::genre_names(&album.attributes.genre_names));
         let album_tracks = album.clone().relationships.map(|r| {
             r.tracks.data.clone()
         }).unwrap_or(vec![]);
@@ -379,17 +462,139 @@ impl Album {
             .collect::<Vec<_>>();
         let song_ids = songs.iter().map(|song| song.id.clone()).collect::<Vec<String>>();
         let songs = apple_music::fetch_songs(&country, song_ids).unwrap_or(vec![]);
-        self.add_tracks(songs.iter().map(|song| Track::from_am_song(song)).collect());
+        self.add_tracks(conn, songs.iter().map(|song| Track::from_am_song(conn, song)).collect());
+        self
+    }
+
+    pub fn update_with_bc_album(&mut self, conn: &Connection, page: &bandcamp::Page) -> &mut Album {
+        let tralbum        = &page.tralbum;
+        self.provider      = Provider::Bandcamp;
+        self.identifier    = tralbum.id.to_string();
+        self.owner_id      = page.band.clone().map(|b| b.id.to_string());
+        self.owner_name    = Some(tralbum.artist.clone());
+        self.url           = page.url.clone();
+        self.title         = tralbum.current.title.clone();
+        self.description   = tralbum.current.about.clone();
+        self.thumbnail_url = tralbum.get_thumbnail_url();
+        self.artwork_url   = tralbum.get_artwork_url();
+        self.state         = State::Alive;
+        if let Some(published_at) = tralbum.album_release_date.clone()
+            .and_then(|d| bandcamp::parse_date(&d)) {
+            self.published_at = published_at;
+        }
+        if let Some(ref band) = page.band {
+            self.add_artists(conn, vec![Artist::from_bc_band(conn, band, &page.band_url())]);
+        }
+        let tracks = tralbum.trackinfo.iter()
+            .map(|info| Track::from_bc_track_info(conn, page, info))
+            .collect::<Vec<_>>();
+        self.add_tracks(conn, tracks);
         self
     }
 
+    pub fn update_with_dz_album(&mut self, conn: &Connection, album: &deezer::Album) -> &mut Album {
+        self.provider      = Provider::Deezer;
+        self.identifier    = album.id.to_string();
+        if let Some(ref artist) = album.artist {
+            self.owner_id   = Some(artist.id.to_string());
+            self.owner_name = Some(artist.name.to_string());
+        }
+        self.url           = album.get_url();
+        self.title         = album.title.to_string();
+        self.description   = None;
+        self.thumbnail_url = album.cover_medium.clone();
+        self.artwork_url   = album.cover_xl.clone();
+        self.state         = State::Alive;
+        if let Some(published_at) = album.release_date.clone()
+            .and_then(|d| deezer::parse_date(&d)) {
+            self.published_at = published_at;
+        }
+        let artists = album.contributors.clone()
+            .or(album.artist.clone().map(|a| vec![a]))
+            .unwrap_or(vec![]);
+        self.add_artists(conn, artists.iter().map(|a| Artist::from_dz_artist(conn, a)).collect());
+        self.try_add_genres(conn, &album.genre_names());
+
+        let tracks = album.tracks.clone()
+            .map(|t| t.data).unwrap_or(vec![]).iter()
+            .map(|t| Track::from_dz_track(conn, t))
+            .map(|ref mut t| t.update_with_dz_album(conn, album).clone())
+            .collect::<Vec<_>>();
+        self.add_tracks(conn, tracks);
+        self
+    }
+
+    /// This function looks up the album on gracenote by its artist and title,
+    /// and enriches the tracks of the album that are found in the response.
+    /// The other tracks are left to `Track::enrich`.
+    pub fn enrich(&mut self, conn: &Connection) -> Result<Vec<Track>, Error> {
+        let artist = self.artists.clone()
+            .and_then(|artists| artists.first().map(|a| a.name.to_string()))
+            .or(self.owner_name.clone())
+            .ok_or(Error::NotFound)?;
+        let album = gracenote::search_album(&artist, &self.title)?.ok_or(Error::NotFound)?;
+        if self.tracks.is_empty() {
+            self.tracks = Track::find_by_album(conn, self.id);
+        }
+        let mut tracks = vec![];
+        for track in self.tracks.iter_mut() {
+            if let Some(gn_track) = album.find_track(&track.title) {
+                track.update_with_gn_track(conn, &album, gn_track)?;
+                tracks.push(track.clone());
+            }
+        }
+        Ok(tracks)
+    }
+
+    /// This function finds the alive albums that have tracks which haven't been looked up on gracenote yet.
+    pub fn find_unenriched(conn: &Connection) -> Result<Vec<Album>, Error> {
+        let stmt = conn.prepare(
+            &format!("SELECT {} FROM albums
+                      WHERE albums.state = 'alive' AND EXISTS (
+                        SELECT 1 FROM album_tracks
+                          INNER JOIN tracks ON tracks.id = album_tracks.track_id
+                          WHERE album_tracks.album_id = albums.id AND tracks.enriched_at IS NULL
+                      )
+                      ORDER BY albums.published_at DESC",
+                     Album::props_str("")))?;
+        let rows = stmt.query(&[])?;
+        Ok(Album::rows_to_items(rows))
+    }
+
     pub fn disable(&mut self) -> &mut Album {
         self.state = State::Dead;
         self
     }
 
-    pub fn delete(&self) -> Result<(), Error> {
-        let conn = conn()?;
+    /// This function records a failure of fetching the album like `Track::record_failure`,
+    /// but albums don't have the history.
+    pub fn record_failure(&mut self, err: &Error) -> &mut Album {
+        self.failure_count += 1;
+        self.failed_at      = Some(Utc::now().naive_utc());
+        self.last_failure   = Some(err.summary());
+        if err.is_not_found() {
+            self.missing_count += 1;
+            if self.missing_count >= DEAD_AFTER_NOT_FOUND {
+                self.disable();
+            }
+        } else {
+            self.missing_count = 0;
+        }
+        self
+    }
+
+    pub fn recover(&mut self) -> &mut Album {
+        self.failure_count = 0;
+        self.missing_count = 0;
+        self
+    }
+
+    pub fn revive(&mut self) -> &mut Album {
+        self.state = State::Alive;
+        self.recover()
+    }
+
+    pub fn delete(&self, conn: &Connection) -> Result<(), Error> {
         let stmt = conn.prepare("DELETE FROM albums WHERE id=$1")?;
         let result = stmt.query(&[&self.id]);
         match result {
@@ -402,7 +607,9 @@ impl Album {
 #[cfg(test)]
 mod test {
     use model::enclosure::Enclosure;
-    use model::Model;
+    use model::{Model, conn};
+    use model::state::{State, DEAD_AFTER_NOT_FOUND};
+    use error::{Error, ProviderError};
     use super::Album;
     use Provider;
     #[test]
@@ -414,25 +621,56 @@ mod test {
     }
     #[test]
     fn test_find_of_create() {
+        let conn       = conn().unwrap();
         let identifier = "PLy8LZ8FM-o0ViuGAF68RAaXkQ8V-3dbTX".to_string();
-        let result     = Album::find_or_create(Provider::YouTube, identifier);
+        let result     = Album::find_or_create(&*conn, Provider::YouTube, identifier);
         assert!(result.is_ok());
     }
     #[test]
     fn test_delete() {
+        let conn       = conn().unwrap();
         let identifier = "test_delete".to_string();
-        let album      = Album::find_or_create(Provider::YouTube, identifier).unwrap();
-        let result     = album.delete();
+        let album      = Album::find_or_create(&*conn, Provider::YouTube, identifier).unwrap();
+        let result     = album.delete(&*conn);
         assert!(result.is_ok());
     }
     #[test]
     fn test_save() {
+        let conn = conn().unwrap();
         let id = "test_save";
-        let mut album = Album::find_or_create(Provider::YouTube, id.to_string()).unwrap();
+        let mut album = Album::find_or_create(&*conn, Provider::YouTube, id.to_string()).unwrap();
         album.title   = "title".to_string();
-        let result    = album.save();
+        let result    = album.save(&*conn);
         assert!(result.is_ok());
-        let album = Album::find_or_create(Provider::YouTube, id.to_string()).unwrap();
+        let album = Album::find_or_create(&*conn, Provider::YouTube, id.to_string()).unwrap();
         assert_eq!(&album.title, "title");
     }
+    #[test]
+    fn test_record_failure() {
+        let mut album = Album::new(Provider::Spotify, "test_record_failure".to_string());
+        album.record_failure(&Error::Provider(Provider::Spotify, ProviderError::Timeout));
+        assert_eq!(album.state, State::Pending);
+        for _ in 0..DEAD_AFTER_NOT_FOUND - 1 {
+            album.record_failure(&Error::Provider(Provider::Spotify, ProviderError::NotFound));
+            assert_eq!(album.state, State::Pending);
+        }
+        album.record_failure(&Error::Provider(Provider::Spotify, ProviderError::NotFound));
+        assert_eq!(album.state, State::Dead);
+        assert_eq!(album.failure_count, DEAD_AFTER_NOT_FOUND + 1);
+        assert!(album.failed_at.is_some());
+        album.revive();
+        assert_eq!(album.state, State::Alive);
+        assert_eq!(album.failure_count, 0);
+    }
+    #[test]
+    fn test_record_failure_not_in_a_row() {
+        let mut album = Album::new(Provider::Spotify, "test_record_failure_not_in_a_row".to_string());
+        for _ in 0..DEAD_AFTER_NOT_FOUND - 1 {
+            album.record_failure(&Error::Provider(Provider::Spotify, ProviderError::NotFound));
+        }
+        album.record_failure(&Error::Provider(Provider::Spotify, ProviderError::Timeout));
+        assert_eq!(album.missing_count, 0);
+        album.record_failure(&Error::Provider(Provider::Spotify, ProviderError::NotFound));
+        assert_eq!(album.state, State::Pending);
+    }
 }
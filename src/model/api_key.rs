@@ -0,0 +1,179 @@
+use std::fmt;
+use uuid::Uuid;
+use chrono::{NaiveDateTime, Utc};
+use rand::{Rng, OsRng};
+use sha2::{Sha256, Digest};
+use super::{Connection, Row};
+use error::Error;
+
+/// The scopes of api keys. Each scope allows the requests of the scopes below it,
+/// e.g. a key with `Write` can also read.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Scope {
+    /// Reading the items.
+    #[serde(rename = "read")]
+    Read,
+    /// Creating and updating the items, which calls the apis of the providers.
+    #[serde(rename = "write")]
+    Write,
+    /// Merging artists and reviving dead items.
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl Scope {
+    pub fn parse(value: &str) -> Option<Scope> {
+        match value {
+            "read"  => Some(Scope::Read),
+            "write" => Some(Scope::Write),
+            "admin" => Some(Scope::Admin),
+            _       => None,
+        }
+    }
+    pub fn allows(&self, scope: Scope) -> bool {
+        *self >= scope
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Scope::Read  => "read",
+            Scope::Write => "write",
+            Scope::Admin => "admin",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A key of a client of the api. Only the hash of the key is stored,
+/// so the key itself is shown only once when it's issued.
+/// `prefix` is the beginning of the key, that tells the keys apart in the list.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiKey {
+    pub id:         Uuid,
+    pub name:       String,
+    pub prefix:     String,
+    #[serde(skip_serializing)]
+    pub key_hash:   String,
+    pub scope:      Scope,
+    pub created_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+static PROPS: [&'static str; 7]  = ["id",
+                                    "name",
+                                    "prefix",
+                                    "key_hash",
+                                    "scope",
+                                    "created_at",
+                                    "revoked_at"];
+
+static KEY_PREFIX:    &'static str = "ps_";
+static PREFIX_LENGTH: usize        = 11;
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// This function hashes a key. The keys are random enough, so they don't need salts.
+pub fn hash(key: &str) -> String {
+    let mut hasher = Sha256::default();
+    hasher.input(key.as_bytes());
+    to_hex(&hasher.result())
+}
+
+impl ApiKey {
+    fn props_str(prefix: &str) -> String {
+        PROPS
+            .iter()
+            .map(|&p| format!("{}{}", prefix, p))
+            .collect::<Vec<String>>().join(",")
+    }
+    fn row_to_item(row: &Row) -> Self {
+        let scope: String = row.get(4);
+        ApiKey {
+            id:         row.get(0),
+            name:       row.get(1),
+            prefix:     row.get(2),
+            key_hash:   row.get(3),
+            scope:      Scope::parse(&scope).unwrap_or(Scope::Read),
+            created_at: row.get(5),
+            revoked_at: row.get(6),
+        }
+    }
+
+    /// This function creates a new key, and returns it with the key itself.
+    pub fn issue(conn: &Connection, name: &str, scope: Scope) -> Result<(ApiKey, String), Error> {
+        let mut rng = OsRng::new().map_err(|_| Error::Unexpected)?;
+        let mut bytes = [0u8; 24];
+        rng.fill_bytes(&mut bytes);
+        let key = format!("{}{}", KEY_PREFIX, to_hex(&bytes));
+        let api_key = ApiKey {
+            id:         Uuid::new_v4(),
+            name:       name.to_string(),
+            prefix:     key.chars().take(PREFIX_LENGTH).collect(),
+            key_hash:   hash(&key),
+            scope:      scope,
+            created_at: Utc::now().naive_utc(),
+            revoked_at: None,
+        };
+        conn.execute(&format!("INSERT INTO api_keys ({}) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                              ApiKey::props_str("")),
+                     &[&api_key.id,
+                       &api_key.name,
+                       &api_key.prefix,
+                       &api_key.key_hash,
+                       &api_key.scope.to_string(),
+                       &api_key.created_at,
+                       &api_key.revoked_at])?;
+        Ok((api_key, key))
+    }
+
+    /// This function finds the key that isn't revoked.
+    pub fn find_by_key(conn: &Connection, key: &str) -> Result<ApiKey, Error> {
+        let stmt = conn.prepare(&format!("SELECT {} FROM api_keys
+                                          WHERE key_hash = $1 AND revoked_at IS NULL",
+                                         ApiKey::props_str("")))?;
+        let rows = stmt.query(&[&hash(key)])?;
+        let row  = rows.iter().next().ok_or(Error::NotFound)?;
+        Ok(ApiKey::row_to_item(row))
+    }
+
+    pub fn find_all(conn: &Connection) -> Result<Vec<ApiKey>, Error> {
+        let stmt = conn.prepare(&format!("SELECT {} FROM api_keys ORDER BY created_at DESC",
+                                         ApiKey::props_str("")))?;
+        let rows = stmt.query(&[])?;
+        Ok(rows.iter().map(ApiKey::row_to_item).collect())
+    }
+
+    /// This function revokes the keys that have the id or the prefix, and returns the number of them.
+    pub fn revoke(conn: &Connection, id_or_prefix: &str) -> Result<u64, Error> {
+        let now  = Utc::now().naive_utc();
+        match Uuid::parse_str(id_or_prefix) {
+            Ok(id) => conn.execute("UPDATE api_keys SET revoked_at = $2
+                                    WHERE id = $1 AND revoked_at IS NULL", &[&id, &now]),
+            Err(_) => conn.execute("UPDATE api_keys SET revoked_at = $2
+                                    WHERE prefix = $1 AND revoked_at IS NULL", &[&id_or_prefix, &now]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scope() {
+        assert!(Scope::Admin.allows(Scope::Write));
+        assert!(Scope::Write.allows(Scope::Read));
+        assert!(!Scope::Read.allows(Scope::Write));
+        assert_eq!(Scope::parse("admin"), Some(Scope::Admin));
+        assert_eq!(Scope::parse("root"), None);
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
@@ -1,4 +1,4 @@
-use postgres;
+use super::Row;
 use uuid::Uuid;
 use std::fmt;
 use std::collections::BTreeMap;
@@ -8,13 +8,22 @@ use youtube;
 use soundcloud;
 use spotify;
 use apple_music;
+use bandcamp;
+use mixcloud;
+use deezer;
+use mixcloud::HasPictures;
+use adapter;
 use error::Error;
-use super::{conn, Model};
+use super::{Model, Connection, SearchIndex};
+use search;
+use search::SearchType;
 use model::provider::Provider;
 use model::enclosure::Enclosure;
+use model::genre::Genre;
+use model::canonical_artist::CanonicalArtist;
 use youtube::HasThumbnail;
 
-static PROPS: [&'static str; 9]  = ["id",
+static PROPS: [&'static str; 10] = ["id",
                                     "provider",
                                     "identifier",
                                     "url",
@@ -22,7 +31,8 @@ static PROPS: [&'static str; 9]  = ["id",
                                     "thumbnail_url",
                                     "artwork_url",
                                     "created_at",
-                                    "updated_at"];
+                                    "updated_at",
+                                    "canonical_artist_id"];
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Artist {
@@ -35,6 +45,9 @@ pub struct Artist {
     pub artwork_url:   Option<String>,
     pub created_at:    NaiveDateTime,
     pub updated_at:    NaiveDateTime,
+    pub canonical_artist_id: Option<Uuid>,
+    pub canonical_artist:    Option<CanonicalArtist>,
+    pub genres:        Option<Vec<Genre>>,
 }
 
 impl fmt::Display for Artist {
@@ -54,7 +67,10 @@ impl<'a> Model<'a> for Artist {
     fn search_prop() -> &'static str {
         "name"
     }
-    fn row_to_item(row: postgres::rows::Row) -> Artist {
+    fn filter_fields() -> &'static [&'static str] {
+        &["provider", "canonical_artist_id"]
+    }
+    fn row_to_item(row: &Row) -> Artist {
         Artist {
             id:            row.get(0),
             provider:      Provider::new(row.get(1)),
@@ -65,11 +81,13 @@ impl<'a> Model<'a> for Artist {
             artwork_url:   row.get(6),
             created_at:    row.get(7),
             updated_at:    row.get(8),
+            canonical_artist_id: row.get(9),
+            canonical_artist:    None,
+            genres:        None,
         }
     }
 
-    fn create(&self) -> Result<Artist, Error> {
-        let conn = conn()?;
+    fn create(&self, conn: &Connection) -> Result<Artist, Error> {
         let stmt = conn.prepare("INSERT INTO artists (provider, identifier, url, name)
                                  VALUES ($1, $2, $3, $4) RETURNING id")?;
         let rows = stmt.query(&[&self.provider.to_string(), &self.identifier, &self.url, &self.name])?;
@@ -80,9 +98,8 @@ impl<'a> Model<'a> for Artist {
         Ok(artist)
     }
 
-    fn save(&mut self) -> Result<(), Error> {
+    fn save(&mut self, conn: &Connection) -> Result<(), Error> {
         self.updated_at = Utc::now().naive_utc();
-        let conn = conn().unwrap();
         let stmt = conn.prepare("UPDATE artists SET
                                  provider      = $2,
                                  identifier    = $3,
@@ -104,10 +121,22 @@ impl<'a> Model<'a> for Artist {
                                   &self.updated_at,
         ]);
         match result {
-            Ok(_)  => Ok(()),
+            Ok(_)  => self.update_search_index(conn),
             Err(_) => Err(Error::Unexpected),
         }
     }
+
+    fn set_relations(conn: &Connection, artists: &mut Vec<Artist>) -> Result<(), Error> {
+        let ids: Vec<Uuid> = artists.iter().map(|i| i.id).collect();
+        let genres_of_artist = Genre::find_by_artists(conn, &ids)?;
+        for artist in artists.iter_mut() {
+            if let Some(ref mut genres) = genres_of_artist.get(&artist.id) {
+                artist.genres = Some(genres.clone())
+            }
+        }
+        Artist::set_canonical_artists(conn, artists)?;
+        Ok(())
+    }
 }
 
 impl<'a> Enclosure<'a> for Artist {
@@ -122,6 +151,9 @@ impl<'a> Enclosure<'a> for Artist {
             artwork_url:   None,
             created_at:    Utc::now().naive_utc(),
             updated_at:    Utc::now().naive_utc(),
+            canonical_artist_id: None,
+            canonical_artist:    None,
+            genres:        None,
         }
     }
 
@@ -134,44 +166,33 @@ impl<'a> Enclosure<'a> for Artist {
         self
     }
 
-    fn fetch_props(&mut self) -> Result<(), Error> {
-        match self.provider {
-            Provider::YouTube => match youtube::fetch_channel(&self.identifier) {
-                Ok(artist) => self.update_with_yt_channel(&artist),
-                Err(_)     => self,
-            },
-            Provider::AppleMusic => {
-                let country = apple_music::country(&self.url);
-                match apple_music::fetch_artist(&country, &self.identifier) {
-                    Ok(artist) => self.update_with_am_artist(&artist),
-                    Err(_)     => self,
-                }
-            },
-            Provider::Spotify => match spotify::fetch_artist(&self.identifier) {
-                Ok(artist) => self.update_with_sp_artist(&artist),
-                Err(_)     => self,
-            },
-            _ => self,
-        };
+    fn fetch_props(&mut self, conn: &Connection) -> Result<(), Error> {
+        if let Some(adapter) = adapter::find(&self.provider) {
+            let _ = adapter.fetch_artist(conn, self);
+        }
         Ok(())
     }
 
-    fn find_by_entry_id(_entry_id: Uuid) -> Vec<Artist> {
-        vec![]
+    fn find_by_entry_ids(_conn: &Connection, entry_ids: &[Uuid]) -> Result<BTreeMap<Uuid, Vec<Artist>>, Error> {
+        Ok(entry_ids.iter().map(|id| (*id, vec![])).collect())
     }
 }
 
 impl Artist {
-    pub fn find_all() -> Vec<Artist> {
-        let conn = conn().unwrap();
+    /// This function indexes the name of the artist for `search`.
+    pub fn update_search_index(&self, conn: &Connection) -> Result<(), Error> {
+        let tokens = search::weigh_tokens(&[(Some(self.name.as_str()), 3.0)]);
+        SearchIndex::update_in(conn, &SearchType::Artist.to_string(), self.id, &tokens)
+    }
+
+    pub fn find_all(conn: &Connection) -> Vec<Artist> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM artists ORDER BY artists.created_at DESC",
                      Artist::props_str(""))).unwrap();
         let rows = stmt.query(&[]).unwrap();
         Artist::rows_to_items(rows)
     }
-    fn find_by(provider: &Provider, identifier: &str) -> Result<Artist, Error> {
-        let conn = conn().unwrap();
+    fn find_by(conn: &Connection, provider: &Provider, identifier: &str) -> Result<Artist, Error> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM {}
                      WHERE provider = $1 AND identifier = $2
@@ -184,15 +205,14 @@ impl Artist {
         }
         return Err(Error::NotFound)
     }
-    pub fn find_or_create(provider: Provider, identifier: String) -> Result<Artist, Error> {
-        return match Artist::find_by(&provider, &identifier) {
+    pub fn find_or_create(conn: &Connection, provider: Provider, identifier: String) -> Result<Artist, Error> {
+        return match Artist::find_by(conn, &provider, &identifier) {
             Ok(item) => Ok(item),
-            Err(_)   => Artist::new(provider, identifier).create()
+            Err(_)   => Artist::new(provider, identifier).create(conn)
         }
     }
 
-    pub fn find_by_provider(provider: &Provider) -> Vec<Artist> {
-        let conn = conn().unwrap();
+    pub fn find_by_provider(conn: &Connection, provider: &Provider) -> Vec<Artist> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM artists WHERE artists.provider = $1
                         ORDER BY artists.created_at DESC",
@@ -201,8 +221,7 @@ impl Artist {
         Artist::rows_to_items(rows)
     }
 
-    fn find_by_relations(ids: &Vec<Uuid>, name: &str) -> Result<BTreeMap<Uuid, Vec<Artist>>, Error> {
-        let conn = conn().unwrap();
+    fn find_by_relations(conn: &Connection, ids: &Vec<Uuid>, name: &str) -> Result<BTreeMap<Uuid, Vec<Artist>>, Error> {
         let sql = format!("SELECT {0}, {1}_artists.{1}_id FROM artists
                       LEFT OUTER JOIN {1}_artists ON {1}_artists.artist_id = artists.id
                       WHERE {1}_artists.{1}_id = ANY($1) ORDER BY artists.created_at DESC",
@@ -219,46 +238,130 @@ impl Artist {
                 artists.push(Self::row_to_item(row))
             }
         }
+        let canonical_ids: Vec<Uuid> = items.values()
+            .flat_map(|artists| artists.iter().filter_map(|a| a.canonical_artist_id))
+            .collect();
+        let canonical_artists = CanonicalArtist::find_by_ids(conn, &canonical_ids)?;
+        for artists in items.values_mut() {
+            Artist::assign_canonical_artists(artists, &canonical_artists);
+        }
+        Ok(items)
+    }
+
+    /// The canonical artist is set so that the artists of any provider are shown
+    /// as the same artist.
+    fn set_canonical_artists(conn: &Connection, artists: &mut Vec<Artist>) -> Result<(), Error> {
+        let ids: Vec<Uuid> = artists.iter().filter_map(|a| a.canonical_artist_id).collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let canonical_artists = CanonicalArtist::find_by_ids(conn, &ids)?;
+        Artist::assign_canonical_artists(artists, &canonical_artists);
+        Ok(())
+    }
+
+    fn assign_canonical_artists(artists: &mut Vec<Artist>, canonical_artists: &BTreeMap<Uuid, CanonicalArtist>) {
+        for artist in artists.iter_mut() {
+            artist.canonical_artist = artist.canonical_artist_id
+                .and_then(|id| canonical_artists.get(&id).cloned());
+        }
+    }
+
+    pub fn find_by_canonical_artists(conn: &Connection, canonical_artist_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Artist>>, Error> {
+        let stmt = conn.prepare(&format!("SELECT {} FROM artists
+                                          WHERE artists.canonical_artist_id = ANY($1)
+                                          ORDER BY artists.created_at ASC",
+                                         Artist::props_str("")))?;
+        let rows = stmt.query(&[&canonical_artist_ids])?;
+        let mut items: BTreeMap<Uuid, Vec<Artist>> = BTreeMap::new();
+        for id in canonical_artist_ids.iter() {
+            items.insert(*id, vec![]);
+        }
+        for artist in Artist::rows_to_items(rows) {
+            if let Some(id) = artist.canonical_artist_id {
+                if let Some(artists) = items.get_mut(&id) {
+                    artists.push(artist)
+                }
+            }
+        }
         Ok(items)
     }
 
-    pub fn find_by_tracks(track_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Artist>>, Error> {
-        Artist::find_by_relations(track_ids, "track")
+    pub fn find_by_tracks(conn: &Connection, track_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Artist>>, Error> {
+        Artist::find_by_relations(conn, track_ids, "track")
     }
 
-    pub fn find_by_albums(album_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Artist>>, Error> {
-        Artist::find_by_relations(album_ids, "album")
+    pub fn find_by_albums(conn: &Connection, album_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Artist>>, Error> {
+        Artist::find_by_relations(conn, album_ids, "album")
     }
 
 
-    pub fn from_yt_channel(channel: &youtube::Channel) -> Artist {
-        Artist::find_or_create(Provider::YouTube, channel.id.to_string())
+    pub fn from_yt_channel(conn: &Connection, channel: &youtube::Channel) -> Artist {
+        Artist::find_or_create(conn, Provider::YouTube, channel.id.to_string())
             .unwrap()
             .update_with_yt_channel(channel)
             .clone()
     }
-    pub fn from_sp_artist(artist: &spotify::Artist) -> Artist {
-        Artist::find_or_create(Provider::Spotify, (*artist).id.to_string())
+    pub fn from_sp_artist(conn: &Connection, artist: &spotify::Artist) -> Artist {
+        Artist::find_or_create(conn, Provider::Spotify, (*artist).id.to_string())
             .unwrap()
-            .update_with_sp_artist(artist)
+            .update_with_sp_artist(conn, artist)
             .clone()
     }
 
-    pub fn from_sc_user(playlist: &soundcloud::User) -> Artist {
-        Artist::find_or_create(Provider::SoundCloud, (*playlist).id.to_string())
+    pub fn from_sc_user(conn: &Connection, playlist: &soundcloud::User) -> Artist {
+        Artist::find_or_create(conn, Provider::SoundCloud, (*playlist).id.to_string())
             .unwrap()
             .update_with_sc_user(playlist)
             .clone()
     }
 
-    pub fn from_am_artist(artist: &apple_music::Artist) -> Artist {
-        Artist::find_or_create(Provider::AppleMusic, (*artist).id.to_string())
+    pub fn from_am_artist(conn: &Connection, artist: &apple_music::Artist) -> Artist {
+        Artist::find_or_create(conn, Provider::AppleMusic, (*artist).id.to_string())
+            .unwrap()
+            .update_with_am_artist(conn, artist)
+            .clone()
+    }
+
+    pub fn from_bc_band(conn: &Connection, band: &bandcamp::Band, url: &str) -> Artist {
+        Artist::find_or_create(conn, Provider::Bandcamp, band.id.to_string())
+            .unwrap()
+            .update_with_bc_band(band, url)
+            .clone()
+    }
+
+    pub fn from_mc_user(conn: &Connection, user: &mixcloud::User) -> Artist {
+        Artist::find_or_create(conn, Provider::Mixcloud, user.username.to_string())
+            .unwrap()
+            .update_with_mc_user(user)
+            .clone()
+    }
+
+    pub fn from_dz_artist(conn: &Connection, artist: &deezer::Artist) -> Artist {
+        Artist::find_or_create(conn, Provider::Deezer, artist.id.to_string())
             .unwrap()
-            .update_with_am_artist(artist)
+            .update_with_dz_artist(artist)
             .clone()
     }
 
-    pub fn update_with_sp_artist(&mut self, artist: &spotify::Artist) -> &mut Artist {
+    pub fn add_genres(&mut self, conn: &Connection, names: &Vec<String>) -> Result<(), Error> {
+        let mut genres = Genre::add_to(conn, "artist", self.id, names)?;
+        let mut items  = self.genres.clone().unwrap_or(vec![]);
+        items.retain(|g| !genres.contains(g));
+        items.append(&mut genres);
+        self.genres = Some(items);
+        Ok(())
+    }
+
+    /// The genres are added while the artist is updated with a response of a provider.
+    /// A failure doesn't stop the update, so it is logged here instead of being returned.
+    fn try_add_genres(&mut self, conn: &Connection, names: &Vec<String>) {
+        if let Err(e) = self.add_genres(conn, names) {
+            println!("Failed to add genres {:?} to artist {}: {}", names, self.id, e);
+        }
+    }
+
+    pub fn update_with_sp_artist(&mut self, conn: &Connection, artist: &spotify::Artist) -> &mut Artist {
         self.provider       = Provider::Spotify;
         self.identifier     = artist.id.to_string();
         self.url            = artist.uri.clone();
@@ -272,10 +375,13 @@ impl Artist {
                 self.thumbnail_url = Some(images[1].url.clone());
             }
         }
+        if let Some(ref genres) = artist.genres {
+            self.try_add_genres(conn, genres);
+        }
         self
     }
 
-    pub fn update_with_am_artist(&mut self, artist: &apple_music::Artist) -> &mut Artist {
+    pub fn update_with_am_artist(&mut self, conn: &Connection, artist: &apple_music::Artist) -> &mut Artist {
         self.provider      = Provider::AppleMusic;
         self.identifier    = artist.id.to_string();
         self.url           = artist.attributes.url.to_string();
@@ -285,6 +391,7 @@ impl Artist {
                 self.thumbnail_url = Some(album.attributes.artwork.get_thumbnail_url());
                 self.artwork_url   = Some(album.attributes.artwork.get_artwork_url());
             }
+        self.try_add_genres(conn, &apple_music::genre_names(&artist.attributes.genre_names));
         self
     }
 
@@ -298,6 +405,36 @@ impl Artist {
         self
     }
 
+    pub fn update_with_bc_band(&mut self, band: &bandcamp::Band, url: &str) -> &mut Artist {
+        self.provider      = Provider::Bandcamp;
+        self.identifier    = band.id.to_string();
+        self.url           = url.to_string();
+        self.name          = band.name.clone();
+        self.thumbnail_url = band.get_thumbnail_url();
+        self.artwork_url   = band.get_artwork_url();
+        self
+    }
+
+    pub fn update_with_mc_user(&mut self, user: &mixcloud::User) -> &mut Artist {
+        self.provider      = Provider::Mixcloud;
+        self.identifier    = user.username.to_string();
+        self.url           = user.url.to_string();
+        self.name          = user.name.to_string();
+        self.thumbnail_url = user.get_thumbnail_url();
+        self.artwork_url   = user.get_artwork_url();
+        self
+    }
+
+    pub fn update_with_dz_artist(&mut self, artist: &deezer::Artist) -> &mut Artist {
+        self.provider      = Provider::Deezer;
+        self.identifier    = artist.id.to_string();
+        self.url           = artist.get_url();
+        self.name          = artist.name.to_string();
+        self.thumbnail_url = artist.picture_medium.clone();
+        self.artwork_url   = artist.picture_xl.clone();
+        self
+    }
+
     pub fn update_with_yt_channel(&mut self, channel: &youtube::Channel) -> &mut Artist {
         let s              = &channel.snippet;
         self.provider      = Provider::YouTube;
@@ -312,7 +449,7 @@ impl Artist {
 
 #[cfg(test)]
 mod test {
-    use model::Model;
+    use model::{Model, conn};
     use model::enclosure::Enclosure;
     use super::Artist;
     use Provider;
@@ -324,18 +461,20 @@ mod test {
     }
     #[test]
     fn test_find_of_create() {
+        let conn       = conn().unwrap();
         let identifier = "4tZwfgrHOc3mvqYlEYSvVi".to_string();
-        let result     = Artist::find_or_create(Provider::Spotify, identifier);
+        let result     = Artist::find_or_create(&*conn, Provider::Spotify, identifier);
         assert!(result.is_ok());
     }
     #[test]
     fn test_save() {
+        let conn = conn().unwrap();
         let id = "test_save";
-        let mut artist = Artist::find_or_create(Provider::Spotify, id.to_string()).unwrap();
+        let mut artist = Artist::find_or_create(&*conn, Provider::Spotify, id.to_string()).unwrap();
         artist.name   = "name".to_string();
-        let result    = artist.save();
+        let result    = artist.save(&*conn);
         assert!(result.is_ok());
-        let artist = Artist::find_or_create(Provider::Spotify, id.to_string()).unwrap();
+        let artist = Artist::find_or_create(&*conn, Provider::Spotify, id.to_string()).unwrap();
         assert_eq!(&artist.name, "name");
     }
 }
@@ -0,0 +1,176 @@
+use super::{Connection, Row};
+use uuid::Uuid;
+use std::fmt;
+use std::collections::BTreeMap;
+use chrono::{NaiveDateTime, Utc};
+
+use error::Error;
+use super::Model;
+use model::artist::Artist;
+
+static PROPS: [&'static str; 6]  = ["id",
+                                    "name",
+                                    "thumbnail_url",
+                                    "artwork_url",
+                                    "created_at",
+                                    "updated_at"];
+
+/// A canonical artist groups the artists of each provider that are the same person or band.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CanonicalArtist {
+    pub id:            Uuid,
+    pub name:          String,
+    pub thumbnail_url: Option<String>,
+    pub artwork_url:   Option<String>,
+    pub created_at:    NaiveDateTime,
+    pub updated_at:    NaiveDateTime,
+    pub artists:       Option<Vec<Artist>>,
+}
+
+impl PartialEq for CanonicalArtist {
+    fn eq(&self, a: &CanonicalArtist) -> bool {
+        return self.id == a.id
+    }
+}
+
+impl fmt::Display for CanonicalArtist {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<'a> Model<'a> for CanonicalArtist {
+    fn table_name() -> String {
+        "canonical_artists".to_string()
+    }
+    fn props_str(prefix: &str) -> String {
+        PROPS
+            .iter()
+            .map(|&p| format!("{}{}", prefix, p))
+            .collect::<Vec<String>>().join(",")
+    }
+    fn search_prop() -> &'static str {
+        "name"
+    }
+    fn row_to_item(row: &Row) -> CanonicalArtist {
+        CanonicalArtist {
+            id:            row.get(0),
+            name:          row.get(1),
+            thumbnail_url: row.get(2),
+            artwork_url:   row.get(3),
+            created_at:    row.get(4),
+            updated_at:    row.get(5),
+            artists:       None,
+        }
+    }
+    fn create(&self, conn: &Connection) -> Result<CanonicalArtist, Error> {
+        let stmt = conn.prepare("INSERT INTO canonical_artists (name, thumbnail_url, artwork_url)
+                                 VALUES ($1, $2, $3) RETURNING id")?;
+        let rows = stmt.query(&[&self.name, &self.thumbnail_url, &self.artwork_url])?;
+        let mut canonical_artist = self.clone();
+        for row in rows.iter() {
+            canonical_artist.id = row.get(0);
+        }
+        Ok(canonical_artist)
+    }
+    fn save(&mut self, conn: &Connection) -> Result<(), Error> {
+        self.updated_at = Utc::now().naive_utc();
+        let stmt = conn.prepare("UPDATE canonical_artists SET
+                                 name          = $2,
+                                 thumbnail_url = $3,
+                                 artwork_url   = $4,
+                                 created_at    = $5,
+                                 updated_at    = $6
+                                 WHERE id = $1")?;
+        let result = stmt.query(&[&self.id,
+                                  &self.name,
+                                  &self.thumbnail_url,
+                                  &self.artwork_url,
+                                  &self.created_at,
+                                  &self.updated_at]);
+        match result {
+            Ok(_)  => Ok(()),
+            Err(_) => Err(Error::Unexpected)
+        }
+    }
+    fn set_relations(conn: &Connection, canonical_artists: &mut Vec<CanonicalArtist>) -> Result<(), Error> {
+        let ids: Vec<Uuid> = canonical_artists.iter().map(|i| i.id).collect();
+        let artists_map = Artist::find_by_canonical_artists(conn, &ids)?;
+        for canonical_artist in canonical_artists {
+            if let Some(ref mut artists) = artists_map.get(&canonical_artist.id) {
+                canonical_artist.artists = Some(artists.clone())
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CanonicalArtist {
+    pub fn new(artist: &Artist) -> CanonicalArtist {
+        CanonicalArtist {
+            id:            Uuid::new_v4(),
+            name:          artist.name.to_string(),
+            thumbnail_url: artist.thumbnail_url.clone(),
+            artwork_url:   artist.artwork_url.clone(),
+            created_at:    Utc::now().naive_utc(),
+            updated_at:    Utc::now().naive_utc(),
+            artists:       None,
+        }
+    }
+
+    pub fn find_by_ids(conn: &Connection, ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, CanonicalArtist>, Error> {
+        if ids.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let stmt = conn.prepare(&format!("SELECT {} FROM canonical_artists WHERE id = ANY($1)",
+                                         CanonicalArtist::props_str("")))?;
+        let rows = stmt.query(&[&ids])?;
+        let mut items: BTreeMap<Uuid, CanonicalArtist> = BTreeMap::new();
+        for item in CanonicalArtist::rows_to_items(rows) {
+            items.insert(item.id, item);
+        }
+        Ok(items)
+    }
+
+    /// This function merges the artists into one canonical artist.
+    /// If some of them already belong to canonical artists,
+    /// the oldest one is kept and the others are merged into it.
+    pub fn merge(conn: &Connection, artists: &Vec<Artist>) -> Result<CanonicalArtist, Error> {
+        let first = artists.first().ok_or(Error::Unprocessable)?;
+        let ids: Vec<Uuid> = artists.iter().filter_map(|a| a.canonical_artist_id).collect();
+        let trans = conn.transaction()?;
+        let mut existings = CanonicalArtist::find_by_ids(&trans, &ids)?.into_iter()
+            .map(|(_, v)| v)
+            .collect::<Vec<CanonicalArtist>>();
+        existings.sort_by_key(|c| c.created_at);
+        let canonical_artist = match existings.first() {
+            Some(c) => c.clone(),
+            None    => CanonicalArtist::new(first).create(&trans)?,
+        };
+        let artist_ids: Vec<Uuid> = artists.iter().map(|a| a.id).collect();
+        trans.execute("UPDATE artists SET canonical_artist_id = $1
+                       WHERE id = ANY($2) OR canonical_artist_id = ANY($3)",
+                      &[&canonical_artist.id, &artist_ids, &ids])?;
+        let others: Vec<Uuid> = ids.into_iter().filter(|id| *id != canonical_artist.id).collect();
+        trans.execute("DELETE FROM canonical_artists WHERE id = ANY($1)", &[&others])?;
+        trans.commit()?;
+        let mut items = vec![canonical_artist];
+        CanonicalArtist::set_relations(conn, &mut items)?;
+        Ok(items[0].clone())
+    }
+
+    /// This function removes the artist from its canonical artist,
+    /// and the canonical artist is deleted when it has no other artists.
+    pub fn unmerge(conn: &Connection, artist: &Artist) -> Result<(), Error> {
+        let canonical_artist_id = match artist.canonical_artist_id {
+            Some(id) => id,
+            None     => return Ok(()),
+        };
+        let trans = conn.transaction()?;
+        trans.execute("UPDATE artists SET canonical_artist_id = NULL WHERE id = $1", &[&artist.id])?;
+        trans.execute("DELETE FROM canonical_artists WHERE id = $1 AND NOT EXISTS (
+                         SELECT 1 FROM artists WHERE artists.canonical_artist_id = $1
+                       )", &[&canonical_artist_id])?;
+        trans.commit()
+    }
+}
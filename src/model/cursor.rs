@@ -0,0 +1,98 @@
+use std::str;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+use error::Error;
+use super::Sort;
+
+static TIMESTAMP_FORMAT: &'static str = "%Y-%m-%dT%H:%M:%S%.6f";
+
+/// The position of the last item of a page.
+///
+/// The items of a list are ordered by a timestamp and then by their ids,
+/// so the next page starts right after the cursor even if rows are updated in between.
+/// The position is meaningful only in the order it was taken from, so the cursor keeps the sort.
+/// Clients get the cursor as an opaque string, and shouldn't rely on its format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub sort: String,
+    pub at:   NaiveDateTime,
+    pub id:   Uuid,
+}
+
+impl Cursor {
+    pub fn new(sort: &Sort, at: NaiveDateTime, id: Uuid) -> Cursor {
+        Cursor {
+            sort: sort.to_param(),
+            at:   at,
+            id:   id,
+        }
+    }
+
+    /// This function rejects a cursor that is used with another sort than the one it was taken from.
+    pub fn check(&self, sort: &Sort) -> Result<(), Error> {
+        if self.sort == sort.to_param() {
+            Ok(())
+        } else {
+            Err(Error::Unprocessable)
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}|{}|{}", self.sort, self.at.format(TIMESTAMP_FORMAT), self.id)
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn decode(value: &str) -> Result<Cursor, Error> {
+        if value.len() % 2 != 0 {
+            return Err(Error::Unprocessable);
+        }
+        let mut bytes = Vec::new();
+        for chunk in value.as_bytes().chunks(2) {
+            let hex = str::from_utf8(chunk).map_err(|_| Error::Unprocessable)?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| Error::Unprocessable)?);
+        }
+        let raw = String::from_utf8(bytes).map_err(|_| Error::Unprocessable)?;
+        let mut parts = raw.splitn(3, '|');
+        let sort = match parts.next() {
+            Some(sort) if !sort.is_empty() => sort,
+            _                              => return Err(Error::Unprocessable),
+        };
+        let at = parts.next()
+            .and_then(|at| NaiveDateTime::parse_from_str(at, TIMESTAMP_FORMAT).ok())
+            .ok_or(Error::Unprocessable)?;
+        let id = parts.next()
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .ok_or(Error::Unprocessable)?;
+        Ok(Cursor {
+            sort: sort.to_string(),
+            at:   at,
+            id:   id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_and_decode() {
+        let at     = NaiveDateTime::from_timestamp(1500000000, 123456000);
+        let cursor = Cursor::new(&Sort::new("published_at", true), at, Uuid::new_v4());
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert_eq!(cursor.sort, "-published_at");
+        assert!(Cursor::decode("abc").is_err());
+        assert!(Cursor::decode("zz").is_err());
+        assert!(Cursor::decode("").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let cursor = Cursor::new(&Sort::default(), NaiveDateTime::from_timestamp(0, 0), Uuid::new_v4());
+        assert!(cursor.check(&Sort::default()).is_ok());
+        assert!(cursor.check(&Sort::new("created_at", false)).is_err());
+        assert!(cursor.check(&Sort::new("updated_at", true)).is_err());
+    }
+}
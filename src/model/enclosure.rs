@@ -1,32 +1,37 @@
 use uuid::Uuid;
+use std::collections::BTreeMap;
 use error::Error;
 use model::provider::Provider;
-use super::{conn, Model};
+use super::{Connection, Model};
 
 pub trait Enclosure<'a> where Self: Model<'a> {
     fn new(provider: Provider, identifier: String) -> Self;
     fn set_owner_id(&mut self, owner_id: Option<String>) -> &mut Self;
     fn set_url(&mut self, url: String) -> &mut Self;
-    fn fetch_props(&mut self) -> Result<(), Error>;
-    fn find_by_entry_id(entry_id: Uuid) -> Vec<Self>;
-    fn find_by(provider: &Provider, identifier: &str) -> Result<Self, Error> {
-        let conn = conn().unwrap();
+    fn fetch_props(&mut self, conn: &Connection) -> Result<(), Error>;
+    fn find_by_entry_ids(conn: &Connection, entry_ids: &[Uuid]) -> Result<BTreeMap<Uuid, Vec<Self>>, Error>;
+    fn find_by_entry_id(conn: &Connection, entry_id: Uuid) -> Vec<Self> {
+        Self::find_by_entry_ids(conn, &[entry_id]).ok()
+            .and_then(|mut items| items.remove(&entry_id))
+            .unwrap_or(vec![])
+    }
+    fn find_by(conn: &Connection, provider: &Provider, identifier: &str) -> Result<Self, Error> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM {}
                      WHERE provider = $1 AND identifier = $2
                      ORDER BY published_at DESC",
-                     Self::props_str(""), Self::table_name())).unwrap();
-        let rows = stmt.query(&[&(*provider).to_string(), &identifier]).unwrap();
+                     Self::props_str(""), Self::table_name()))?;
+        let rows = stmt.query(&[&(*provider).to_string(), &identifier])?;
         let items = Self::rows_to_items(rows);
         if items.len() > 0 {
             return Ok(items[0].clone());
         }
         return Err(Error::NotFound)
     }
-    fn find_or_create(provider: Provider, identifier: String) -> Result<Self, Error> {
-        return match Self::find_by(&provider, &identifier) {
+    fn find_or_create(conn: &Connection, provider: Provider, identifier: String) -> Result<Self, Error> {
+        return match Self::find_by(conn, &provider, &identifier) {
             Ok(item) => Ok(item),
-            Err(_)   => Self::new(provider, identifier).create()
+            Err(_)   => Self::new(provider, identifier).create(conn)
         }
     }
 }
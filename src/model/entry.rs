@@ -1,14 +1,17 @@
-use postgres;
+use super::{Connection, Row};
 use uuid::Uuid;
 use error::Error;
 use chrono::{NaiveDateTime, Utc};
-use super::{conn, Model};
+use super::Model;
 use model::enclosure::Enclosure;
 use scraper;
+use raw;
 use Track;
 use Playlist;
 use Album;
-use model::PaginatedCollection;
+use model::{PaginatedCollection, Page, Cursor, Sort, SearchIndex};
+use search;
+use search::SearchType;
 use serde_json::Value;
 use feed_rs;
 
@@ -75,7 +78,16 @@ impl<'a> Model<'a> for Entry {
             .map(|&p| format!("{}{}", prefix, p))
             .collect::<Vec<String>>().join(",")
     }
-    fn row_to_item(row: postgres::rows::Row) -> Entry {
+    fn filter_fields() -> &'static [&'static str] {
+        &["feed_id", "locale"]
+    }
+    fn sort_fields() -> &'static [&'static str] {
+        &["updated_at", "created_at", "published"]
+    }
+    fn published_field() -> Option<&'static str> {
+        Some("published")
+    }
+    fn row_to_item(row: &Row) -> Entry {
         Entry {
             id:          row.get(0),
             url:         row.get(1),
@@ -98,13 +110,12 @@ impl<'a> Model<'a> for Entry {
             feed_id:     row.get(18),
             created_at:  row.get(19),
             updated_at:  row.get(20),
-            tracks:      Track::find_by_entry_id(row.get(0)),
-            playlists:   Playlist::find_by_entry_id(row.get(0)),
-            albums:      Album::find_by_entry_id(row.get(0)),
+            tracks:      Vec::new(),
+            playlists:   Vec::new(),
+            albums:      Vec::new(),
         }
     }
-    fn create(&self) -> Result<Entry, Error> {
-        let conn = conn()?;
+    fn create(&self, conn: &Connection) -> Result<Entry, Error> {
         let stmt = conn.prepare("INSERT INTO entries (url, published) VALUES ($1, $2) RETURNING id")?;
         let rows = stmt.query(&[&self.url, &NaiveDateTime::from_timestamp(0, 0)])?;
         let mut entry = self.clone();
@@ -113,9 +124,8 @@ impl<'a> Model<'a> for Entry {
         }
         Ok(entry)
     }
-    fn save(&mut self) -> Result<(), Error> {
+    fn save(&mut self, conn: &Connection) -> Result<(), Error> {
         self.updated_at = Utc::now().naive_utc();
-        let conn = conn()?;
         let stmt = conn.prepare("UPDATE entries SET
                                    url         = $2,
                                    title       = $3,
@@ -159,13 +169,38 @@ impl<'a> Model<'a> for Entry {
                      &self.feed_id,
                      &self.created_at,
                      &self.updated_at])?;
+        self.update_search_index(conn)
+    }
+    fn set_relations(conn: &Connection, entries: &mut Vec<Entry>) -> Result<(), Error> {
+        let ids: Vec<Uuid> = entries.iter().map(|i| i.id).collect();
+        let tracks_map    = Track::find_by_entry_ids(conn, &ids)?;
+        let playlists_map = Playlist::find_by_entry_ids(conn, &ids)?;
+        let albums_map    = Album::find_by_entry_ids(conn, &ids)?;
+        for entry in entries {
+            if let Some(tracks) = tracks_map.get(&entry.id) {
+                entry.tracks = tracks.clone()
+            }
+            if let Some(playlists) = playlists_map.get(&entry.id) {
+                entry.playlists = playlists.clone()
+            }
+            if let Some(albums) = albums_map.get(&entry.id) {
+                entry.albums = albums.clone()
+            }
+        }
         Ok(())
     }
 }
 
 impl Entry {
-    pub fn new(url: String) -> Result<Entry, Error> {
-        let conn = conn()?;
+    /// This function indexes the title, the summary and the text of the entry for `search`.
+    pub fn update_search_index(&self, conn: &Connection) -> Result<(), Error> {
+        let tokens = search::weigh_tokens(&[(self.title.as_ref().map(|s| s.as_str()), 3.0),
+                                            (self.summary.as_ref().map(|s| s.as_str()), 1.0),
+                                            (self.text.as_ref().map(|s| s.as_str()), 1.0)]);
+        SearchIndex::update_in(conn, &SearchType::Entry.to_string(), self.id, &tokens)
+    }
+
+    pub fn new(conn: &Connection, url: String) -> Result<Entry, Error> {
         let stmt = conn.prepare("INSERT INTO entries (url) VALUES ($1) RETURNING id")?;
         let rows = stmt.query(&[&url])?;
         for row in rows.iter() {
@@ -203,54 +238,66 @@ impl Entry {
         Err(Error::Unexpected)
     }
 
-    pub fn find_by_url(url: &str) -> Result<Entry, Error> {
-        let conn = conn()?;
+    pub fn find_by_url(conn: &Connection, url: &str) -> Result<Entry, Error> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM entries
                         WHERE url = $1", Self::props_str("")))?;
         let rows = stmt.query(&[&url])?;
-        let entries = Entry::rows_to_items(rows);
+        let mut entries = Entry::rows_to_items(rows);
+        Entry::set_relations(conn, &mut entries)?;
         if entries.len() > 0 {
             return Ok(entries[0].clone());
         }
         return Err(Error::NotFound)
     }
 
-    pub fn find_or_create_by_url(url: String) -> Result<Entry, Error> {
-        match Entry::find_by_url(&url) {
+    pub fn find_or_create_by_url(conn: &Connection, url: String) -> Result<Entry, Error> {
+        match Entry::find_by_url(conn, &url) {
             Ok(entry) => Ok(entry),
-            Err(Error::NotFound) => Entry::create_by_url(url),
+            Err(Error::NotFound) => Entry::create_by_url(conn, url),
             Err(e) => Err(e),
         }
     }
 
-    pub fn find_by_feed_id(feed_id: Uuid, newer_than: Option<NaiveDateTime>, page: i64, per_page: i64) -> PaginatedCollection<Entry> {
-        let conn = conn().unwrap();
-        let stmt = conn.prepare(
-            &format!("SELECT {} FROM entries
-                        WHERE entries.feed_id = $1 AND entries.published >= $2
-                        ORDER BY entries.published DESC
-                        LIMIT $4 OFFSET $3",
-                     Entry::props_str(""))).unwrap();
-        let offset = page * per_page;
+    pub fn find_by_feed_id(conn: &Connection, feed_id: Uuid, newer_than: Option<NaiveDateTime>, page: &Page) -> Result<PaginatedCollection<Entry>, Error> {
         let published = newer_than.unwrap_or(NaiveDateTime::from_timestamp(1000, 0)); // ignore 0 timestamp
-        let rows   = stmt.query(&[&feed_id, &published, &offset, &per_page]).unwrap();
-        let items  = Self::rows_to_items(rows);
-        let mut total: i64 = 0;
-        let sql = "SELECT COUNT(*) FROM entries WHERE entries.feed_id = $1";
-        for row in conn.query(&sql, &[&feed_id]).unwrap().iter() {
-            total = row.get(0);
-        }
-        PaginatedCollection {
-            page:     page,
-            per_page: per_page,
-            total:    total,
-            items:    items,
+        let sort      = Sort::new("published", true);
+        let limit     = page.limit();
+        let offset    = page.offset();
+        let rows = if let Some(ref cursor) = page.cursor {
+            cursor.check(&sort)?;
+            let stmt = conn.prepare(
+                &format!("SELECT {} FROM entries
+                            WHERE entries.feed_id = $1 AND entries.published >= $2
+                              AND (entries.published, entries.id) < ($5, $6)
+                            ORDER BY entries.published DESC, entries.id DESC
+                            LIMIT $4 OFFSET $3",
+                         Entry::props_str("")))?;
+            stmt.query(&[&feed_id, &published, &offset, &limit, &cursor.at, &cursor.id])?
+        } else {
+            let stmt = conn.prepare(
+                &format!("SELECT {} FROM entries
+                            WHERE entries.feed_id = $1 AND entries.published >= $2
+                            ORDER BY entries.published DESC, entries.id DESC
+                            LIMIT $4 OFFSET $3",
+                         Entry::props_str("")))?;
+            stmt.query(&[&feed_id, &published, &offset, &limit])?
+        };
+        let items   = Self::rows_to_items(rows);
+        let cursors = items.iter().map(|e| Cursor::new(&sort, e.published, e.id)).collect();
+        let mut total = None;
+        if page.with_total {
+            let sql = "SELECT COUNT(*) FROM entries WHERE entries.feed_id = $1";
+            for row in conn.query(&sql, &[&feed_id])?.iter() {
+                total = Some(row.get(0));
+            }
         }
+        let mut entries = PaginatedCollection::paginate(page, items, cursors, total);
+        Self::set_relations(conn, &mut entries.items)?;
+        Ok(entries)
     }
 
-    pub fn create_by_url(url: String) -> Result<Entry, Error> {
-        let conn = conn()?;
+    pub fn create_by_url(conn: &Connection, url: String) -> Result<Entry, Error> {
         let stmt = conn.prepare("INSERT INTO entries (url, published) VALUES ($1, $2) RETURNING id")?;
         let rows = stmt.query(&[&url, &NaiveDateTime::from_timestamp(0, 0)])?;
         for row in rows.iter() {
@@ -292,8 +339,8 @@ impl Entry {
         self.published.timestamp() >= 1000 && self.feed_id != None
     }
 
-    pub fn find_or_create_by_url_if_invalid(url: String) -> Result<Entry, Error> {
-        match Entry::find_by_url(&url) {
+    pub fn find_or_create_by_url_if_invalid(conn: &Connection, url: String) -> Result<Entry, Error> {
+        match Entry::find_by_url(conn, &url) {
             Ok(entry) => {
                 if !entry.is_valid() {
                     Ok(entry)
@@ -302,7 +349,7 @@ impl Entry {
                 }
             },
             Err(_) => {
-                Entry::create_by_url(url)
+                Entry::create_by_url(conn, url)
             }
         }
     }
@@ -333,8 +380,8 @@ impl Entry {
         !title.is_empty()
     }
 
-    pub fn playlistify(&mut self) -> Result<(), Error> {
-        let product = scraper::scrape(&self.url)?;
+    pub fn playlistify(&mut self, conn: &Connection) -> Result<(), Error> {
+        let product = scraper::scrape(conn, &self.url)?;
         self.content = Some(product.content);
         self.text = Some(product.text);
         match product.og_obj {
@@ -348,62 +395,97 @@ impl Entry {
             },
             None => (),
         }
-        for t in product.tracks {
-            let mut track = t.clone();
-            track.save()?;
-            match self.tracks.iter().find(|&t| t.id == track.id) {
-                Some(_) => (),
-                None    => self.add_track(track.clone())?,
+        let mut tracks = product.tracks;
+        tracks.append(&mut self.raw_tracks(conn));
+        self.add_enclosures(conn, tracks, product.playlists, product.albums)
+    }
+
+    /// This function saves the enclosures and links them to the entry in one transaction,
+    /// so that the entry never has a part of them when something fails halfway.
+    /// The playlists and the albums of the scraper may not be created yet, so they are found or created in it too.
+    pub fn add_enclosures(&mut self,
+                          conn:          &Connection,
+                          mut tracks:    Vec<Track>,
+                          mut playlists: Vec<Playlist>,
+                          mut albums:    Vec<Album>) -> Result<(), Error> {
+        let trans = conn.transaction()?;
+        for track in tracks.iter_mut() {
+            track.save(&trans)?;
+            Entry::upsert_relation(&trans, "track", track.id, self.id)?;
+        }
+        for playlist in playlists.iter_mut() {
+            playlist.id = Playlist::find_or_create(&trans, playlist.provider, playlist.identifier.to_string())?.id;
+            playlist.save(&trans)?;
+            Entry::upsert_relation(&trans, "playlist", playlist.id, self.id)?;
+        }
+        for album in albums.iter_mut() {
+            album.id = Album::find_or_create(&trans, album.provider, album.identifier.to_string())?.id;
+            album.save(&trans)?;
+            Entry::upsert_relation(&trans, "album", album.id, self.id)?;
+        }
+        trans.commit()?;
+        for track in tracks {
+            if !self.tracks.iter().any(|t| t.id == track.id) {
+                self.tracks.push(track);
             }
         }
-        for p in product.playlists {
-            let new_playlist = Playlist::find_or_create(p.provider, p.identifier.to_string())?;
-            let mut playlist = p.clone();
-            playlist.id      = new_playlist.id;
-            playlist.save()?;
-            match self.playlists.iter().find(|&p| p.id == playlist.id) {
-                Some(_) => (),
-                None    => self.add_playlist(playlist.clone())?,
+        for playlist in playlists {
+            if !self.playlists.iter().any(|p| p.id == playlist.id) {
+                self.playlists.push(playlist);
             }
         }
-        for a in product.albums {
-            let new_album = Album::find_or_create(a.provider, a.identifier.to_string())?;
-            let mut album = a.clone();
-            album.id      = new_album.id;
-            album.save()?;
-            match self.albums.iter().find(|&a| a.id == album.id) {
-                Some(_) => (),
-                None    => self.add_album(album.clone())?,
+        for album in albums {
+            if !self.albums.iter().any(|a| a.id == album.id) {
+                self.albums.push(album);
             }
         }
-
         Ok(())
     }
 
-    pub fn add_track(&mut self, track: Track) -> Result<(), Error> {
-        let conn = conn()?;
-        let stmt = conn.prepare("INSERT INTO track_entries (track_id, entry_id)
-                                 VALUES ($1, $2)")?;
-        stmt.query(&[&track.id, &self.id])?;
+    /// This function creates raw tracks of the audio files in the enclosures
+    /// of the feed entry, e.g. podcast episodes.
+    pub fn raw_tracks(&self, conn: &Connection) -> Vec<Track> {
+        let links = match self.enclosure {
+            Value::Array(ref links) => links.clone(),
+            Value::Object(_)        => vec![self.enclosure.clone()],
+            _                       => vec![],
+        };
+        links.iter().filter_map(|link| {
+            let href = link["href"].as_str()?;
+            let mime = link["mime_type"].as_str().or(link["type"].as_str()).unwrap_or("");
+            if mime.starts_with(raw::AUDIO_MIME_PREFIX) || raw::is_audio_file_url(href) {
+                Some(Track::from_raw_url(conn, href))
+            } else {
+                None
+            }
+        }).collect()
+    }
+
+    pub fn add_track(&mut self, conn: &Connection, track: Track) -> Result<(), Error> {
+        Entry::upsert_relation(conn, "track", track.id, self.id)?;
         self.tracks.push(track);
         Ok(())
     }
 
-    pub fn add_playlist(&mut self, playlist: Playlist) -> Result<(), Error> {
-        let conn = conn()?;
-        let stmt = conn.prepare("INSERT INTO playlist_entries (playlist_id, entry_id)
-                                 VALUES ($1, $2)")?;
-        stmt.query(&[&playlist.id, &self.id])?;
+    pub fn add_playlist(&mut self, conn: &Connection, playlist: Playlist) -> Result<(), Error> {
+        Entry::upsert_relation(conn, "playlist", playlist.id, self.id)?;
         self.playlists.push(playlist);
         Ok(())
     }
 
-    pub fn add_album(&mut self, album: Album) -> Result<(), Error> {
-        let conn = conn()?;
-        let stmt = conn.prepare("INSERT INTO album_entries (album_id, entry_id)
-                                 VALUES ($1, $2)")?;
-        stmt.query(&[&album.id, &self.id])?;
+    pub fn add_album(&mut self, conn: &Connection, album: Album) -> Result<(), Error> {
+        Entry::upsert_relation(conn, "album", album.id, self.id)?;
         self.albums.push(album);
         Ok(())
     }
+
+    fn upsert_relation(conn: &Connection, name: &str, id: Uuid, entry_id: Uuid) -> Result<(), Error> {
+        let stmt = conn.prepare(&format!("INSERT INTO {0}_entries
+                                            ({0}_id, entry_id, created_at, updated_at)
+                                          VALUES ($1, $2, $3, $3)
+                                          ON CONFLICT ({0}_id, entry_id)
+                                          DO UPDATE SET updated_at=$3", name))?;
+        stmt.query(&[&id, &entry_id, &Utc::now().naive_utc()])?;
+        Ok(())
+    }
 }
@@ -1,9 +1,9 @@
-use postgres;
+use super::Row;
 use uuid::Uuid;
 use error::Error;
 use chrono::{NaiveDateTime, Utc};
 use feed_rs;
-use super::{conn, Model, Entry};
+use super::{Connection, Model, Entry};
 use model::state::State;
 use rss;
 
@@ -54,7 +54,10 @@ impl<'a> Model<'a> for Feed {
             .map(|&p| format!("{}{}", prefix, p))
             .collect::<Vec<String>>().join(",")
     }
-    fn row_to_item(row: postgres::rows::Row) -> Feed {
+    fn filter_fields() -> &'static [&'static str] {
+        &["state", "language"]
+    }
+    fn row_to_item(row: &Row) -> Feed {
         Feed {
             id:           row.get(0),
             url:          row.get(1),
@@ -75,8 +78,7 @@ impl<'a> Model<'a> for Feed {
             updated_at:   row.get(14),
         }
     }
-    fn create(&self) -> Result<Feed, Error> {
-        let conn = conn()?;
+    fn create(&self, conn: &Connection) -> Result<Feed, Error> {
         let stmt = conn.prepare("INSERT INTO feeds (id, u) VALUES ($1)")?;
         let rows = stmt.query(&[
             &self.id,
@@ -101,9 +103,8 @@ impl<'a> Model<'a> for Feed {
         }
         Ok(feed)
     }
-    fn save(&mut self) -> Result<(), Error> {
+    fn save(&mut self, conn: &Connection) -> Result<(), Error> {
         self.updated_at = Utc::now().naive_utc();
-        let conn = conn()?;
         let stmt = conn.prepare("UPDATE feeds SET
                                    url          = $2,
                                    title        = $3,
@@ -140,8 +141,7 @@ impl<'a> Model<'a> for Feed {
 }
 
 impl Feed {
-    pub fn find_by_url(url: &str) -> Result<Feed, Error> {
-        let conn = conn()?;
+    pub fn find_by_url(conn: &Connection, url: &str) -> Result<Feed, Error> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM feeds
                         WHERE url = $1", Self::props_str("")))?;
@@ -153,15 +153,14 @@ impl Feed {
         return Err(Error::NotFound)
     }
 
-    pub fn find_or_create_by_url(url: String) -> Result<Feed, Error> {
-        match Feed::find_by_url(&url) {
+    pub fn find_or_create_by_url(conn: &Connection, url: String) -> Result<Feed, Error> {
+        match Feed::find_by_url(conn, &url) {
             Ok(feed) => Ok(feed),
-            Err(_)   => Feed::create_by_url(url)
+            Err(_)   => Feed::create_by_url(conn, url)
         }
     }
 
-    pub fn create_by_url(url: String) -> Result<Feed, Error> {
-        let conn = conn()?;
+    pub fn create_by_url(conn: &Connection, url: String) -> Result<Feed, Error> {
         let stmt = conn.prepare("INSERT INTO feeds (url) VALUES ($1) RETURNING id")?;
         let rows = stmt.query(&[&url])?;
         for row in rows.iter() {
@@ -207,7 +206,7 @@ impl Feed {
         self.cover_url    = rss_feed.cover_url;
     }
 
-    pub fn crawl(&mut self) -> Result<Vec<Entry>, Error> {
+    pub fn crawl(&mut self, conn: &Connection) -> Result<Vec<Entry>, Error> {
         let rss_feed      = rss::fetch(&self.url)?;
         let mut entries   = vec![];
         for entry in rss_feed.entries {
@@ -215,13 +214,13 @@ impl Feed {
                 continue;
             }
             let alt = entry.alternate.first().unwrap();
-            match Entry::find_or_create_by_url_if_invalid(alt.href.to_string()) {
+            match Entry::find_or_create_by_url_if_invalid(conn, alt.href.to_string()) {
                 Ok(mut e) => {
                     println!("Found new entry: {}", e.url);
                     e.update_with_feed_entry(&entry);
                     e.feed_id = Some(self.id);
-                    let _ = e.playlistify();
-                    if let Ok(_) = e.save() {
+                    let _ = e.playlistify(conn);
+                    if let Ok(_) = e.save(conn) {
                         entries.push(e);
                     }
                 },
@@ -0,0 +1,194 @@
+use super::{Connection, Row};
+use uuid::Uuid;
+use std::fmt;
+use std::collections::BTreeMap;
+use chrono::{NaiveDateTime, Utc};
+
+use error::Error;
+use super::Model;
+
+static PROPS: [&'static str; 4]  = ["id",
+                                    "name",
+                                    "created_at",
+                                    "updated_at"];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Genre {
+    pub id:         Uuid,
+    pub name:       String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl PartialEq for Genre {
+    fn eq(&self, g: &Genre) -> bool {
+        return self.name == g.name
+    }
+}
+
+impl fmt::Display for Genre {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<'a> Model<'a> for Genre {
+    fn table_name() -> String {
+        "genres".to_string()
+    }
+    fn props_str(prefix: &str) -> String {
+        PROPS
+            .iter()
+            .map(|&p| format!("{}{}", prefix, p))
+            .collect::<Vec<String>>().join(",")
+    }
+    fn search_prop() -> &'static str {
+        "name"
+    }
+    fn row_to_item(row: &Row) -> Genre {
+        Genre {
+            id:         row.get(0),
+            name:       row.get(1),
+            created_at: row.get(2),
+            updated_at: row.get(3),
+        }
+    }
+    fn create(&self, conn: &Connection) -> Result<Genre, Error> {
+        let stmt = conn.prepare("INSERT INTO genres (name) VALUES ($1) RETURNING id")?;
+        let rows = stmt.query(&[&self.name])?;
+        let mut genre = self.clone();
+        for row in rows.iter() {
+            genre.id = row.get(0);
+        }
+        Ok(genre)
+    }
+    fn save(&mut self, conn: &Connection) -> Result<(), Error> {
+        self.updated_at = Utc::now().naive_utc();
+        let stmt = conn.prepare("UPDATE genres SET
+                                      name       = $2,
+                                      created_at = $3,
+                                      updated_at = $4
+                                      WHERE id = $1")?;
+        let result = stmt.query(&[&self.id,
+                                  &self.name,
+                                  &self.created_at,
+                                  &self.updated_at]);
+        match result {
+            Ok(_)  => Ok(()),
+            Err(_) => Err(Error::Unexpected)
+        }
+    }
+}
+
+impl Genre {
+    pub fn new(name: String) -> Genre {
+        Genre {
+            id:         Uuid::new_v4(),
+            name:       name,
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+
+    /// Genre names are compared case-insensitively,
+    /// so "Hip Hop" and "hip hop" are the same genre.
+    pub fn find_by_name(conn: &Connection, name: &str) -> Result<Genre, Error> {
+        let stmt = conn.prepare(&format!("SELECT {} FROM genres WHERE lower(name) = lower($1)",
+                                         Genre::props_str("")))?;
+        let rows = stmt.query(&[&name])?;
+        let items = Genre::rows_to_items(rows);
+        if items.len() > 0 {
+            return Ok(items[0].clone());
+        }
+        Err(Error::NotFound)
+    }
+
+    pub fn find_or_create_by_name(conn: &Connection, name: &str) -> Result<Genre, Error> {
+        match Genre::find_by_name(conn, name) {
+            Ok(genre)            => Ok(genre),
+            Err(Error::NotFound) => Genre::new(name.trim().to_string()).create(conn),
+            Err(e)               => Err(e),
+        }
+    }
+
+    fn find_by_relations(conn: &Connection, ids: &Vec<Uuid>, name: &str) -> Result<BTreeMap<Uuid, Vec<Genre>>, Error> {
+        let sql = format!("SELECT {0}, {1}_genres.{1}_id FROM genres
+                      LEFT OUTER JOIN {1}_genres ON {1}_genres.genre_id = genres.id
+                      WHERE {1}_genres.{1}_id = ANY($1) ORDER BY {1}_genres.created_at ASC",
+                          Genre::props_str("genres."), name);
+        let stmt = conn.prepare(&sql)?;
+        let rows = stmt.query(&[&ids])?;
+        let mut items: BTreeMap<Uuid, Vec<Genre>> = BTreeMap::new();
+        for id in ids.iter() {
+            items.insert(*id, vec![]);
+        }
+        for row in rows.iter() {
+            let id: Uuid = row.get(PROPS.len());
+            if let Some(genres) = items.get_mut(&id) {
+                genres.push(Self::row_to_item(row))
+            }
+        }
+        Ok(items)
+    }
+
+    pub fn find_by_tracks(conn: &Connection, track_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Genre>>, Error> {
+        Genre::find_by_relations(conn, track_ids, "track")
+    }
+
+    pub fn find_by_albums(conn: &Connection, album_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Genre>>, Error> {
+        Genre::find_by_relations(conn, album_ids, "album")
+    }
+
+    pub fn find_by_artists(conn: &Connection, artist_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Genre>>, Error> {
+        Genre::find_by_relations(conn, artist_ids, "artist")
+    }
+
+    /// This function links the genres of the names to a track, an album or an artist.
+    /// The genres that are already linked are kept, so each provider can add its own.
+    pub fn add_to(conn: &Connection, name: &str, id: Uuid, genre_names: &Vec<String>) -> Result<Vec<Genre>, Error> {
+        let stmt = conn.prepare(&format!("INSERT INTO {0}_genres ({0}_id, genre_id) VALUES ($1, $2)
+                                          ON CONFLICT DO NOTHING", name))?;
+        let mut genres: Vec<Genre> = vec![];
+        for genre_name in Genre::normalize_names(genre_names).iter() {
+            let genre = Genre::find_or_create_by_name(conn, genre_name)?;
+            if genres.contains(&genre) {
+                continue;
+            }
+            stmt.query(&[&id, &genre.id])?;
+            genres.push(genre);
+        }
+        Ok(genres)
+    }
+
+    fn normalize_names(names: &Vec<String>) -> Vec<String> {
+        let mut items: Vec<String> = vec![];
+        for name in names.iter().map(|n| n.trim()) {
+            if name.is_empty() || items.iter().any(|i| i.to_lowercase() == name.to_lowercase()) {
+                continue;
+            }
+            items.push(name.to_string());
+        }
+        items
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Genre;
+
+    #[test]
+    fn test_new() {
+        let genre = Genre::new("Deep House".to_string());
+        assert_eq!(genre.name, "Deep House");
+        assert_eq!(genre, Genre::new("Deep House".to_string()));
+    }
+
+    #[test]
+    fn test_normalize_names() {
+        let names = vec!["Hip Hop".to_string(),
+                         " hip hop ".to_string(),
+                         "".to_string(),
+                         "Jazz".to_string()];
+        assert_eq!(Genre::normalize_names(&names), vec!["Hip Hop", "Jazz"]);
+    }
+}
@@ -1,4 +1,4 @@
-use postgres;
+use super::{Connection, Row};
 use uuid::Uuid;
 use std::fmt;
 use std::collections::BTreeMap;
@@ -10,8 +10,10 @@ use youtube;
 use youtube::HasThumbnail;
 use soundcloud;
 use spotify;
+use deezer;
+use adapter;
 use error::Error;
-use super::{conn, Model};
+use super::Model;
 use model::provider::Provider;
 use model::state::State;
 use model::enclosure::Enclosure;
@@ -74,7 +76,16 @@ impl<'a> Model<'a> for Playlist {
             .map(|&p| format!("{}{}", prefix, p))
             .collect::<Vec<String>>().join(",")
     }
-    fn row_to_item(row: postgres::rows::Row) -> Playlist {
+    fn filter_fields() -> &'static [&'static str] {
+        &["provider", "state", "owner_id"]
+    }
+    fn sort_fields() -> &'static [&'static str] {
+        &["updated_at", "created_at", "published_at"]
+    }
+    fn published_field() -> Option<&'static str> {
+        Some("published_at")
+    }
+    fn row_to_item(row: &Row) -> Playlist {
         Playlist {
             id:            row.get(0),
             provider:      Provider::new(row.get(1)),
@@ -94,11 +105,11 @@ impl<'a> Model<'a> for Playlist {
             tracks:        vec![],
         }
     }
-    fn create(&self) -> Result<Playlist, Error> {
-        let conn = conn()?;
-        let stmt = conn.prepare("INSERT INTO playlists (provider, identifier, url, title)
-                                 VALUES ($1, $2, $3, $4) RETURNING id")?;
-        let rows = stmt.query(&[&self.provider.to_string(), &self.identifier, &self.url, &self.title])?;
+    fn create(&self, conn: &Connection) -> Result<Playlist, Error> {
+        let stmt = conn.prepare("INSERT INTO playlists (provider, identifier, url, title, state)
+                                 VALUES ($1, $2, $3, $4, $5) RETURNING id")?;
+        let rows = stmt.query(&[&self.provider.to_string(), &self.identifier, &self.url, &self.title,
+                                 &self.state.to_string()])?;
         let mut playlist = self.clone();
         for row in rows.iter() {
             playlist.id = row.get(0);
@@ -106,9 +117,10 @@ impl<'a> Model<'a> for Playlist {
         Ok(playlist)
     }
 
-    fn save(&mut self) -> Result<(), Error> {
+    /// This function updates the row with the connection, which can be a transaction,
+    /// e.g. the one that `Entry::add_enclosures` opens.
+    fn save(&mut self, conn: &Connection) -> Result<(), Error> {
         self.updated_at = Utc::now().naive_utc();
-        let conn = conn()?;
         let stmt = conn.prepare("UPDATE playlists SET
                                       provider      = $2,
                                       identifier    = $3,
@@ -147,9 +159,9 @@ impl<'a> Model<'a> for Playlist {
         }
     }
 
-    fn set_relations(playlists: &mut Vec<Playlist>) -> Result<(), Error> {
+    fn set_relations(conn: &Connection, playlists: &mut Vec<Playlist>) -> Result<(), Error> {
         let ids: Vec<Uuid> = playlists.iter().map(|i| i.id).collect();
-        let playlist_tracks_map = PlaylistTrack::find_by_playlist_ids(ids.clone())?;
+        let playlist_tracks_map = PlaylistTrack::find_by_playlist_ids(conn, ids.clone())?;
         for playlist in playlists {
             if let Some(playlist_tracks) = playlist_tracks_map.get(&playlist.id) {
                 playlist.tracks = playlist_tracks.clone()
@@ -189,7 +201,7 @@ impl<'a> Enclosure<'a> for Playlist {
             published_at:  Utc::now().naive_utc(),
             created_at:    Utc::now().naive_utc(),
             updated_at:    Utc::now().naive_utc(),
-            state:         State::Alive,
+            state:         State::Pending,
             tracks:        vec![],
         }
     }
@@ -201,67 +213,44 @@ impl<'a> Enclosure<'a> for Playlist {
         self.owner_id = owner_id;
         self
     }
-    fn fetch_props(&mut self) -> Result<(), Error> {
-        match self.provider {
-            Provider::YouTube => {
-                let items = youtube::fetch_playlist_items(&self.identifier)
-                    .map(|res| res.items)
-                    .unwrap_or(vec![]);
-                if let Ok(res) = youtube::fetch_playlist(&self.identifier) {
-                    if let Some(playlist) = res.items.iter().nth(0) {
-                        self.update_with_yt_playlist(&playlist, &items);
-                    }
-                }
-                self.disable();
-            },
-            Provider::SoundCloud => {
-                match soundcloud::fetch_playlist(&self.identifier) {
-                    Ok(playlist) => self.update_with_sc_playlist(&playlist),
-                    Err(_)       => self.disable()
-                };
-            },
-            Provider::AppleMusic => {
-                let country = apple_music::country(&self.url);
-                match apple_music::fetch_playlist(&self.identifier, &country) {
-                    Ok(playlist) => self.update_with_am_playlist(&playlist),
-                    Err(_)       => self.disable(),
-                };
-            },
-            Provider::Spotify => {
-                if let Some(owner_id) = self.clone().owner_id {
-                    match spotify::fetch_playlist(&owner_id, &self.identifier) {
-                        Ok(playlist) => {
-                            self.update_with_sp_playlist(&playlist);
-                        },
-                        Err(_)       => {
-                            self.disable();
-                        },
-                    }
-                }
-            },
-            _ => (),
-        };
-        match self.state {
-            State::Alive => Ok(()),
-            State::Dead  => Err(Error::NotFound),
+    fn fetch_props(&mut self, conn: &Connection) -> Result<(), Error> {
+        if let Some(adapter) = adapter::find(&self.provider) {
+            match adapter.fetch_playlist(conn, self) {
+                Ok(_)                          => (),
+                Err(ref e) if e.is_not_found() => { self.disable(); },
+                Err(e)                         => return Err(e),
+            }
+        }
+        if self.state.is_gone() {
+            Err(Error::NotFound)
+        } else {
+            Ok(())
         }
     }
-    fn find_by_entry_id(entry_id: Uuid) -> Vec<Playlist> {
-        let conn = conn().unwrap();
+    fn find_by_entry_ids(conn: &Connection, entry_ids: &[Uuid]) -> Result<BTreeMap<Uuid, Vec<Playlist>>, Error> {
         let stmt = conn.prepare(
-            &format!("SELECT {} FROM playlists p LEFT JOIN playlist_entries pe
+            &format!("SELECT {}, pe.entry_id FROM playlists p LEFT JOIN playlist_entries pe
                         ON p.id = pe.playlist_id
-                        WHERE pe.entry_id = $1
+                        WHERE pe.entry_id = ANY($1)
                         ORDER BY p.published_at DESC",
-                     Playlist::props_str("p."))).unwrap();
-        let rows = stmt.query(&[&entry_id]).unwrap();
-        Playlist::rows_to_items(rows)
+                     Playlist::props_str("p.")))?;
+        let rows = stmt.query(&[&entry_ids])?;
+        let mut items: BTreeMap<Uuid, Vec<Playlist>> = BTreeMap::new();
+        for id in entry_ids.iter() {
+            items.insert(*id, vec![]);
+        }
+        for row in rows.iter() {
+            let id: Uuid = row.get(PROPS.len());
+            if let Some(playlists) = items.get_mut(&id) {
+                playlists.push(Self::row_to_item(row))
+            }
+        }
+        Ok(items)
     }
 }
 
 impl Playlist {
-    pub fn find_actives() -> Vec<Playlist> {
-        let conn = conn().unwrap();
+    pub fn find_actives(conn: &Connection) -> Vec<Playlist> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM playlists WHERE velocity > 0 ORDER BY updated_at ASC",
                      Playlist::props_str(""))).unwrap();
@@ -269,8 +258,7 @@ impl Playlist {
         Playlist::rows_to_items(rows)
     }
 
-    pub fn find_by_tracks(ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Playlist>>, Error> {
-        let conn = conn().unwrap();
+    pub fn find_by_tracks(conn: &Connection, ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Playlist>>, Error> {
         let sql = format!("SELECT {}, playlist_tracks.track_id FROM playlists
                       LEFT OUTER JOIN playlist_tracks ON playlist_tracks.playlist_id = playlists.id
                       WHERE playlist_tracks.track_id = ANY($1) ORDER BY playlist_tracks.updated_at DESC LIMIT {}",
@@ -291,41 +279,49 @@ impl Playlist {
     }
 
 
-    pub fn from_yt_playlist(playlist: &youtube::Playlist, items: &Vec<youtube::PlaylistItem>) -> Playlist {
-        Playlist::find_or_create(Provider::YouTube, (*playlist).id.to_string())
+    pub fn from_yt_playlist(conn: &Connection, playlist: &youtube::Playlist, items: &Vec<youtube::PlaylistItem>) -> Playlist {
+        Playlist::find_or_create(conn, Provider::YouTube, (*playlist).id.to_string())
             .unwrap()
-            .update_with_yt_playlist(playlist, items)
+            .update_with_yt_playlist(conn, playlist, items)
             .clone()
     }
 
-    pub fn from_sp_playlist(playlist: &spotify::Playlist) -> Playlist {
-        Playlist::find_or_create(Provider::Spotify, (*playlist).id.to_string())
+    pub fn from_sp_playlist(conn: &Connection, playlist: &spotify::Playlist) -> Playlist {
+        Playlist::find_or_create(conn, Provider::Spotify, (*playlist).id.to_string())
             .unwrap()
-            .update_with_sp_playlist(playlist)
+            .update_with_sp_playlist(conn, playlist)
             .clone()
     }
 
-    pub fn from_sc_playlist(playlist: &soundcloud::Playlist) -> Playlist {
-        Playlist::find_or_create(Provider::SoundCloud, (*playlist).id.to_string())
+    pub fn from_sc_playlist(conn: &Connection, playlist: &soundcloud::Playlist) -> Playlist {
+        Playlist::find_or_create(conn, Provider::SoundCloud, (*playlist).id.to_string())
             .unwrap()
-            .update_with_sc_playlist(playlist)
+            .update_with_sc_playlist(conn, playlist)
             .clone()
     }
 
-    pub fn from_am_playlist(playlist: &apple_music::Playlist) -> Playlist {
-        Playlist::find_or_create(Provider::AppleMusic, (*playlist).id.to_string())
+    pub fn from_am_playlist(conn: &Connection, playlist: &apple_music::Playlist) -> Playlist {
+        Playlist::find_or_create(conn, Provider::AppleMusic, (*playlist).id.to_string())
             .unwrap()
             .update_with_am_playlist(playlist)
             .clone()
     }
 
-    fn add_tracks(&mut self, tracks: Vec<Track>) -> Vec<PlaylistTrack> {
+    pub fn from_dz_playlist(conn: &Connection, playlist: &deezer::Playlist) -> Playlist {
+        Playlist::find_or_create(conn, Provider::Deezer, playlist.id.to_string())
+            .unwrap()
+            .update_with_dz_playlist(conn, playlist)
+            .clone()
+    }
+
+    pub fn add_tracks(&mut self, conn: &Connection, tracks: Vec<Track>) -> Vec<PlaylistTrack> {
         let new_tracks = tracks.iter().map(|t| {
             let mut t = t.clone();
-            if let Ok(new_track) = Track::find_or_create(t.provider,
+            if let Ok(new_track) = Track::find_or_create(conn,
+                                                         t.provider,
                                                          t.identifier.to_string()) {
                 t.id      = new_track.id;
-                t.save().and_then(|_| PlaylistTrack::upsert(&self, &t))
+                t.save(conn).and_then(|_| PlaylistTrack::upsert(conn, &self, &t))
             } else {
                 Err(Error::Unexpected)
             }
@@ -334,7 +330,7 @@ impl Playlist {
         new_tracks
     }
 
-    pub fn update_with_yt_playlist(&mut self, playlist: &youtube::Playlist, items: &Vec<youtube::PlaylistItem>) -> &mut Playlist {
+    pub fn update_with_yt_playlist(&mut self, conn: &Connection, playlist: &youtube::Playlist, items: &Vec<youtube::PlaylistItem>) -> &mut Playlist {
         self.provider      = Provider::YouTube;
         self.identifier    = playlist.id.to_string();
         self.owner_id      = Some(playlist.snippet.channelId.to_string());
@@ -350,13 +346,13 @@ impl Playlist {
             Err(_)           => (),
         }
         let tracks = items.iter()
-            .map(|ref i| Track::from_yt_playlist_item(i))
+            .map(|ref i| Track::from_yt_playlist_item(conn, i))
             .collect::<Vec<_>>();
-        self.add_tracks(tracks);
+        self.add_tracks(conn, tracks);
         self
     }
 
-    pub fn update_with_sp_playlist(&mut self, playlist: &spotify::Playlist) -> &mut Playlist {
+    pub fn update_with_sp_playlist(&mut self, conn: &Connection, playlist: &spotify::Playlist) -> &mut Playlist {
         self.provider       = Provider::Spotify;
         self.identifier     = playlist.id.to_string();
         self.owner_id       = Some(playlist.owner.id.to_string());
@@ -380,15 +376,15 @@ impl Playlist {
             .collect::<Vec<_>>();
         let sp_tracks = spotify::fetch_tracks(track_ids).unwrap_or(vec![]);
         let tracks = sp_tracks.iter()
-            .map(|ref t| Track::from_sp_track(t))
+            .map(|ref t| Track::from_sp_track(conn, t))
             .filter(|ref r| r.is_ok())
             .map(|r| r.unwrap())
             .collect::<Vec<_>>();
-        self.add_tracks(tracks);
+        self.add_tracks(conn, tracks);
         self
     }
 
-    pub fn update_with_sc_playlist(&mut self, playlist: &soundcloud::Playlist) -> &mut Playlist {
+    pub fn update_with_sc_playlist(&mut self, conn: &Connection, playlist: &soundcloud::Playlist) -> &mut Playlist {
         self.provider      = Provider::SoundCloud;
         self.identifier    = playlist.id.to_string();
         self.owner_id      = Some(playlist.user.id.to_string());
@@ -404,9 +400,9 @@ impl Playlist {
             Err(_)           => (),
         };
         let tracks = playlist.tracks.iter()
-            .map(|ref t| Track::from_sc_track(t))
+            .map(|ref t| Track::from_sc_track(conn, t))
             .collect::<Vec<_>>();
-        self.add_tracks(tracks);
+        self.add_tracks(conn, tracks);
         self
     }
 
@@ -427,13 +423,36 @@ impl Playlist {
         self
     }
 
+    pub fn update_with_dz_playlist(&mut self, conn: &Connection, playlist: &deezer::Playlist) -> &mut Playlist {
+        self.provider      = Provider::Deezer;
+        self.identifier    = playlist.id.to_string();
+        self.owner_id      = Some(playlist.creator.id.to_string());
+        self.owner_name    = Some(playlist.creator.name.to_string());
+        self.url           = playlist.link.to_string();
+        self.title         = playlist.title.to_string();
+        self.description   = playlist.description.clone();
+        self.thumbnail_url = playlist.picture_medium.clone();
+        self.artwork_url   = playlist.picture_xl.clone();
+        self.state         = State::Alive;
+        if let Some(published_at) = playlist.creation_date.clone()
+            .and_then(|d| NaiveDateTime::parse_from_str(&d, "%Y-%m-%d %H:%M:%S").ok()) {
+            self.published_at = published_at;
+        }
+        let tracks = playlist.tracks.clone()
+            .map(|t| t.data).unwrap_or(vec![]).iter()
+            .filter(|t| t.readable.unwrap_or(true))
+            .map(|t| Track::from_dz_track(conn, t))
+            .collect::<Vec<_>>();
+        self.add_tracks(conn, tracks);
+        self
+    }
+
     pub fn disable(&mut self) -> &mut Playlist {
         self.state = State::Dead;
         self
     }
 
-    pub fn delete(&self) -> Result<(), Error> {
-        let conn = conn()?;
+    pub fn delete(&self, conn: &Connection) -> Result<(), Error> {
         let stmt = conn.prepare("DELETE FROM playlists WHERE id=$1")?;
         let result = stmt.query(&[&self.id]);
         match result {
@@ -442,57 +461,18 @@ impl Playlist {
         }
     }
 
-    pub fn fetch_tracks(&mut self) -> Result<Vec<PlaylistTrack>, Error> {
-        match self.provider {
-            Provider::YouTube    => Ok(vec![]),
-            Provider::SoundCloud => Ok(vec![]),
-            Provider::AppleMusic => self.fetch_apple_music_tracks(),
-            Provider::Spotify    => self.fetch_spotify_tracks(),
-            _                    => Ok(vec![]),
-        }
-    }
-
-    pub fn fetch_apple_music_tracks(&mut self) -> Result<Vec<PlaylistTrack>, Error> {
-        let country = apple_music::country(&self.url);
-        let playlist = apple_music::fetch_playlist(&country, &self.identifier)?;
-        let songs = playlist.get_songs();
-        let song_ids = songs.iter().map(|song| song.id.clone()).collect::<Vec<String>>();
-        let songs = apple_music::fetch_songs(&country, song_ids).unwrap_or(vec![]);
-        Ok(self.add_tracks(songs.iter().map(|song| Track::from_am_song(song)).collect()))
-    }
-
-    pub fn fetch_spotify_tracks(&mut self) -> Result<Vec<PlaylistTrack>, Error> {
-        let mut items = vec![];
-        let owner_id = self.clone().owner_id.ok_or(Error::Unexpected)?;
-
-        let mut page = spotify::fetch_playlist_tracks(&owner_id, &self.identifier)?;
-        items.append(&mut self.add_tracks(
-            page.items.iter()
-                .filter(|pt| pt.track.is_some())
-                .map(|pt| Track::from_sp_track(&pt.track.clone().unwrap()))
-                .filter(|ref r| r.is_ok())
-                .map(|r| r.unwrap())
-                .collect())
-        );
-        while page.next.is_some() {
-            page = page.fetch_next()?;
-            items.append(&mut self.add_tracks(
-                page.items.iter()
-                    .filter(|pt| pt.track.is_some())
-                    .map(|pt| Track::from_sp_track(&pt.track.clone().unwrap()))
-                    .filter(|ref r| r.is_ok())
-                    .map(|r| r.unwrap())
-                    .collect())
-            );
+    pub fn fetch_tracks(&mut self, conn: &Connection) -> Result<Vec<PlaylistTrack>, Error> {
+        match adapter::find(&self.provider) {
+            Some(adapter) => adapter.fetch_playlist_tracks(conn, self),
+            None          => Ok(vec![]),
         }
-        Ok(items)
     }
 }
 
 #[cfg(test)]
 mod test {
     use model::enclosure::Enclosure;
-    use model::Model;
+    use model::{Model, conn};
     use super::Playlist;
     use Provider;
     #[test]
@@ -504,25 +484,28 @@ mod test {
     }
     #[test]
     fn test_find_of_create() {
+        let conn       = conn().unwrap();
         let identifier = "PLy8LZ8FM-o0ViuGAF68RAaXkQ8V-3dbTX".to_string();
-        let result     = Playlist::find_or_create(Provider::YouTube, identifier);
+        let result     = Playlist::find_or_create(&*conn, Provider::YouTube, identifier);
         assert!(result.is_ok());
     }
     #[test]
     fn test_delete() {
+        let conn       = conn().unwrap();
         let identifier = "test_delete".to_string();
-        let playlist   = Playlist::find_or_create(Provider::YouTube, identifier).unwrap();
-        let result     = playlist.delete();
+        let playlist   = Playlist::find_or_create(&*conn, Provider::YouTube, identifier).unwrap();
+        let result     = playlist.delete(&*conn);
         assert!(result.is_ok());
     }
     #[test]
     fn test_save() {
+        let conn = conn().unwrap();
         let id = "test_save";
-        let mut playlist = Playlist::find_or_create(Provider::YouTube, id.to_string()).unwrap();
+        let mut playlist = Playlist::find_or_create(&*conn, Provider::YouTube, id.to_string()).unwrap();
         playlist.title = "title".to_string();
-        let result = playlist.save();
+        let result = playlist.save(&*conn);
         assert!(result.is_ok());
-        let playlist = Playlist::find_or_create(Provider::YouTube, id.to_string()).unwrap();
+        let playlist = Playlist::find_or_create(&*conn, Provider::YouTube, id.to_string()).unwrap();
         assert_eq!(&playlist.title, "title");
     }
 }
@@ -1,9 +1,9 @@
 use std::collections::BTreeMap;
 use uuid::Uuid;
 use chrono::{NaiveDateTime, Utc};
-use postgres;
+use super::{Connection, Row};
 use error::Error;
-use super::{conn, Model};
+use super::Model;
 use model::track::{Track, PROPS as TRACK_PROPS};
 use model::playlist::Playlist;
 
@@ -28,7 +28,7 @@ impl PlaylistTrack {
             .map(|&p| format!("{}{}", prefix, p))
             .collect::<Vec<String>>().join(",")
     }
-    fn row_to_item(row: postgres::rows::Row) -> Self {
+    fn row_to_item(row: &Row) -> Self {
         let offset = TRACK_PROPS.len();
         PlaylistTrack {
             playlist_id: row.get(offset),
@@ -38,8 +38,7 @@ impl PlaylistTrack {
             track:       Track::row_to_item(row),
         }
     }
-    pub fn find_by_playlist_ids(playlist_ids: Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<PlaylistTrack>>, Error> {
-        let conn = conn().unwrap();
+    pub fn find_by_playlist_ids(conn: &Connection, playlist_ids: Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<PlaylistTrack>>, Error> {
         let sql = format!("SELECT {}, {} FROM tracks
                       LEFT OUTER JOIN playlist_tracks
                       ON playlist_tracks.track_id = tracks.id
@@ -69,16 +68,18 @@ impl PlaylistTrack {
         };
         Ok(items)
     }
-    pub fn upsert(playlist: &Playlist, track: &Track) -> Result<PlaylistTrack, Error> {
-        let conn = conn()?;
+    pub fn upsert(conn: &Connection, playlist: &Playlist, track: &Track) -> Result<PlaylistTrack, Error> {
         let stmt = conn.prepare("INSERT INTO playlist_tracks
                       (track_id, playlist_id, created_at, updated_at)
                       VALUES ($1, $2, $3, $4)
                       ON CONFLICT (track_id, playlist_id)
-                      DO UPDATE SET updated_at=$4
-                      RETURNING playlist_tracks.created_at, playlist_tracks.updated_at")?;
+                      DO UPDATE SET updated_at=$4")?;
         let now = Utc::now().naive_utc();
-        let rows = stmt.query(&[&track.id, &playlist.id, &now, &now])?;
+        stmt.execute(&[&track.id, &playlist.id, &now, &now])?;
+        // SQLite doesn't return the timestamps of an upsert, so they are selected
+        let stmt = conn.prepare("SELECT created_at, updated_at FROM playlist_tracks
+                                 WHERE track_id = $1 AND playlist_id = $2")?;
+        let rows = stmt.query(&[&track.id, &playlist.id])?;
         let row = rows.iter().next().ok_or(Error::Unexpected)?;
         Ok(PlaylistTrack {
             playlist_id: playlist.id,
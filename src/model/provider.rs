@@ -6,6 +6,9 @@ pub enum Provider {
     YouTube,
     SoundCloud,
     Spotify,
+    Bandcamp,
+    Mixcloud,
+    Deezer,
     Custom,
     Raw
 }
@@ -17,6 +20,9 @@ impl PartialEq for Provider {
             Provider::YouTube    => match *p { Provider::YouTube    => true, _ => false },
             Provider::SoundCloud => match *p { Provider::SoundCloud => true, _ => false },
             Provider::Spotify    => match *p { Provider::Spotify    => true, _ => false },
+            Provider::Bandcamp   => match *p { Provider::Bandcamp   => true, _ => false },
+            Provider::Mixcloud   => match *p { Provider::Mixcloud   => true, _ => false },
+            Provider::Deezer     => match *p { Provider::Deezer     => true, _ => false },
             Provider::Custom     => match *p { Provider::Custom     => true, _ => false },
             Provider::Raw        => match *p { Provider::Raw        => true, _ => false },
         }
@@ -30,6 +36,9 @@ impl Provider {
             Provider::YouTube    => "YouTube",
             Provider::SoundCloud => "SoundCloud",
             Provider::Spotify    => "Spotify",
+            Provider::Bandcamp   => "Bandcamp",
+            Provider::Mixcloud   => "Mixcloud",
+            Provider::Deezer     => "Deezer",
             Provider::Custom     => "Custom",
             Provider::Raw        => "Raw",
         }.to_string()
@@ -44,6 +53,12 @@ impl Provider {
             "soundcloud" => Provider::SoundCloud,
             "Spotify"    => Provider::Spotify,
             "spotify"    => Provider::Spotify,
+            "Bandcamp"   => Provider::Bandcamp,
+            "bandcamp"   => Provider::Bandcamp,
+            "Mixcloud"   => Provider::Mixcloud,
+            "mixcloud"   => Provider::Mixcloud,
+            "Deezer"     => Provider::Deezer,
+            "deezer"     => Provider::Deezer,
             "Custom"     => Provider::Custom,
             _            => Provider::Raw,
         }
@@ -0,0 +1,221 @@
+use chrono::{DateTime, NaiveDateTime};
+use uuid::Uuid;
+use params;
+use error::Error;
+use super::{Model, Filter, FilterType, State, Provider, ToSql};
+
+/// The order of a list.
+///
+/// The items are sorted by a timestamp field and then by their ids,
+/// so that the field can be used as the cursor of the pages.
+/// The default is `created_at`, which doesn't change while the crawlers update the rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sort {
+    pub field: &'static str,
+    pub desc:  bool,
+}
+
+impl Default for Sort {
+    fn default() -> Sort {
+        Sort::new("created_at", true)
+    }
+}
+
+impl Sort {
+    pub fn new(field: &'static str, desc: bool) -> Sort {
+        Sort {
+            field: field,
+            desc:  desc,
+        }
+    }
+    /// `-published_at` sorts by `published_at` in descending order, and `published_at` in ascending order.
+    pub fn parse(value: &str, fields: &[&'static str]) -> Result<Sort, Error> {
+        let (name, desc) = if value.starts_with('-') {
+            (&value[1..], true)
+        } else {
+            (value, false)
+        };
+        fields.iter()
+            .find(|f| **f == name)
+            .map(|&f| Sort::new(f, desc))
+            .ok_or(Error::Unprocessable)
+    }
+    /// The value of `?sort=` for this order.
+    pub fn to_param(&self) -> String {
+        if self.desc {
+            format!("-{}", self.field)
+        } else {
+            self.field.to_string()
+        }
+    }
+    pub fn to_query(&self) -> String {
+        let direction = if self.desc { "DESC" } else { "ASC" };
+        format!("ORDER BY {0} {1}, id {1}", self.field, direction)
+    }
+    /// The condition of the items after the cursor, that has the values at `$num` and `$num + 1`.
+    pub fn to_cursor_condition(&self, num: usize) -> String {
+        let comparison = if self.desc { "<" } else { ">" };
+        format!("({}, id) {} (${}, ${})", self.field, comparison, num, num + 1)
+    }
+}
+
+/// A value of a filter that is parsed from a query parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Text(String),
+    Float(f64),
+    Uuid(Uuid),
+    Timestamp(NaiveDateTime),
+}
+
+impl Param {
+    /// This function parses the value of a field, and rejects the values that the field can't have.
+    pub fn parse(field: &str, value: &str) -> Result<Param, Error> {
+        match field {
+            "state" => State::parse(value)
+                .map(|state| Param::Text(state.to_string()))
+                .ok_or(Error::Unprocessable),
+            "provider" => {
+                let provider = Provider::new(value.to_string());
+                if provider == Provider::Raw && value.to_lowercase() != "raw" {
+                    return Err(Error::Unprocessable);
+                }
+                Ok(Param::Text(provider.to_string()))
+            },
+            "feed_id" | "canonical_artist_id" => Uuid::parse_str(value)
+                .map(Param::Uuid)
+                .map_err(|_| Error::Unprocessable),
+            _ => Ok(Param::Text(value.to_string())),
+        }
+    }
+    /// Timestamps are given as unix time in seconds, or in RFC 3339.
+    pub fn parse_timestamp(value: &str) -> Result<Param, Error> {
+        if let Ok(secs) = value.parse::<i64>() {
+            return Ok(Param::Timestamp(NaiveDateTime::from_timestamp(secs, 0)));
+        }
+        DateTime::parse_from_rfc3339(value)
+            .map(|t| Param::Timestamp(t.naive_utc()))
+            .map_err(|_| Error::Unprocessable)
+    }
+    pub fn as_sql(&self) -> &ToSql {
+        match *self {
+            Param::Text(ref v)      => v,
+            Param::Float(ref v)     => v,
+            Param::Uuid(ref v)      => v,
+            Param::Timestamp(ref v) => v,
+        }
+    }
+}
+
+/// The conditions and the order of a list.
+/// The conditions are ANDed.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub conditions: Vec<(FilterType, &'static str, Param)>,
+    pub sort:       Sort,
+}
+
+impl Query {
+    pub fn new() -> Query {
+        Query::default()
+    }
+
+    pub fn filter(mut self, filter_type: FilterType, field: &'static str, value: Param) -> Query {
+        self.conditions.push((filter_type, field, value));
+        self
+    }
+
+    pub fn sort(mut self, sort: Sort) -> Query {
+        self.sort = sort;
+        self
+    }
+
+    pub fn filters(&self) -> Vec<Filter> {
+        self.conditions.iter().map(|&(filter_type, field, ref value)| Filter {
+            filter_type: filter_type,
+            field:       field,
+            value:       value.as_sql(),
+        }).collect()
+    }
+
+    /// This function builds a query from the query parameters of a request,
+    /// e.g. `?provider=Spotify&state=alive&published_after=1500000000&sort=-published_at`.
+    ///
+    /// - `<field>=<value>` for the fields of `T::filter_fields()`
+    /// - `query=<text>` for the items whose `T::search_prop()` contains the text
+    /// - `type=active` for the items whose velocity is positive
+    /// - `published_after` and `published_before` for `T::published_field()`
+    /// - `sort=<field>` or `sort=-<field>` for the fields of `T::sort_fields()`
+    ///
+    /// The other parameters, e.g. `page`, are ignored. Fields that the model doesn't allow are rejected.
+    pub fn from_params<'a, T: Model<'a>>(map: &params::Map) -> Result<Query, Error> {
+        let mut query = Query::new();
+        for &field in T::filter_fields().iter() {
+            if let Some(value) = string_param(map, field) {
+                query = query.filter(FilterType::Equals, field, Param::parse(field, &value)?);
+            }
+        }
+        if let Some(value) = string_param(map, "query") {
+            query = query.filter(FilterType::Contains, T::search_prop(), Param::Text(format!("%{}%", value)));
+        }
+        if let Some(value) = string_param(map, "type") {
+            if value == "active" {
+                if !T::props_str("").split(',').any(|p| p == "velocity") {
+                    return Err(Error::Unprocessable);
+                }
+                query = query.filter(FilterType::GreaterThan, "velocity", Param::Float(0.0));
+            }
+        }
+        let after  = string_param(map, "published_after");
+        let before = string_param(map, "published_before");
+        if after.is_some() || before.is_some() {
+            let field = T::published_field().ok_or(Error::Unprocessable)?;
+            if let Some(value) = after {
+                query = query.filter(FilterType::GreaterThanOrEquals, field, Param::parse_timestamp(&value)?);
+            }
+            if let Some(value) = before {
+                query = query.filter(FilterType::LessThan, field, Param::parse_timestamp(&value)?);
+            }
+        }
+        if let Some(value) = string_param(map, "sort") {
+            query = query.sort(Sort::parse(&value, T::sort_fields())?);
+        }
+        Ok(query)
+    }
+}
+
+fn string_param(map: &params::Map, key: &str) -> Option<String> {
+    match map.find(&[key]) {
+        Some(&params::Value::String(ref value)) if !value.is_empty() => Some(value.to_string()),
+        _                                                            => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sort() {
+        let fields = ["updated_at", "published_at"];
+        assert_eq!(Sort::parse("-published_at", &fields).unwrap(), Sort::new("published_at", true));
+        assert_eq!(Sort::parse("updated_at", &fields).unwrap(), Sort::new("updated_at", false));
+        assert!(Sort::parse("title", &fields).is_err());
+        assert_eq!(Sort::new("published_at", true).to_query(), "ORDER BY published_at DESC, id DESC");
+        assert_eq!(Sort::new("published_at", true).to_param(), "-published_at");
+        assert_eq!(Sort::default().to_param(), "-created_at");
+        assert_eq!(Sort::new("published_at", false).to_cursor_condition(3), "(published_at, id) > ($3, $4)");
+    }
+
+    #[test]
+    fn test_param() {
+        assert_eq!(Param::parse("state", "alive").unwrap(), Param::Text("alive".to_string()));
+        assert!(Param::parse("state", "zombie").is_err());
+        assert_eq!(Param::parse("provider", "spotify").unwrap(), Param::Text("Spotify".to_string()));
+        assert!(Param::parse("provider", "napster").is_err());
+        assert!(Param::parse("feed_id", "not-a-uuid").is_err());
+        assert_eq!(Param::parse_timestamp("0").unwrap(), Param::Timestamp(NaiveDateTime::from_timestamp(0, 0)));
+        assert!(Param::parse_timestamp("2018-06-01T00:00:00Z").is_ok());
+        assert!(Param::parse_timestamp("yesterday").is_err());
+    }
+}
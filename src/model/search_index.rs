@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use uuid::Uuid;
+use error::Error;
+use super::{Connection, ToSql};
+
+/// The number of the tokens that are inserted with a statement,
+/// so that the parameters don't exceed the limit of SQLite.
+static BATCH_SIZE: usize = 200;
+
+/// An item that has all the tokens of a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub item_type: String,
+    pub item_id:   Uuid,
+    pub score:     f64,
+}
+
+/// An inverted index from the tokens to the items that have them.
+/// Each row has the weight of a token in an item, and the score of an item is the sum of the weights.
+pub struct SearchIndex;
+
+impl SearchIndex {
+    /// This function replaces the tokens of an item with `tokens`, the weights of the tokens.
+    /// The old tokens are deleted in the same transaction, so a failed insert keeps them.
+    pub fn update_in(conn: &Connection, item_type: &str, item_id: Uuid, tokens: &BTreeMap<String, f64>) -> Result<(), Error> {
+        let item_type = item_type.to_string();
+        let trans     = conn.transaction()?;
+        trans.execute("DELETE FROM search_tokens WHERE item_type = $1 AND item_id = $2",
+                      &[&item_type, &item_id])?;
+        let tokens = tokens.iter().collect::<Vec<_>>();
+        for chunk in tokens.chunks(BATCH_SIZE) {
+            let mut values = vec![];
+            let mut params: Vec<&ToSql> = vec![&item_type, &item_id];
+            for &(token, weight) in chunk {
+                values.push(format!("($1, $2, ${}, ${})", params.len() + 1, params.len() + 2));
+                params.push(token);
+                params.push(weight);
+            }
+            trans.execute(&format!("INSERT INTO search_tokens (item_type, item_id, token, weight)
+                                    VALUES {}", values.join(", ")), &params)?;
+        }
+        trans.commit()
+    }
+
+    /// This function finds the items of `item_types` that have all the `tokens`, in the order of their scores.
+    /// The tokens must be distinct.
+    pub fn search(conn: &Connection, tokens: &Vec<String>, item_types: &Vec<String>, limit: i64, offset: i64) -> Result<Vec<SearchHit>, Error> {
+        if tokens.is_empty() || item_types.is_empty() {
+            return Ok(vec![]);
+        }
+        let count = tokens.len() as i64;
+        let stmt  = conn.prepare("SELECT item_type, item_id, SUM(weight) AS score FROM search_tokens
+                                  WHERE token = ANY($1) AND item_type = ANY($2)
+                                  GROUP BY item_type, item_id
+                                  HAVING COUNT(*) = $3
+                                  ORDER BY score DESC, item_id
+                                  LIMIT $4 OFFSET $5")?;
+        let rows  = stmt.query(&[tokens, item_types, &count, &limit, &offset])?;
+        Ok(rows.iter().map(|row| SearchHit {
+            item_type: row.get(0),
+            item_id:   row.get(1),
+            score:     row.get(2),
+        }).collect())
+    }
+}
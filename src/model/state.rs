@@ -1,34 +1,69 @@
 use std::fmt;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// The number of the confirmed not found responses in a row that make an enclosure dead.
+/// The other failures, e.g. timeouts, never make it dead.
+pub const DEAD_AFTER_NOT_FOUND: i32 = 3;
+
+/// The lifecycle of an enclosure. The states except `Alive` and `Pending`
+/// are set from the responses of the providers, see `state()` of each provider.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum State {
+    /// The enclosure is created, but not fetched from the provider yet.
+    #[serde(rename = "pending")]
+    Pending,
     #[serde(rename = "alive")]
     Alive,
+    /// The enclosure can't be played in some countries.
+    #[serde(rename = "region_restricted")]
+    RegionRestricted,
+    /// The owner made the enclosure private.
+    #[serde(rename = "private")]
+    Private,
+    /// The provider removed the enclosure, e.g. for a copyright claim.
+    #[serde(rename = "removed")]
+    Removed,
+    /// The enclosure exists, but can't be played or embedded.
+    #[serde(rename = "unavailable")]
+    Unavailable,
+    /// The provider doesn't have the enclosure any more.
     #[serde(rename = "dead")]
     Dead,
 }
 
-impl PartialEq for State {
-    fn eq(&self, p: &State) -> bool {
-        match *self {
-            State::Alive => match *p { State::Alive => true, _ => false },
-            State::Dead  => match *p { State::Dead  => true, _ => false },
-        }
-    }
-}
-
 impl State {
     fn to_string(&self) -> String {
         match *self {
-            State::Alive => "alive",
-            State::Dead  => "dead",
+            State::Pending          => "pending",
+            State::Alive            => "alive",
+            State::RegionRestricted => "region_restricted",
+            State::Private          => "private",
+            State::Removed          => "removed",
+            State::Unavailable      => "unavailable",
+            State::Dead             => "dead",
         }.to_string()
     }
+    pub fn parse(str: &str) -> Option<State> {
+        match str {
+            "pending"           => Some(State::Pending),
+            "alive"             => Some(State::Alive),
+            "region_restricted" => Some(State::RegionRestricted),
+            "private"           => Some(State::Private),
+            "removed"           => Some(State::Removed),
+            "unavailable"       => Some(State::Unavailable),
+            "dead"              => Some(State::Dead),
+            _                   => None,
+        }
+    }
+    /// Unknown states are treated as unavailable, not dead,
+    /// so that they aren't dropped before they are fetched again.
     pub fn new(str: String) -> State {
-        match str.as_ref() {
-            "alive" => State::Alive,
-            "dead"  => State::Dead,
-            _       => State::Dead,
+        State::parse(&str).unwrap_or(State::Unavailable)
+    }
+    /// This function tells whether the provider doesn't have the enclosure any more.
+    pub fn is_gone(&self) -> bool {
+        match *self {
+            State::Removed | State::Dead => true,
+            _                            => false,
         }
     }
 }
@@ -38,3 +73,21 @@ impl fmt::Display for State {
         write!(f, "{}", self.to_string())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::State;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(State::parse("region_restricted"), Some(State::RegionRestricted));
+        assert_eq!(State::parse("Alive"), None);
+        assert_eq!(State::new("unknown".to_string()), State::Unavailable);
+        for state in [State::Pending, State::Alive, State::RegionRestricted, State::Private,
+                      State::Removed, State::Unavailable, State::Dead].iter() {
+            assert_eq!(State::parse(&state.to_string()), Some(*state));
+        }
+        assert!(State::Removed.is_gone());
+        assert!(!State::Private.is_gone());
+    }
+}
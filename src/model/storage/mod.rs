@@ -0,0 +1,492 @@
+use std::slice;
+use std::vec;
+use uuid::Uuid;
+use chrono::NaiveDateTime;
+use serde_json;
+use error::Error;
+
+#[cfg(feature = "postgres")]
+pub mod pg;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+static TIMESTAMP_FORMATS: [&'static str; 2] = ["%Y-%m-%d %H:%M:%S%.f",
+                                               "%Y-%m-%dT%H:%M:%S%.f"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Postgres,
+    Sqlite,
+}
+
+/// A connection to a database that the models run their queries with.
+///
+/// Queries are written in the sql of Postgres, with `$1` style parameters.
+/// The other backends translate what they don't support themselves,
+/// so the models don't need to know which database they run on.
+pub trait Connection {
+    fn backend(&self) -> Backend;
+    fn query(&self, sql: &str, params: &[&ToSql]) -> Result<Rows, Error>;
+    fn execute(&self, sql: &str, params: &[&ToSql]) -> Result<u64, Error>;
+    fn batch_execute(&self, sql: &str) -> Result<(), Error>;
+    fn prepare<'a>(&'a self, sql: &str) -> Result<Statement<'a>, Error>;
+    fn transaction<'a>(&'a self) -> Result<Transaction<'a>, Error>;
+    fn has_broken(&self) -> bool {
+        false
+    }
+}
+
+/// This function opens a connection to the database of the url.
+/// `postgres://` urls need the `postgres` feature and `sqlite:` urls need the `sqlite` feature.
+pub fn connect(url: &str) -> Result<Box<Connection + Send>, Error> {
+    if let Some(conn) = connect_postgres(url) {
+        return conn;
+    }
+    if let Some(conn) = connect_sqlite(url) {
+        return conn;
+    }
+    Err(Error::Database(format!("unsupported database url: {}", url)))
+}
+
+/// This function opens an in-memory database that only the connection can see.
+/// It needs the `sqlite` feature.
+#[cfg(feature = "sqlite")]
+pub fn connect_memory() -> Result<Box<Connection>, Error> {
+    Ok(Box::new(sqlite::SqliteConnection::memory()?))
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn connect_memory() -> Result<Box<Connection>, Error> {
+    Err(Error::Database("the in-memory database needs the sqlite feature".to_string()))
+}
+
+#[cfg(feature = "postgres")]
+fn connect_postgres(url: &str) -> Option<Result<Box<Connection + Send>, Error>> {
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        Some(pg::PgConnection::connect(url).map(|c| Box::new(c) as Box<Connection + Send>))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(feature = "postgres"))]
+fn connect_postgres(_url: &str) -> Option<Result<Box<Connection + Send>, Error>> {
+    None
+}
+
+#[cfg(feature = "sqlite")]
+fn connect_sqlite(url: &str) -> Option<Result<Box<Connection + Send>, Error>> {
+    if url.starts_with("sqlite:") {
+        Some(sqlite::SqliteConnection::connect(url).map(|c| Box::new(c) as Box<Connection + Send>))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn connect_sqlite(_url: &str) -> Option<Result<Box<Connection + Send>, Error>> {
+    None
+}
+
+pub struct Statement<'a> {
+    conn: &'a Connection,
+    sql:  String,
+}
+
+impl<'a> Statement<'a> {
+    pub fn new(conn: &'a Connection, sql: &str) -> Statement<'a> {
+        Statement { conn: conn, sql: sql.to_string() }
+    }
+    pub fn query(&self, params: &[&ToSql]) -> Result<Rows, Error> {
+        self.conn.query(&self.sql, params)
+    }
+    pub fn execute(&self, params: &[&ToSql]) -> Result<u64, Error> {
+        self.conn.execute(&self.sql, params)
+    }
+}
+
+/// A transaction that is rolled back when it is dropped without `commit`.
+/// A transaction in a transaction is a savepoint.
+pub struct Transaction<'a> {
+    conn:     &'a Connection,
+    depth:    u32,
+    finished: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn new(conn: &'a Connection, depth: u32) -> Result<Transaction<'a>, Error> {
+        if depth == 0 {
+            conn.batch_execute("BEGIN")?;
+        } else {
+            conn.batch_execute(&format!("SAVEPOINT sp{}", depth))?;
+        }
+        Ok(Transaction { conn: conn, depth: depth, finished: false })
+    }
+
+    pub fn commit(mut self) -> Result<(), Error> {
+        self.finished = true;
+        if self.depth == 0 {
+            self.conn.batch_execute("COMMIT")
+        } else {
+            self.conn.batch_execute(&format!("RELEASE SAVEPOINT sp{}", self.depth))
+        }
+    }
+
+    fn rollback(&self) -> Result<(), Error> {
+        if self.depth == 0 {
+            self.conn.batch_execute("ROLLBACK")
+        } else {
+            self.conn.batch_execute(&format!("ROLLBACK TO SAVEPOINT sp{0}; RELEASE SAVEPOINT sp{0}",
+                                             self.depth))
+        }
+    }
+}
+
+impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.rollback();
+        }
+    }
+}
+
+impl<'a> Connection for Transaction<'a> {
+    fn backend(&self) -> Backend {
+        self.conn.backend()
+    }
+    fn query(&self, sql: &str, params: &[&ToSql]) -> Result<Rows, Error> {
+        self.conn.query(sql, params)
+    }
+    fn execute(&self, sql: &str, params: &[&ToSql]) -> Result<u64, Error> {
+        self.conn.execute(sql, params)
+    }
+    fn batch_execute(&self, sql: &str) -> Result<(), Error> {
+        self.conn.batch_execute(sql)
+    }
+    fn prepare<'b>(&'b self, sql: &str) -> Result<Statement<'b>, Error> {
+        Ok(Statement::new(self, sql))
+    }
+    fn transaction<'b>(&'b self) -> Result<Transaction<'b>, Error> {
+        Transaction::new(self, self.depth + 1)
+    }
+}
+
+/// A value of a parameter or a column.
+/// Null values keep their types, because Postgres checks the types of parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Bool(Option<bool>),
+    Int4(Option<i32>),
+    Int8(Option<i64>),
+    Float8(Option<f64>),
+    Text(Option<String>),
+    Uuid(Option<Uuid>),
+    Timestamp(Option<NaiveDateTime>),
+    Json(Option<serde_json::Value>),
+    UuidArray(Option<Vec<Uuid>>),
+    TextArray(Option<Vec<String>>),
+}
+
+impl SqlValue {
+    pub fn is_null(&self) -> bool {
+        match *self {
+            SqlValue::Bool(None)      |
+            SqlValue::Int4(None)      |
+            SqlValue::Int8(None)      |
+            SqlValue::Float8(None)    |
+            SqlValue::Text(None)      |
+            SqlValue::Uuid(None)      |
+            SqlValue::Timestamp(None) |
+            SqlValue::Json(None)      |
+            SqlValue::UuidArray(None) |
+            SqlValue::TextArray(None) => true,
+            _                         => false,
+        }
+    }
+}
+
+pub trait ToSql {
+    fn to_sql(&self) -> SqlValue;
+}
+
+/// A type whose null is a typed `SqlValue`, so that `Option` of it is `ToSql`.
+pub trait Nullable {
+    fn null() -> SqlValue;
+}
+
+macro_rules! to_sql {
+    ($t:ty, $variant:ident) => {
+        impl ToSql for $t {
+            fn to_sql(&self) -> SqlValue {
+                SqlValue::$variant(Some(self.clone()))
+            }
+        }
+        impl Nullable for $t {
+            fn null() -> SqlValue {
+                SqlValue::$variant(None)
+            }
+        }
+    }
+}
+
+to_sql!(bool, Bool);
+to_sql!(i32, Int4);
+to_sql!(i64, Int8);
+to_sql!(f64, Float8);
+to_sql!(String, Text);
+to_sql!(Uuid, Uuid);
+to_sql!(NaiveDateTime, Timestamp);
+to_sql!(serde_json::Value, Json);
+to_sql!(Vec<Uuid>, UuidArray);
+to_sql!(Vec<String>, TextArray);
+
+impl ToSql for str {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Text(Some(self.to_string()))
+    }
+}
+
+impl ToSql for [Uuid] {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::UuidArray(Some(self.to_vec()))
+    }
+}
+
+impl<'a, T: ToSql + ?Sized> ToSql for &'a T {
+    fn to_sql(&self) -> SqlValue {
+        (**self).to_sql()
+    }
+}
+
+impl<T: ToSql + Nullable> ToSql for Option<T> {
+    fn to_sql(&self) -> SqlValue {
+        match *self {
+            Some(ref v) => v.to_sql(),
+            None        => T::null(),
+        }
+    }
+}
+
+/// A type that a column is converted to.
+/// Backends without the native types store them as text,
+/// so the conversions accept the text representations too.
+pub trait FromSql: Sized {
+    fn from_sql(value: &SqlValue) -> Result<Self, Error>;
+}
+
+impl FromSql for bool {
+    fn from_sql(value: &SqlValue) -> Result<bool, Error> {
+        match *value {
+            SqlValue::Bool(Some(v)) => Ok(v),
+            SqlValue::Int4(Some(v)) => Ok(v != 0),
+            SqlValue::Int8(Some(v)) => Ok(v != 0),
+            _                       => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for i32 {
+    fn from_sql(value: &SqlValue) -> Result<i32, Error> {
+        match *value {
+            SqlValue::Int4(Some(v)) => Ok(v),
+            SqlValue::Int8(Some(v)) => Ok(v as i32),
+            _                       => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for i64 {
+    fn from_sql(value: &SqlValue) -> Result<i64, Error> {
+        match *value {
+            SqlValue::Int4(Some(v)) => Ok(v as i64),
+            SqlValue::Int8(Some(v)) => Ok(v),
+            _                       => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for f64 {
+    fn from_sql(value: &SqlValue) -> Result<f64, Error> {
+        match *value {
+            SqlValue::Float8(Some(v)) => Ok(v),
+            SqlValue::Int4(Some(v))   => Ok(v as f64),
+            SqlValue::Int8(Some(v))   => Ok(v as f64),
+            _                         => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for String {
+    fn from_sql(value: &SqlValue) -> Result<String, Error> {
+        match *value {
+            SqlValue::Text(Some(ref v)) => Ok(v.to_string()),
+            SqlValue::Uuid(Some(ref v)) => Ok(v.to_string()),
+            _                           => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for Uuid {
+    fn from_sql(value: &SqlValue) -> Result<Uuid, Error> {
+        match *value {
+            SqlValue::Uuid(Some(v))     => Ok(v),
+            SqlValue::Text(Some(ref v)) => Uuid::parse_str(v).map_err(|_| Error::Unexpected),
+            _                           => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for NaiveDateTime {
+    fn from_sql(value: &SqlValue) -> Result<NaiveDateTime, Error> {
+        match *value {
+            SqlValue::Timestamp(Some(v)) => Ok(v),
+            SqlValue::Text(Some(ref v))  => TIMESTAMP_FORMATS.iter()
+                .filter_map(|f| NaiveDateTime::parse_from_str(v, f).ok())
+                .next()
+                .ok_or(Error::Unexpected),
+            _                            => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for serde_json::Value {
+    fn from_sql(value: &SqlValue) -> Result<serde_json::Value, Error> {
+        match *value {
+            SqlValue::Json(Some(ref v)) => Ok(v.clone()),
+            SqlValue::Text(Some(ref v)) => serde_json::from_str(v).map_err(|_| Error::Unexpected),
+            _                           => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for Vec<Uuid> {
+    fn from_sql(value: &SqlValue) -> Result<Vec<Uuid>, Error> {
+        match *value {
+            SqlValue::UuidArray(Some(ref v)) => Ok(v.clone()),
+            SqlValue::Text(Some(ref v))      => serde_json::from_str(v).map_err(|_| Error::Unexpected),
+            _                                => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl FromSql for Vec<String> {
+    fn from_sql(value: &SqlValue) -> Result<Vec<String>, Error> {
+        match *value {
+            SqlValue::TextArray(Some(ref v)) => Ok(v.clone()),
+            SqlValue::Text(Some(ref v))      => serde_json::from_str(v).map_err(|_| Error::Unexpected),
+            _                                => Err(Error::Unexpected),
+        }
+    }
+}
+
+impl<T: FromSql> FromSql for Option<T> {
+    fn from_sql(value: &SqlValue) -> Result<Option<T>, Error> {
+        if value.is_null() {
+            Ok(None)
+        } else {
+            T::from_sql(value).map(Some)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Row {
+    values: Vec<SqlValue>,
+}
+
+impl Row {
+    pub fn new(values: Vec<SqlValue>) -> Row {
+        Row { values: values }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// This function panics if the column doesn't exist or has another type,
+    /// in the same way as `postgres::rows::Row::get`.
+    pub fn get<T: FromSql>(&self, idx: usize) -> T {
+        match self.get_opt(idx) {
+            Ok(v)  => v,
+            Err(_) => panic!("error retrieving column {}: {:?}", idx, self.values.get(idx)),
+        }
+    }
+
+    pub fn get_opt<T: FromSql>(&self, idx: usize) -> Result<T, Error> {
+        self.values.get(idx).ok_or(Error::Unexpected).and_then(T::from_sql)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rows {
+    rows: Vec<Row>,
+}
+
+impl Rows {
+    pub fn new(rows: Vec<Row>) -> Rows {
+        Rows { rows: rows }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn iter(&self) -> slice::Iter<Row> {
+        self.rows.iter()
+    }
+}
+
+impl IntoIterator for Rows {
+    type Item     = Row;
+    type IntoIter = vec::IntoIter<Row>;
+    fn into_iter(self) -> vec::IntoIter<Row> {
+        self.rows.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+    use chrono::NaiveDate;
+    use error::Error;
+    use super::{connect, Row, SqlValue, ToSql};
+
+    #[test]
+    fn test_to_sql() {
+        let id = Uuid::new_v4();
+        assert_eq!((&id).to_sql(), SqlValue::Uuid(Some(id)));
+        assert_eq!(Some(3).to_sql(), SqlValue::Int4(Some(3)));
+        assert_eq!((None as Option<String>).to_sql(), SqlValue::Text(None));
+        assert_eq!("title".to_sql(), SqlValue::Text(Some("title".to_string())));
+    }
+
+    #[test]
+    fn test_get() {
+        let id  = Uuid::new_v4();
+        let row = Row::new(vec![SqlValue::Text(Some(id.to_string())),
+                                SqlValue::Text(Some("2018-06-01 10:20:30.5".to_string())),
+                                SqlValue::Int8(Some(240)),
+                                SqlValue::Text(None),
+                                SqlValue::Text(Some("[\"rock\"]".to_string()))]);
+        let uuid: Uuid = row.get(0);
+        assert_eq!(uuid, id);
+        assert_eq!(row.get::<::chrono::NaiveDateTime>(1),
+                   NaiveDate::from_ymd(2018, 6, 1).and_hms_milli(10, 20, 30, 500));
+        assert_eq!(row.get::<i32>(2), 240);
+        assert_eq!(row.get::<Option<String>>(3), None);
+        assert_eq!(row.get::<::serde_json::Value>(4), json!(["rock"]));
+        assert!(row.get_opt::<String>(2).is_err());
+    }
+
+    #[test]
+    fn test_connect_unsupported_url() {
+        match connect("mysql://localhost/pink_spider") {
+            Err(Error::Database(message)) => assert!(message.contains("mysql://localhost/pink_spider")),
+            _                             => assert!(false),
+        }
+    }
+}
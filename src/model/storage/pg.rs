@@ -0,0 +1,103 @@
+use postgres;
+use postgres::TlsMode;
+use postgres::rows::Row as PgRow;
+use postgres::types::ToSql as PgToSql;
+use chrono::{DateTime, Utc};
+use error::Error;
+use super::{Backend, Connection, Statement, Transaction, Row, Rows, SqlValue, ToSql};
+
+pub struct PgConnection {
+    conn: postgres::Connection,
+}
+
+impl PgConnection {
+    pub fn connect(url: &str) -> Result<PgConnection, Error> {
+        let conn = postgres::Connection::connect(url.trim(), TlsMode::None)?;
+        Ok(PgConnection { conn: conn })
+    }
+}
+
+fn to_pg(value: SqlValue) -> Box<PgToSql> {
+    match value {
+        SqlValue::Bool(v)      => Box::new(v),
+        SqlValue::Int4(v)      => Box::new(v),
+        SqlValue::Int8(v)      => Box::new(v),
+        SqlValue::Float8(v)    => Box::new(v),
+        SqlValue::Text(v)      => Box::new(v),
+        SqlValue::Uuid(v)      => Box::new(v),
+        SqlValue::Timestamp(v) => Box::new(v),
+        SqlValue::Json(v)      => Box::new(v),
+        SqlValue::UuidArray(v) => Box::new(v),
+        SqlValue::TextArray(v) => Box::new(v),
+    }
+}
+
+fn from_pg(row: &PgRow, idx: usize) -> Result<SqlValue, Error> {
+    let value = match row.columns()[idx].type_().name() {
+        "bool"                           => SqlValue::Bool(get(row, idx)?),
+        "int2"                           => SqlValue::Int4(get::<Option<i16>>(row, idx)?.map(|v| v as i32)),
+        "int4"                           => SqlValue::Int4(get(row, idx)?),
+        "int8"                           => SqlValue::Int8(get(row, idx)?),
+        "float4"                         => SqlValue::Float8(get::<Option<f32>>(row, idx)?.map(|v| v as f64)),
+        "float8"                         => SqlValue::Float8(get(row, idx)?),
+        "text" | "varchar" | "bpchar" | "name" => SqlValue::Text(get(row, idx)?),
+        "uuid"                           => SqlValue::Uuid(get(row, idx)?),
+        "timestamp"                      => SqlValue::Timestamp(get(row, idx)?),
+        "timestamptz"                    => SqlValue::Timestamp(get::<Option<DateTime<Utc>>>(row, idx)?
+                                                                .map(|v| v.naive_utc())),
+        "json" | "jsonb"                 => SqlValue::Json(get(row, idx)?),
+        "_uuid"                          => SqlValue::UuidArray(get(row, idx)?),
+        "_text" | "_varchar"             => SqlValue::TextArray(get(row, idx)?),
+        name                             => {
+            println!("Unsupported column type: {}", name);
+            return Err(Error::Unexpected);
+        },
+    };
+    Ok(value)
+}
+
+fn get<T: postgres::types::FromSql>(row: &PgRow, idx: usize) -> Result<T, Error> {
+    match row.get_opt(idx) {
+        Some(Ok(v))  => Ok(v),
+        Some(Err(e)) => Err(Error::from(e)),
+        None         => Err(Error::Unexpected),
+    }
+}
+
+impl Connection for PgConnection {
+    fn backend(&self) -> Backend {
+        Backend::Postgres
+    }
+    fn query(&self, sql: &str, params: &[&ToSql]) -> Result<Rows, Error> {
+        let values = params.iter().map(|p| to_pg(p.to_sql())).collect::<Vec<Box<PgToSql>>>();
+        let params = values.iter().map(|v| &**v).collect::<Vec<&PgToSql>>();
+        let stmt   = self.conn.prepare_cached(sql)?;
+        let mut rows = vec![];
+        for row in stmt.query(&params)?.iter() {
+            let mut values = vec![];
+            for idx in 0..row.len() {
+                values.push(from_pg(&row, idx)?);
+            }
+            rows.push(Row::new(values));
+        }
+        Ok(Rows::new(rows))
+    }
+    fn execute(&self, sql: &str, params: &[&ToSql]) -> Result<u64, Error> {
+        let values = params.iter().map(|p| to_pg(p.to_sql())).collect::<Vec<Box<PgToSql>>>();
+        let params = values.iter().map(|v| &**v).collect::<Vec<&PgToSql>>();
+        let stmt   = self.conn.prepare_cached(sql)?;
+        Ok(stmt.execute(&params)?)
+    }
+    fn batch_execute(&self, sql: &str) -> Result<(), Error> {
+        Ok(self.conn.batch_execute(sql)?)
+    }
+    fn prepare<'a>(&'a self, sql: &str) -> Result<Statement<'a>, Error> {
+        Ok(Statement::new(self, sql))
+    }
+    fn transaction<'a>(&'a self) -> Result<Transaction<'a>, Error> {
+        Transaction::new(self, 0)
+    }
+    fn has_broken(&self) -> bool {
+        self.conn.is_desynchronized()
+    }
+}
@@ -0,0 +1,168 @@
+use std::sync::Mutex;
+use rusqlite;
+use rusqlite::types::Value;
+use regex::{Regex, Captures};
+use error::Error;
+use migration;
+use super::{Backend, Connection, Statement, Transaction, Row, Rows, SqlValue, ToSql};
+
+static MEMORY: &'static str = ":memory:";
+
+lazy_static! {
+    /// The connections to the shared in-memory database are migrated one by one.
+    static ref MIGRATION_LOCK: Mutex<()> = Mutex::new(());
+    static ref ANY: Regex = Regex::new(r"(?i)=\s*ANY\(\$(\d+)\)").unwrap();
+    static ref PARAM: Regex = Regex::new(r"\$(\d+)").unwrap();
+    static ref ILIKE: Regex = Regex::new(r"(?i)\bILIKE\b").unwrap();
+    static ref NOW: Regex = Regex::new(r"(?i)\bNOW\(\)").unwrap();
+    static ref RETURNING: Regex = Regex::new(
+        r"(?is)^(\s*INSERT\s+INTO\s+(\w+).*?)\s+RETURNING\s+(.+?)\s*$"
+    ).unwrap();
+}
+
+/// A connection to an SQLite database for small deployments and tests.
+///
+/// The urls are `sqlite:path/to/file.sqlite3` or `sqlite::memory:`.
+/// An in-memory database is migrated when it is opened,
+/// and the connections of the pool share it.
+pub struct SqliteConnection {
+    conn: rusqlite::Connection,
+}
+
+impl SqliteConnection {
+    pub fn connect(url: &str) -> Result<SqliteConnection, Error> {
+        let path = url.trim().trim_left_matches("sqlite:").trim_left_matches("//");
+        if path == MEMORY {
+            let conn = SqliteConnection::open("file:pink_spider?mode=memory&cache=shared")?;
+            let _lock = MIGRATION_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            migration::up(&conn, None)?;
+            return Ok(conn);
+        }
+        SqliteConnection::open(path)
+    }
+
+    /// This function opens a private in-memory database, which is migrated
+    /// and dropped with the connection.
+    pub fn memory() -> Result<SqliteConnection, Error> {
+        let conn = SqliteConnection::open(MEMORY)?;
+        migration::up(&conn, None)?;
+        Ok(conn)
+    }
+
+    fn open(path: &str) -> Result<SqliteConnection, Error> {
+        let flags = rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE |
+                    rusqlite::OpenFlags::SQLITE_OPEN_CREATE    |
+                    rusqlite::OpenFlags::SQLITE_OPEN_URI;
+        let conn = rusqlite::Connection::open_with_flags(path, flags)?;
+        conn.busy_timeout(::std::time::Duration::from_secs(30))?;
+        conn.create_scalar_function("regexp_replace", 4, true, |ctx| {
+            let text        = ctx.get::<String>(0)?;
+            let pattern     = ctx.get::<String>(1)?;
+            let replacement = ctx.get::<String>(2)?;
+            let re = Regex::new(&pattern)
+                .map_err(|e| rusqlite::Error::UserFunctionError(Box::new(e)))?;
+            Ok(re.replace_all(&text, replacement.as_str()).into_owned())
+        })?;
+        Ok(SqliteConnection { conn: conn })
+    }
+
+    fn query_rows(&self, sql: &str, params: &[&ToSql]) -> Result<Rows, Error> {
+        let values = params.iter().map(|p| to_sqlite(p.to_sql())).collect::<Vec<Value>>();
+        let params = values.iter().map(|v| v as &rusqlite::types::ToSql).collect::<Vec<_>>();
+        let mut stmt = self.conn.prepare_cached(sql)?;
+        let count    = stmt.column_count();
+        let mut rows = vec![];
+        let mut result = stmt.query(&params)?;
+        while let Some(row) = result.next() {
+            let row = row?;
+            let mut values = vec![];
+            for idx in 0..count {
+                values.push(from_sqlite(row.get_checked(idx)?));
+            }
+            rows.push(Row::new(values));
+        }
+        Ok(Rows::new(rows))
+    }
+}
+
+/// This function translates the sql of Postgres into the sql of SQLite.
+/// Arrays are bound as json, so `= ANY($1)` becomes a subquery of `json_each`.
+pub fn translate(sql: &str) -> String {
+    let sql = ANY.replace_all(sql, "IN (SELECT value FROM json_each($$$1))");
+    let sql = PARAM.replace_all(&sql, |caps: &Captures| format!("?{}", &caps[1]));
+    let sql = ILIKE.replace_all(&sql, "LIKE");
+    NOW.replace_all(&sql, "CURRENT_TIMESTAMP").into_owned()
+}
+
+fn to_sqlite(value: SqlValue) -> Value {
+    let value = match value {
+        SqlValue::Bool(v)      => v.map(|v| Value::Integer(v as i64)),
+        SqlValue::Int4(v)      => v.map(|v| Value::Integer(v as i64)),
+        SqlValue::Int8(v)      => v.map(Value::Integer),
+        SqlValue::Float8(v)    => v.map(Value::Real),
+        SqlValue::Text(v)      => v.map(Value::Text),
+        SqlValue::Uuid(v)      => v.map(|v| Value::Text(v.to_string())),
+        SqlValue::Timestamp(v) => v.map(|v| Value::Text(v.format("%Y-%m-%d %H:%M:%S%.f").to_string())),
+        SqlValue::Json(v)      => v.map(|v| Value::Text(v.to_string())),
+        SqlValue::UuidArray(v) => v.map(|v| Value::Text(json!(v).to_string())),
+        SqlValue::TextArray(v) => v.map(|v| Value::Text(json!(v).to_string())),
+    };
+    value.unwrap_or(Value::Null)
+}
+
+fn from_sqlite(value: Value) -> SqlValue {
+    match value {
+        Value::Null       => SqlValue::Text(None),
+        Value::Integer(v) => SqlValue::Int8(Some(v)),
+        Value::Real(v)    => SqlValue::Float8(Some(v)),
+        Value::Text(v)    => SqlValue::Text(Some(v)),
+        Value::Blob(v)    => SqlValue::Text(Some(String::from_utf8_lossy(&v).into_owned())),
+    }
+}
+
+impl Connection for SqliteConnection {
+    fn backend(&self) -> Backend {
+        Backend::Sqlite
+    }
+    /// SQLite doesn't support `RETURNING`,
+    /// so the columns of the inserted row are selected after the insert.
+    fn query(&self, sql: &str, params: &[&ToSql]) -> Result<Rows, Error> {
+        let sql = translate(sql);
+        match RETURNING.captures(&sql) {
+            Some(caps) => {
+                self.execute(&caps[1], params)?;
+                self.query_rows(&format!("SELECT {} FROM {} WHERE rowid = last_insert_rowid()",
+                                         &caps[3], &caps[2]), &[])
+            },
+            None => self.query_rows(&sql, params),
+        }
+    }
+    fn execute(&self, sql: &str, params: &[&ToSql]) -> Result<u64, Error> {
+        let values = params.iter().map(|p| to_sqlite(p.to_sql())).collect::<Vec<Value>>();
+        let params = values.iter().map(|v| v as &rusqlite::types::ToSql).collect::<Vec<_>>();
+        let mut stmt = self.conn.prepare_cached(&translate(sql))?;
+        Ok(stmt.execute(&params)? as u64)
+    }
+    fn batch_execute(&self, sql: &str) -> Result<(), Error> {
+        Ok(self.conn.execute_batch(&translate(sql))?)
+    }
+    fn prepare<'a>(&'a self, sql: &str) -> Result<Statement<'a>, Error> {
+        Ok(Statement::new(self, sql))
+    }
+    fn transaction<'a>(&'a self) -> Result<Transaction<'a>, Error> {
+        Transaction::new(self, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::translate;
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(translate("SELECT id FROM tracks WHERE id = ANY($1) LIMIT $3 OFFSET $2"),
+                   "SELECT id FROM tracks WHERE id IN (SELECT value FROM json_each(?1)) LIMIT ?3 OFFSET ?2");
+        assert_eq!(translate("SELECT id FROM genres WHERE name ILIKE $1 AND created_at < NOW()"),
+                   "SELECT id FROM genres WHERE name LIKE ?1 AND created_at < CURRENT_TIMESTAMP");
+    }
+}
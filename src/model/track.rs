@@ -1,25 +1,39 @@
-use postgres;
+use super::{Connection, Row};
 use uuid::Uuid;
 use std::fmt;
 use std::collections::BTreeMap;
 use chrono::{NaiveDateTime, Utc, DateTime};
+use serde_json::Value;
 
 use apple_music;
 use youtube;
 use youtube::HasThumbnail;
 use soundcloud;
 use spotify;
+use bandcamp;
+use mixcloud;
+use mixcloud::HasPictures;
+use deezer;
+use raw;
+use audio_tag;
+use gracenote;
 use lemoned;
+use matcher;
+use search;
+use search::SearchType;
+use adapter;
 use error::Error;
-use super::{conn, Model};
+use super::{Model, PaginatedCollection, Page, Cursor, Sort, SearchIndex};
 use model::enclosure::Enclosure;
 use model::provider::Provider;
-use model::state::State;
+use model::state::{State, DEAD_AFTER_NOT_FOUND};
+use model::track_failure::TrackFailure;
 use model::artist::Artist;
 use model::album::Album;
 use model::playlist::Playlist;
+use model::genre::Genre;
 
-pub static PROPS: [&'static str; 16]  = ["id",
+pub static PROPS: [&'static str; 22]  = ["id",
                                          "provider",
                                          "identifier",
                                          "owner_id",
@@ -34,7 +48,13 @@ pub static PROPS: [&'static str; 16]  = ["id",
                                          "published_at",
                                          "created_at",
                                          "updated_at",
-                                         "state"];
+                                         "state",
+                                         "tags",
+                                         "isrc",
+                                         "failure_count",
+                                         "missing_count",
+                                         "failed_at",
+                                         "last_failure"];
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Track {
@@ -54,9 +74,19 @@ pub struct Track {
     pub created_at:    NaiveDateTime,
     pub updated_at:    NaiveDateTime,
     pub state:         State,
+    pub tags:          Value,
+    pub isrc:          Option<String>,
+    pub failure_count: i32,
+    pub missing_count: i32,
+    pub failed_at:     Option<NaiveDateTime>,
+    pub last_failure:  Option<String>,
     pub album:         Option<Album>,
     pub artists:       Option<Vec<Artist>>,
     pub playlists:     Option<Vec<Playlist>>,
+    pub genres:        Option<Vec<Genre>>,
+    pub moods:         Option<Vec<String>>,
+    pub tempos:        Option<Vec<String>>,
+    pub failures:      Option<Vec<TrackFailure>>,
 }
 
 impl PartialEq for Track {
@@ -81,7 +111,16 @@ impl<'a> Model<'a> for Track {
             .map(|&p| format!("{}{}", prefix, p))
             .collect::<Vec<String>>().join(",")
     }
-    fn row_to_item(row: postgres::rows::Row) -> Track {
+    fn filter_fields() -> &'static [&'static str] {
+        &["provider", "state", "owner_id", "isrc"]
+    }
+    fn sort_fields() -> &'static [&'static str] {
+        &["updated_at", "created_at", "published_at"]
+    }
+    fn published_field() -> Option<&'static str> {
+        Some("published_at")
+    }
+    fn row_to_item(row: &Row) -> Track {
         Track {
             id:            row.get(0),
             provider:      Provider::new(row.get(1)),
@@ -99,16 +138,26 @@ impl<'a> Model<'a> for Track {
             created_at:    row.get(13),
             updated_at:    row.get(14),
             state:         State::new(row.get(15)),
+            tags:          row.get(16),
+            isrc:          row.get(17),
+            failure_count: row.get(18),
+            missing_count: row.get(19),
+            failed_at:     row.get(20),
+            last_failure:  row.get(21),
             album:         None,
             artists:       None,
             playlists:     None,
+            genres:        None,
+            moods:         None,
+            tempos:        None,
+            failures:      None,
         }
     }
-    fn create(&self) -> Result<Track, Error> {
-        let conn = conn()?;
-        let stmt = conn.prepare("INSERT INTO tracks (provider, identifier, url, title)
-                                      VALUES ($1, $2, $3, $4) RETURNING id")?;
-        let rows = stmt.query(&[&self.provider.to_string(), &self.identifier, &self.url, &self.title])?;
+    fn create(&self, conn: &Connection) -> Result<Track, Error> {
+        let stmt = conn.prepare("INSERT INTO tracks (provider, identifier, url, title, state)
+                                      VALUES ($1, $2, $3, $4, $5) RETURNING id")?;
+        let rows = stmt.query(&[&self.provider.to_string(), &self.identifier, &self.url, &self.title,
+                                 &self.state.to_string()])?;
         let mut track = self.clone();
         for row in rows.iter() {
             track.id = row.get(0);
@@ -116,9 +165,10 @@ impl<'a> Model<'a> for Track {
         Ok(track)
     }
 
-    fn save(&mut self) -> Result<(), Error> {
+    /// This function updates the row with the connection, which can be a transaction,
+    /// e.g. the one that `Entry::add_enclosures` opens.
+    fn save(&mut self, conn: &Connection) -> Result<(), Error> {
         self.updated_at = Utc::now().naive_utc();
-        let conn = conn()?;
         let stmt = conn.prepare("UPDATE tracks SET
                                       provider      = $2,
                                       identifier    = $3,
@@ -134,7 +184,14 @@ impl<'a> Model<'a> for Track {
                                       published_at  = $13,
                                       created_at    = $14,
                                       updated_at    = $15,
-                                      state         = $16
+                                      state         = $16,
+                                      tags          = $17,
+                                      isrc          = $18,
+                                      failure_count = $19,
+                                      missing_count = $20,
+                                      failed_at     = $21,
+                                      last_failure  = $22,
+                                      normalized_title = $23
                                       WHERE id = $1")?;
         let result = stmt.query(&[&self.id,
                                   &self.provider.to_string(),
@@ -152,17 +209,28 @@ impl<'a> Model<'a> for Track {
                                   &self.created_at,
                                   &self.updated_at,
                                   &self.state.to_string(),
+                                  &self.tags,
+                                  &self.isrc,
+                                  &self.failure_count,
+                                  &self.missing_count,
+                                  &self.failed_at,
+                                  &self.last_failure,
+                                  &matcher::normalize(&self.title),
         ]);
         match result {
-            Ok(_)  => Ok(()),
+            Ok(_)  => self.update_search_index(conn),
             Err(_) => Err(Error::Unexpected)
         }
     }
 
-    fn set_relations(tracks: &mut Vec<Track>) -> Result<(), Error> {
+    fn set_relations(conn: &Connection, tracks: &mut Vec<Track>) -> Result<(), Error> {
         let ids = tracks.iter().map(|i| i.id).collect();
-        let artists_map   = Artist::find_by_tracks(&ids)?;
-        let playlists_map = Playlist::find_by_tracks(&ids)?;
+        let artists_map   = Artist::find_by_tracks(conn, &ids)?;
+        let playlists_map = Playlist::find_by_tracks(conn, &ids)?;
+        let genres_map    = Genre::find_by_tracks(conn, &ids)?;
+        let moods_map     = Track::find_labels(conn, &ids, "track_moods")?;
+        let tempos_map    = Track::find_labels(conn, &ids, "track_tempos")?;
+        let failures_map  = TrackFailure::find_by_tracks(conn, &ids)?;
         for track in tracks {
             if let Some(ref mut artists) = artists_map.get(&track.id) {
                 track.artists = Some(artists.clone())
@@ -170,6 +238,18 @@ impl<'a> Model<'a> for Track {
             if let Some(ref mut playlists) = playlists_map.get(&track.id) {
                 track.playlists = Some(playlists.clone())
             }
+            if let Some(ref mut genres) = genres_map.get(&track.id) {
+                track.genres = Some(genres.clone())
+            }
+            if let Some(ref mut moods) = moods_map.get(&track.id) {
+                track.moods = Some(moods.clone())
+            }
+            if let Some(ref mut tempos) = tempos_map.get(&track.id) {
+                track.tempos = Some(tempos.clone())
+            }
+            if let Some(ref mut failures) = failures_map.get(&track.id) {
+                track.failures = Some(failures.clone())
+            }
         }
         Ok(())
     }
@@ -193,10 +273,20 @@ impl<'a> Enclosure<'a> for Track {
             published_at:  Utc::now().naive_utc(),
             created_at:    Utc::now().naive_utc(),
             updated_at:    Utc::now().naive_utc(),
-            state:         State::Alive,
+            state:         State::Pending,
+            tags:          json!([]),
+            isrc:          None,
+            failure_count: 0,
+            missing_count: 0,
+            failed_at:     None,
+            last_failure:  None,
             album:         None,
             artists:       None,
             playlists:     None,
+            genres:        None,
+            moods:         None,
+            tempos:        None,
+            failures:      None,
         }
     }
 
@@ -210,88 +300,125 @@ impl<'a> Enclosure<'a> for Track {
         self
     }
 
-    fn fetch_props(&mut self) -> Result<(), Error> {
-        match self.provider {
-            Provider::YouTube => match youtube::fetch_video(&self.identifier) {
-                Ok(video) => self.update_with_yt_video(&video),
-                Err(_)    => self.disable(),
-            },
-            Provider::SoundCloud => match soundcloud::fetch_track(&self.identifier) {
-                Ok(sc_track) => self.update_with_sc_track(&sc_track),
-                Err(_)       => self.disable(),
-            },
-            Provider::AppleMusic => {
-                let country = apple_music::country(&self.url);
-                match apple_music::fetch_song(&self.identifier, &country) {
-                    Ok(song) => self.update_with_am_song(&song),
-                    Err(_)   => self.disable(),
-                }
-            },
-            Provider::Spotify => match spotify::fetch_track(&self.identifier) {
-                Ok(sp_track) => self.update_with_sp_track(&sp_track),
-                Err(_)       => self.disable(),
-            },
-            Provider::Custom => match lemoned::fetch_track(&self.identifier) {
-                Ok(le_track) => self.update_with_le_track(&le_track),
-                Err(_)       => self.disable(),
-            },
-            _ => self,
-        };
-        match self.state {
-            State::Alive => Ok(()),
-            State::Dead  => Err(Error::NotFound),
+    fn fetch_props(&mut self, conn: &Connection) -> Result<(), Error> {
+        if let Some(adapter) = adapter::find(&self.provider) {
+            match adapter.fetch_track(conn, self) {
+                Ok(_)  => { self.recover(); },
+                Err(e) => {
+                    self.record_failure(conn, &e)?;
+                    return Err(e);
+                },
+            }
+        }
+        if self.state.is_gone() {
+            Err(Error::NotFound)
+        } else {
+            Ok(())
         }
     }
 
-    fn find_by_entry_id(entry_id: Uuid) -> Vec<Track> {
-        let conn = conn().unwrap();
+    fn find_by_entry_ids(conn: &Connection, entry_ids: &[Uuid]) -> Result<BTreeMap<Uuid, Vec<Track>>, Error> {
         let stmt = conn.prepare(
-            &format!("SELECT {} FROM tracks t LEFT JOIN track_entries te
+            &format!("SELECT {}, te.entry_id FROM tracks t LEFT JOIN track_entries te
                         ON t.id = te.track_id
-                        WHERE te.entry_id = $1
+                        WHERE te.entry_id = ANY($1)
                         ORDER BY t.published_at DESC",
-                     Track::props_str("t."))).unwrap();
-        let rows = stmt.query(&[&entry_id]).unwrap();
-        Track::rows_to_items(rows)
+                     Track::props_str("t.")))?;
+        let rows = stmt.query(&[&entry_ids])?;
+        let mut items: BTreeMap<Uuid, Vec<Track>> = BTreeMap::new();
+        for id in entry_ids.iter() {
+            items.insert(*id, vec![]);
+        }
+        for row in rows.iter() {
+            let id: Uuid = row.get(PROPS.len());
+            if let Some(tracks) = items.get_mut(&id) {
+                tracks.push(Self::row_to_item(row))
+            }
+        }
+        Ok(items)
     }
 }
 
 impl Track {
-    pub fn from_am_song(song: &apple_music::Song) -> Track {
+    /// This function indexes the title, the owner name and the description of the track for `search`.
+    pub fn update_search_index(&self, conn: &Connection) -> Result<(), Error> {
+        let tokens = search::weigh_tokens(&[(Some(self.title.as_str()), 3.0),
+                                            (self.owner_name.as_ref().map(|s| s.as_str()), 2.0),
+                                            (self.description.as_ref().map(|s| s.as_str()), 1.0)]);
+        SearchIndex::update_in(conn, &SearchType::Track.to_string(), self.id, &tokens)
+    }
+
+    pub fn from_am_song(conn: &Connection, song: &apple_music::Song) -> Track {
         let identifier = (*song).id.to_string();
-        Track::find_or_create(Provider::AppleMusic, identifier.to_string())
+        Track::find_or_create(conn, Provider::AppleMusic, identifier.to_string())
             .unwrap()
-            .update_with_am_song(song)
+            .update_with_am_song(conn, song)
             .clone()
     }
-    pub fn from_yt_playlist_item(item: &youtube::PlaylistItem) -> Track {
+    pub fn from_yt_playlist_item(conn: &Connection, item: &youtube::PlaylistItem) -> Track {
         let identifier = (*item).snippet.resourceId["videoId"].to_string();
-        Track::find_or_create(Provider::YouTube, identifier.to_string())
+        Track::find_or_create(conn, Provider::YouTube, identifier.to_string())
             .unwrap()
-            .update_with_yt_playlist_item(item)
+            .update_with_yt_playlist_item(conn, item)
             .clone()
     }
-    pub fn from_yt_video(video: &youtube::Video) -> Track {
-        Track::find_or_create(Provider::YouTube, (*video).id.to_string())
+    pub fn from_yt_video(conn: &Connection, video: &youtube::Video) -> Track {
+        Track::find_or_create(conn, Provider::YouTube, (*video).id.to_string())
             .unwrap()
-            .update_with_yt_video(video)
+            .update_with_yt_video(conn, video)
             .clone()
     }
-    pub fn from_sc_track(track: &soundcloud::Track) -> Track {
-        Track::find_or_create(Provider::SoundCloud, (*track).id.to_string())
+    pub fn from_sc_track(conn: &Connection, track: &soundcloud::Track) -> Track {
+        Track::find_or_create(conn, Provider::SoundCloud, (*track).id.to_string())
             .unwrap()
-            .update_with_sc_track(track)
+            .update_with_sc_track(conn, track)
             .clone()
     }
-    pub fn from_sp_track(track: &spotify::Track) -> Result<Track, Error> {
+    pub fn from_sp_track(conn: &Connection, track: &spotify::Track) -> Result<Track, Error> {
         let track_id = track.clone().id.ok_or(Error::Unexpected)?;
-        Ok(Track::find_or_create(Provider::Spotify, track_id.to_string())
+        Ok(Track::find_or_create(conn, Provider::Spotify, track_id.to_string())
             .unwrap()
-            .update_with_sp_track(track)
+            .update_with_sp_track(conn, track)
             .clone())
     }
-    fn add_artist(&mut self, artist: &Artist) -> Result<(), Error> {
-        let conn = conn()?;
+    pub fn from_bc_track_info(conn: &Connection, page: &bandcamp::Page, info: &bandcamp::TrackInfo) -> Track {
+        let identifier = info.identifier().unwrap_or(page.tralbum.id.to_string());
+        Track::find_or_create(conn, Provider::Bandcamp, identifier)
+            .unwrap()
+            .update_with_bc_track_info(conn, page, info)
+            .clone()
+    }
+    pub fn from_mc_cloudcast(conn: &Connection, cloudcast: &mixcloud::Cloudcast) -> Track {
+        Track::find_or_create(conn, Provider::Mixcloud, cloudcast.key.to_string())
+            .unwrap()
+            .update_with_mc_cloudcast(conn, cloudcast)
+            .clone()
+    }
+    pub fn from_dz_track(conn: &Connection, track: &deezer::Track) -> Track {
+        Track::find_or_create(conn, Provider::Deezer, track.id.to_string())
+            .unwrap()
+            .update_with_dz_track(conn, track)
+            .clone()
+    }
+    /// This function returns a pending track for an identifier that the provider failed to return.
+    /// Nothing is saved here; the failure is recorded by `fetch_props` when the track is fetched again.
+    /// The errors that aren't of the provider are returned as they are.
+    pub fn from_failure(provider: Provider, identifier: &str, err: Error) -> Result<Track, Error> {
+        match err {
+            Error::Provider(_, _) => Ok(Track::new(provider, identifier.to_string())),
+            err                   => Err(err),
+        }
+    }
+    /// This function creates a track of an audio file.
+    /// The tags are read if possible, but the track is created anyway.
+    pub fn from_raw_url(conn: &Connection, url: &str) -> Track {
+        let tag = raw::fetch_tag(url).unwrap_or(audio_tag::Tag::default());
+        Track::find_or_create(conn, Provider::Raw, url.to_string())
+            .unwrap()
+            .update_with_raw_tag(url, &tag)
+            .clone()
+    }
+    fn add_artist(&mut self, conn: &Connection, artist: &Artist) -> Result<(), Error> {
         let stmt = conn.prepare("INSERT INTO track_artists (track_id, artist_id) VALUES ($1, $2)")?;
         stmt.query(&[&self.id, &artist.id])?;
         match self.artists {
@@ -301,20 +428,20 @@ impl Track {
         }
         Ok(())
     }
-    fn add_artists(&mut self, artists: Vec<Artist>) {
+    fn add_artists(&mut self, conn: &Connection, artists: Vec<Artist>) {
         self.artists = Some(artists.iter().map(|a| {
             let mut a = a.clone();
-            if let Ok(new_artist) = Artist::find_or_create(a.provider,
+            if let Ok(new_artist) = Artist::find_or_create(conn,
+                                                           a.provider,
                                                            a.identifier.to_string()) {
                 a.id      = new_artist.id;
-                let _     = a.save();
-                let _     = self.add_artist(&a);
+                let _     = a.save(conn);
+                let _     = self.add_artist(conn, &a);
             };
             a
         }).collect::<Vec<_>>());
     }
-    pub fn find_by_artist(artist_id: Uuid) -> Vec<Track> {
-        let conn = conn().unwrap();
+    pub fn find_by_artist(conn: &Connection, artist_id: Uuid) -> Vec<Track> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM tracks
                       LEFT OUTER JOIN track_artists ON track_artists.track_id = tracks.id
@@ -323,8 +450,7 @@ impl Track {
         let rows = stmt.query(&[&artist_id]).unwrap();
         Track::rows_to_items(rows)
     }
-    pub fn find_by_album(album_id: Uuid) -> Vec<Track> {
-        let conn = conn().unwrap();
+    pub fn find_by_album(conn: &Connection, album_id: Uuid) -> Vec<Track> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM tracks
                       LEFT OUTER JOIN album_tracks ON album_tracks.track_id = tracks.id
@@ -333,8 +459,7 @@ impl Track {
         let rows = stmt.query(&[&album_id]).unwrap();
         Track::rows_to_items(rows)
     }
-    pub fn find_by_albums(album_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Track>>, Error> {
-        let conn = conn().unwrap();
+    pub fn find_by_albums(conn: &Connection, album_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<Track>>, Error> {
         let sql = format!("SELECT {}, album_tracks.album_id FROM tracks
                       LEFT OUTER JOIN album_tracks ON album_tracks.track_id = tracks.id
                       WHERE album_tracks.album_id = ANY($1)", Track::props_str("tracks."));
@@ -352,8 +477,145 @@ impl Track {
         }
         Ok(items)
     }
-    pub fn find_by_provider(provider: &Provider) -> Vec<Track> {
-        let conn = conn().unwrap();
+    pub fn find_by_genre(conn: &Connection, genre_id: Uuid, page: &Page) -> Result<PaginatedCollection<Track>, Error> {
+        let sort   = Sort::new("published_at", true);
+        let limit  = page.limit();
+        let offset = page.offset();
+        let rows = if let Some(ref cursor) = page.cursor {
+            cursor.check(&sort)?;
+            let stmt = conn.prepare(
+                &format!("SELECT {} FROM tracks
+                          LEFT OUTER JOIN track_genres ON track_genres.track_id = tracks.id
+                          WHERE track_genres.genre_id = $1
+                            AND (tracks.published_at, tracks.id) < ($4, $5)
+                          ORDER BY tracks.published_at DESC, tracks.id DESC
+                          LIMIT $3 OFFSET $2",
+                         Track::props_str("tracks.")))?;
+            stmt.query(&[&genre_id, &offset, &limit, &cursor.at, &cursor.id])?
+        } else {
+            let stmt = conn.prepare(
+                &format!("SELECT {} FROM tracks
+                          LEFT OUTER JOIN track_genres ON track_genres.track_id = tracks.id
+                          WHERE track_genres.genre_id = $1
+                          ORDER BY tracks.published_at DESC, tracks.id DESC
+                          LIMIT $3 OFFSET $2",
+                         Track::props_str("tracks.")))?;
+            stmt.query(&[&genre_id, &offset, &limit])?
+        };
+        let items   = Track::rows_to_items(rows);
+        let cursors = items.iter().map(|t| Cursor::new(&sort, t.published_at, t.id)).collect();
+        let mut total = None;
+        if page.with_total {
+            let sql = "SELECT COUNT(*) FROM track_genres WHERE track_genres.genre_id = $1";
+            for row in conn.query(&sql, &[&genre_id])?.iter() {
+                total = Some(row.get(0));
+            }
+        }
+        let mut tracks = PaginatedCollection::paginate(page, items, cursors, total);
+        Track::set_relations(conn, &mut tracks.items)?;
+        Ok(tracks)
+    }
+    pub fn find_by_same_isrc(&self, conn: &Connection) -> Result<Vec<Track>, Error> {
+        let isrc = match self.isrc {
+            Some(ref isrc) => isrc.to_string(),
+            None           => return Ok(vec![]),
+        };
+        let stmt = conn.prepare(
+            &format!("SELECT {} FROM tracks
+                      WHERE tracks.isrc = $1 AND tracks.id <> $2 AND tracks.state = 'alive'",
+                     Track::props_str("")))?;
+        let rows = stmt.query(&[&isrc, &self.id])?;
+        Ok(Track::rows_to_items(rows))
+    }
+    /// This function finds the tracks of other providers whose normalized title is
+    /// one of the title variants, and they are checked by matcher afterwards.
+    /// The artists are filtered as `matcher` does before the limit, so that the common titles
+    /// don't hide the tracks of the artist: a name of the track is a name of the other one,
+    /// or a part of its title, or a name of the other one is a part of the title of the track.
+    fn find_by_title_variants(&self, conn: &Connection) -> Result<Vec<Track>, Error> {
+        let names    = matcher::artist_names(self);
+        let variants = matcher::title_variants(&self.title).into_iter()
+            .filter(|v| !names.contains(v))
+            .collect::<Vec<String>>();
+        if variants.is_empty() {
+            return Ok(vec![]);
+        }
+        let name_patterns = names.iter().map(|n| format!("% {} %", n)).collect::<Vec<String>>();
+        let title         = format!(" {} ", matcher::normalize(&self.title));
+        let stmt = conn.prepare(
+            &format!("SELECT {} FROM tracks
+                      WHERE tracks.provider <> $1 AND tracks.state = 'alive'
+                        AND tracks.normalized_title = ANY($2)
+                        AND (trim(regexp_replace(lower(tracks.owner_name), '[^[:alnum:]]+', ' ', 'g')) = ANY($3)
+                          OR $5 LIKE '% ' || trim(regexp_replace(lower(tracks.owner_name), '[^[:alnum:]]+', ' ', 'g')) || ' %'
+                          OR ' ' || tracks.normalized_title || ' ' LIKE ANY($4)
+                          OR EXISTS (SELECT 1 FROM track_artists ta JOIN artists a ON a.id = ta.artist_id
+                                      WHERE ta.track_id = tracks.id
+                                        AND (trim(regexp_replace(lower(a.name), '[^[:alnum:]]+', ' ', 'g')) = ANY($3)
+                                          OR $5 LIKE '% ' || trim(regexp_replace(lower(a.name), '[^[:alnum:]]+', ' ', 'g')) || ' %')))
+                      ORDER BY tracks.created_at ASC LIMIT 50",
+                     Track::props_str("")))?;
+        let rows = stmt.query(&[&self.provider.to_string(), &variants, &names, &name_patterns, &title])?;
+        let mut items = Track::rows_to_items(rows);
+        let ids = items.iter().map(|t| t.id).collect();
+        let artists_map = Artist::find_by_tracks(conn, &ids)?;
+        for track in items.iter_mut() {
+            track.artists = artists_map.get(&track.id).cloned();
+        }
+        Ok(items)
+    }
+    /// The links are symmetric, so both of them are inserted in a transaction.
+    fn link(&self, conn: &Connection, track: &Track, matched_by: &str) -> Result<(), Error> {
+        let sql   = "INSERT INTO track_links (track_id, linked_track_id, matched_by)
+                     VALUES ($1, $2, $3) ON CONFLICT DO NOTHING";
+        let trans = conn.transaction()?;
+        trans.execute(sql, &[&self.id, &track.id, &matched_by])?;
+        trans.execute(sql, &[&track.id, &self.id, &matched_by])?;
+        trans.commit()?;
+        Ok(())
+    }
+    /// This function links the track to the same songs of other providers.
+    /// The tracks that have the same isrc are linked first,
+    /// then the tracks that have the same title, artist and duration.
+    /// It scans the titles of the tracks, so it runs in the link_tracks batch instead of the requests.
+    pub fn link_equivalents(&self, conn: &Connection) -> Result<Vec<Track>, Error> {
+        let mut items = vec![];
+        for track in self.find_by_same_isrc(conn)? {
+            self.link(conn, &track, "isrc")?;
+            items.push(track);
+        }
+        for track in self.find_by_title_variants(conn)? {
+            if items.contains(&track) || !matcher::is_equivalent(self, &track) {
+                continue;
+            }
+            self.link(conn, &track, "title")?;
+            items.push(track);
+        }
+        conn.execute("UPDATE tracks SET linked_at = $2 WHERE id = $1",
+                     &[&self.id, &Utc::now().naive_utc()])?;
+        Ok(items)
+    }
+    /// This function finds the alive tracks that haven't been linked yet.
+    pub fn find_unlinked(conn: &Connection, limit: i64) -> Result<Vec<Track>, Error> {
+        let stmt = conn.prepare(
+            &format!("SELECT {} FROM tracks
+                      WHERE tracks.linked_at IS NULL AND tracks.state = 'alive'
+                      ORDER BY tracks.created_at ASC LIMIT $1",
+                     Track::props_str("")))?;
+        let rows = stmt.query(&[&limit])?;
+        Ok(Track::rows_to_items(rows))
+    }
+    pub fn find_equivalents(&self, conn: &Connection) -> Result<Vec<Track>, Error> {
+        let stmt = conn.prepare(
+            &format!("SELECT {} FROM tracks
+                      LEFT OUTER JOIN track_links ON track_links.linked_track_id = tracks.id
+                      WHERE track_links.track_id = $1 AND tracks.state = 'alive'
+                      ORDER BY track_links.created_at ASC",
+                     Track::props_str("tracks.")))?;
+        let rows = stmt.query(&[&self.id])?;
+        Ok(Track::rows_to_items(rows))
+    }
+    pub fn find_by_provider(conn: &Connection, provider: &Provider) -> Vec<Track> {
         let stmt = conn.prepare(
             &format!("SELECT {} FROM tracks WHERE tracks.provider = $1
                         ORDER BY tracks.created_at DESC",
@@ -361,7 +623,115 @@ impl Track {
         let rows = stmt.query(&[&(*provider).to_string()]).unwrap();
         Track::rows_to_items(rows)
     }
-    pub fn update_with_am_song(&mut self, song: &apple_music::Song) -> &mut Track {
+    /// This function looks up the track on gracenote by its artist and title,
+    /// and stores the genres, moods and tempos of the matched track.
+    /// A track that gracenote doesn't match is marked as enriched too, so it isn't looked up again.
+    pub fn enrich(&mut self, conn: &Connection) -> Result<(), Error> {
+        match self.search_gn_track() {
+            Ok((album, track)) => self.update_with_gn_track(conn, &album, &track),
+            Err(e) => {
+                if e.is_not_found() {
+                    self.set_enriched_at(conn)?;
+                }
+                Err(e)
+            },
+        }
+    }
+
+    fn search_gn_track(&self) -> Result<(gracenote::Album, gracenote::Track), Error> {
+        let artist = self.artist_name().ok_or(Error::NotFound)?;
+        let album  = gracenote::search_track(&artist, &self.title)?.ok_or(Error::NotFound)?;
+        let track  = album.find_track(&self.title)
+            .or(album.tracks.first())
+            .ok_or(Error::NotFound)?
+            .clone();
+        Ok((album, track))
+    }
+
+    fn set_enriched_at(&self, conn: &Connection) -> Result<(), Error> {
+        conn.execute("UPDATE tracks SET enriched_at = $2 WHERE id = $1",
+                     &[&self.id, &Utc::now().naive_utc()])?;
+        Ok(())
+    }
+
+    /// This function finds the tracks that haven't been looked up on gracenote yet.
+    pub fn find_unenriched(conn: &Connection) -> Result<Vec<Track>, Error> {
+        let stmt = conn.prepare(
+            &format!("SELECT {} FROM tracks
+                      WHERE tracks.enriched_at IS NULL AND tracks.state NOT IN ('removed', 'dead')
+                      ORDER BY tracks.published_at DESC",
+                     Track::props_str("")))?;
+        let rows = stmt.query(&[])?;
+        Ok(Track::rows_to_items(rows))
+    }
+
+    pub fn update_with_gn_track(&mut self, conn: &Connection, album: &gracenote::Album, track: &gracenote::Track) -> Result<(), Error> {
+        let genre_names = if track.genres.is_empty() {
+            album.genre_names()
+        } else {
+            track.genre_names()
+        };
+        self.add_genres(conn, &genre_names)?;
+        self.set_labels(conn, "track_moods", &track.moods)?;
+        self.set_labels(conn, "track_tempos", &track.tempos)?;
+        self.moods  = Some(track.moods.clone());
+        self.tempos = Some(track.tempos.clone());
+        self.set_enriched_at(conn)
+    }
+
+    fn artist_name(&self) -> Option<String> {
+        self.artists.clone()
+            .and_then(|artists| artists.first().map(|a| a.name.to_string()))
+            .or(self.owner_name.clone())
+            .and_then(|name| if name.is_empty() { None } else { Some(name) })
+    }
+
+    pub fn add_genres(&mut self, conn: &Connection, names: &Vec<String>) -> Result<(), Error> {
+        let mut genres = Genre::add_to(conn, "track", self.id, names)?;
+        let mut items  = self.genres.clone().unwrap_or(vec![]);
+        items.retain(|g| !genres.contains(g));
+        items.append(&mut genres);
+        self.genres = Some(items);
+        Ok(())
+    }
+
+    /// The genres are added while the track is updated with a response of a provider.
+    /// A failure doesn't stop the update, so it is logged here instead of being returned.
+    fn try_add_genres(&mut self, conn: &Connection, names: &Vec<String>) {
+        if let Err(e) = self.add_genres(conn, names) {
+            println!("Failed to add genres {:?} to track {}: {}", names, self.id, e);
+        }
+    }
+
+    fn set_labels(&self, conn: &Connection, table: &str, names: &Vec<String>) -> Result<(), Error> {
+        let stmt = conn.prepare(&format!("DELETE FROM {} WHERE track_id = $1", table))?;
+        stmt.query(&[&self.id])?;
+        let stmt = conn.prepare(&format!("INSERT INTO {} (track_id, name, ord) VALUES ($1, $2, $3)
+                                          ON CONFLICT DO NOTHING", table))?;
+        for (i, name) in names.iter().enumerate() {
+            stmt.query(&[&self.id, name, &(i as i32)])?;
+        }
+        Ok(())
+    }
+
+    fn find_labels(conn: &Connection, ids: &Vec<Uuid>, table: &str) -> Result<BTreeMap<Uuid, Vec<String>>, Error> {
+        let stmt = conn.prepare(&format!("SELECT track_id, name FROM {}
+                                          WHERE track_id = ANY($1) ORDER BY ord ASC", table))?;
+        let rows = stmt.query(&[&ids])?;
+        let mut items: BTreeMap<Uuid, Vec<String>> = BTreeMap::new();
+        for id in ids.iter() {
+            items.insert(*id, vec![]);
+        }
+        for row in rows.iter() {
+            let id: Uuid = row.get(0);
+            if let Some(labels) = items.get_mut(&id) {
+                labels.push(row.get(1))
+            }
+        }
+        Ok(items)
+    }
+
+    pub fn update_with_am_song(&mut self, conn: &Connection, song: &apple_music::Song) -> &mut Track {
         let song_artists = song.clone().relationships.map(|r| {
             r.artists.data.clone()
         });
@@ -375,7 +745,8 @@ impl Track {
         self.audio_url     = song.attributes.previews.first().map(|p| {
             p.url.clone()
         });
-        self.state         = State::Alive;
+        self.isrc          = matcher::normalize_isrc(&song.attributes.isrc);
+        self.state         = song.attributes.state();
         if let Some(song_artist) = song_artists.clone().and_then(|a| a.first().map(|a| a.clone())) {
             let artist_name    = song_artist.attributes.name.clone();
             self.owner_id      = Some(song_artist.id.to_string());
@@ -386,13 +757,14 @@ impl Track {
         if let Some(song_artists) = song_artists.clone() {
             let artist_ids = song_artists.iter().map(|a| a.id.clone()).collect::<Vec<String>>();
             let artists = apple_music::fetch_artists(&country, artist_ids).unwrap_or(vec![]);
-            self.add_artists(artists.iter().map(|a| Artist::from_am_artist(a)).collect());
+            self.add_artists(conn, artists.iter().map(|a| Artist::from_am_artist(conn, a)).collect());
         }
+        self.try_add_genres(conn, &apple_music::genre_names(&song.attributes.genre_names));
 
         self
     }
 
-    pub fn update_with_yt_video(&mut self, video: &youtube::Video) -> &mut Track {
+    pub fn update_with_yt_video(&mut self, conn: &Connection, video: &youtube::Video) -> &mut Track {
         let s              = &video.snippet;
         self.provider      = Provider::YouTube;
         self.identifier    = video.id.to_string();
@@ -404,18 +776,20 @@ impl Track {
         self.thumbnail_url = s.get_thumbnail_url();
         self.artwork_url   = s.get_artwork_url();
         self.audio_url     = None;
-        self.state         = State::Alive;
+        self.tags          = json!(s.tags.clone().unwrap_or(vec![]));
+        self.state         = video.state();
         match DateTime::parse_from_rfc3339(&s.publishedAt) {
             Ok(published_at) => self.published_at = published_at.naive_utc(),
             Err(_)           => (),
         }
         if let Ok(channel) = youtube::fetch_channel(&s.channelId) {
-            self.add_artists(vec![Artist::from_yt_channel(&channel)]);
+            self.add_artists(conn, vec![Artist::from_yt_channel(conn, &channel)]);
         }
+        self.try_add_genres(conn, &s.genre_names());
         self
     }
 
-    pub fn update_with_yt_playlist_item(&mut self, item: &youtube::PlaylistItem) -> &mut Track {
+    pub fn update_with_yt_playlist_item(&mut self, conn: &Connection, item: &youtube::PlaylistItem) -> &mut Track {
         let s              = &item.snippet;
         self.provider      = Provider::YouTube;
         self.identifier    = s.resourceId["videoId"].to_string();
@@ -427,19 +801,19 @@ impl Track {
         self.thumbnail_url = s.get_thumbnail_url();
         self.artwork_url   = s.get_artwork_url();
         self.audio_url     = None;
-        self.state         = State::Alive;
+        self.state         = s.state();
         match DateTime::parse_from_rfc3339(&s.publishedAt) {
             Ok(published_at) => self.published_at = published_at.naive_utc(),
             Err(_)           => (),
         }
         if let Ok(channel) = youtube::fetch_channel(&s.channelId) {
-            self.add_artists(vec![Artist::from_yt_channel(&channel)]);
+            self.add_artists(conn, vec![Artist::from_yt_channel(conn, &channel)]);
         }
         self
     }
 
 
-    pub fn update_with_sc_track(&mut self, track: &soundcloud::Track) -> &mut Track {
+    pub fn update_with_sc_track(&mut self, conn: &Connection, track: &soundcloud::Track) -> &mut Track {
         self.provider      = Provider::SoundCloud;
         self.identifier    = track.id.to_string();
         self.owner_id      = Some(track.user.id.to_string());
@@ -450,16 +824,18 @@ impl Track {
         self.thumbnail_url = track.artwork_url.clone();
         self.artwork_url   = track.artwork_url.clone();
         self.audio_url     = Some(track.stream_url.clone());
-        self.state         = State::Alive;
+        self.tags          = json!(track.tags());
+        self.state         = track.state();
         match DateTime::parse_from_str(&track.created_at, "%Y/%m/%d %H:%M:%S %z") {
             Ok(published_at) => self.published_at = published_at.naive_utc(),
             Err(_)           => (),
         }
-        self.add_artists(vec![Artist::from_sc_user(&track.user)]);
+        self.add_artists(conn, vec![Artist::from_sc_user(conn, &track.user)]);
+        self.try_add_genres(conn, &track.genre_names());
         self
     }
 
-    pub fn update_with_sp_track(&mut self, track: &spotify::Track) -> &mut Track {
+    pub fn update_with_sp_track(&mut self, conn: &Connection, track: &spotify::Track) -> &mut Track {
         if track.id == None {
             return self
         }
@@ -473,22 +849,24 @@ impl Track {
         self.title          = track.name.clone();
         self.description    = None;
         self.audio_url      = track.preview_url.clone();
-        self.state          = State::Alive;
+        self.isrc           = track.external_ids.clone()
+            .and_then(|ids| ids.get("isrc").and_then(|isrc| matcher::normalize_isrc(isrc)));
+        self.state          = track.state();
         self.published_at   = Utc::now().naive_utc();
         if let Some(album) = track.album.clone() {
-            self.update_with_sp_album(&album);
+            self.update_with_sp_album(conn, &album);
         }
 
         let artist_ids = track.artists.iter().map(|a| a.id.clone()).collect::<Vec<String>>();
         let sp_artists = spotify::fetch_artists(artist_ids).unwrap_or_default();
-        let artists = sp_artists.iter().map(|ref a| Artist::from_sp_artist(a))
+        let artists = sp_artists.iter().map(|ref a| Artist::from_sp_artist(conn, a))
             .collect::<Vec<_>>();
-        self.add_artists(artists);
+        self.add_artists(conn, artists);
 
         self
     }
 
-    pub fn update_with_sp_album(&mut self, album: &spotify::Album) -> &mut Track {
+    pub fn update_with_sp_album(&mut self, conn: &Connection, album: &spotify::Album) -> &mut Track {
         if album.images.len() > 0 {
             self.artwork_url   = Some(album.images[0].url.clone());
             self.thumbnail_url = Some(album.images[0].url.clone());
@@ -496,6 +874,101 @@ impl Track {
         if album.images.len() > 1 {
             self.thumbnail_url = Some(album.images[1].url.clone());
         }
+        if let Some(ref genres) = album.genres {
+            self.try_add_genres(conn, genres);
+        }
+        self
+    }
+
+    pub fn update_with_bc_track_info(&mut self, conn: &Connection, page: &bandcamp::Page, info: &bandcamp::TrackInfo) -> &mut Track {
+        let tralbum        = &page.tralbum;
+        self.provider      = Provider::Bandcamp;
+        self.identifier    = info.identifier().unwrap_or(tralbum.id.to_string());
+        self.owner_id      = page.band.clone().map(|b| b.id.to_string());
+        self.owner_name    = Some(info.artist.clone().unwrap_or(tralbum.artist.clone()));
+        self.url           = page.track_url(info);
+        self.title         = info.title.clone();
+        self.thumbnail_url = tralbum.get_thumbnail_url();
+        self.artwork_url   = tralbum.get_artwork_url();
+        self.audio_url     = info.stream_url();
+        self.duration      = info.duration.unwrap_or(0.0) as i32;
+        self.state         = State::Alive;
+        if tralbum.item_type == "track" {
+            self.description = tralbum.current.about.clone();
+        }
+        if let Some(published_at) = tralbum.album_release_date.clone()
+            .and_then(|d| bandcamp::parse_date(&d)) {
+            self.published_at = published_at;
+        }
+        if let Some(ref band) = page.band {
+            self.add_artists(conn, vec![Artist::from_bc_band(conn, band, &page.band_url())]);
+        }
+        self
+    }
+
+    pub fn update_with_mc_cloudcast(&mut self, conn: &Connection, cloudcast: &mixcloud::Cloudcast) -> &mut Track {
+        self.provider      = Provider::Mixcloud;
+        self.identifier    = cloudcast.key.to_string();
+        self.owner_id      = Some(cloudcast.user.username.to_string());
+        self.owner_name    = Some(cloudcast.user.name.to_string());
+        self.url           = cloudcast.url.to_string();
+        self.title         = cloudcast.name.to_string();
+        self.description   = cloudcast.description.clone();
+        self.thumbnail_url = cloudcast.get_thumbnail_url();
+        self.artwork_url   = cloudcast.get_artwork_url();
+        self.audio_url     = None;
+        self.duration      = cloudcast.audio_length.unwrap_or(0);
+        self.tags          = json!(cloudcast.tag_names());
+        self.state         = State::Alive;
+        match DateTime::parse_from_rfc3339(&cloudcast.created_time) {
+            Ok(published_at) => self.published_at = published_at.naive_utc(),
+            Err(_)           => (),
+        }
+        self.add_artists(conn, vec![Artist::from_mc_user(conn, &cloudcast.user)]);
+        self.try_add_genres(conn, &cloudcast.tag_names());
+        self
+    }
+
+    pub fn update_with_dz_track(&mut self, conn: &Connection, track: &deezer::Track) -> &mut Track {
+        self.provider      = Provider::Deezer;
+        self.identifier    = track.id.to_string();
+        self.owner_id      = Some(track.artist.id.to_string());
+        self.owner_name    = Some(track.artist.name.to_string());
+        self.url           = track.link.to_string();
+        self.title         = track.title.to_string();
+        self.description   = None;
+        self.audio_url     = track.preview.clone().and_then(|p| if p.is_empty() { None } else { Some(p) });
+        self.duration      = track.duration;
+        self.isrc          = track.isrc.clone().and_then(|isrc| matcher::normalize_isrc(&isrc));
+        self.state         = track.state();
+        if let Some(published_at) = track.release_date.clone()
+            .and_then(|d| deezer::parse_date(&d)) {
+            self.published_at = published_at;
+        }
+        if let Some(ref album) = track.album {
+            self.update_with_dz_album(conn, album);
+        }
+        let artists = track.contributors.clone().unwrap_or(vec![track.artist.clone()]);
+        self.add_artists(conn, artists.iter().map(|a| Artist::from_dz_artist(conn, a)).collect());
+        self
+    }
+
+    pub fn update_with_dz_album(&mut self, conn: &Connection, album: &deezer::Album) -> &mut Track {
+        self.thumbnail_url = album.cover_medium.clone();
+        self.artwork_url   = album.cover_xl.clone();
+        self.try_add_genres(conn, &album.genre_names());
+        self
+    }
+
+    pub fn update_with_raw_tag(&mut self, url: &str, tag: &audio_tag::Tag) -> &mut Track {
+        self.provider      = Provider::Raw;
+        self.identifier    = url.to_string();
+        self.owner_name    = tag.artist.clone();
+        self.url           = url.to_string();
+        self.title         = tag.title.clone().unwrap_or(raw::file_name(url));
+        self.audio_url     = Some(url.to_string());
+        self.duration      = tag.duration.unwrap_or(0);
+        self.state         = State::Alive;
         self
     }
 
@@ -517,4 +990,37 @@ impl Track {
         self.state = State::Dead;
         self
     }
+
+    /// This function records a failure of fetching the track from the provider.
+    /// The track becomes dead only after `DEAD_AFTER_NOT_FOUND` confirmed not found
+    /// responses, so timeouts and missing credentials don't kill it.
+    /// The counters are saved with the track, and the failure is added to the history.
+    pub fn record_failure(&mut self, conn: &Connection, err: &Error) -> Result<TrackFailure, Error> {
+        self.failure_count += 1;
+        self.failed_at      = Some(Utc::now().naive_utc());
+        self.last_failure   = Some(err.summary());
+        if err.is_not_found() {
+            self.missing_count += 1;
+            if self.missing_count >= DEAD_AFTER_NOT_FOUND {
+                self.disable();
+            }
+        } else {
+            self.missing_count = 0;
+        }
+        TrackFailure::create(conn, self.id, err)
+    }
+
+    /// This function resets the counters after the track is fetched successfully.
+    /// The last failure is kept for the history.
+    pub fn recover(&mut self) -> &mut Track {
+        self.failure_count = 0;
+        self.missing_count = 0;
+        self
+    }
+
+    /// This function makes a dead track alive again, e.g. when it was killed by mistake.
+    pub fn revive(&mut self) -> &mut Track {
+        self.state = State::Alive;
+        self.recover()
+    }
 }
@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+use uuid::Uuid;
+use chrono::NaiveDateTime;
+use super::{Connection, Row};
+use error::Error;
+
+/// A failure of fetching a track from its provider. The failures are kept
+/// as the history, so that a dead track can be told from a flaky provider.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrackFailure {
+    pub id:         i32,
+    pub track_id:   Uuid,
+    pub error:      String,
+    pub message:    String,
+    pub created_at: NaiveDateTime,
+}
+
+static PROPS: [&'static str; 5]  = ["id",
+                                    "track_id",
+                                    "error",
+                                    "message",
+                                    "created_at"];
+
+/// The number of the failures of a track that are kept and returned with it.
+static HISTORY_SIZE: i64 = 20;
+
+impl TrackFailure {
+    fn props_str(prefix: &str) -> String {
+        PROPS
+            .iter()
+            .map(|&p| format!("{}{}", prefix, p))
+            .collect::<Vec<String>>().join(",")
+    }
+    fn row_to_item(row: &Row) -> Self {
+        TrackFailure {
+            id:         row.get(0),
+            track_id:   row.get(1),
+            error:      row.get(2),
+            message:    row.get(3),
+            created_at: row.get(4),
+        }
+    }
+    /// This function adds a failure to the history of a track,
+    /// and drops the old ones so that the history doesn't grow without limit.
+    pub fn create(conn: &Connection, track_id: Uuid, err: &Error) -> Result<TrackFailure, Error> {
+        let name    = err.name();
+        let message = err.summary();
+        let stmt = conn.prepare(&format!("INSERT INTO track_failures (track_id, error, message)
+                                          VALUES ($1, $2, $3) RETURNING {}",
+                                         TrackFailure::props_str("")))?;
+        let failure = {
+            let rows = stmt.query(&[&track_id, &name, &message])?;
+            let row  = rows.iter().next().ok_or(Error::Unexpected)?;
+            TrackFailure::row_to_item(row)
+        };
+        conn.execute("DELETE FROM track_failures
+                        WHERE track_id = $1 AND id NOT IN (
+                          SELECT id FROM track_failures WHERE track_id = $1
+                            ORDER BY created_at DESC, id DESC
+                            LIMIT $2
+                        )", &[&track_id, &HISTORY_SIZE])?;
+        Ok(failure)
+    }
+    pub fn find_by_tracks(conn: &Connection, track_ids: &Vec<Uuid>) -> Result<BTreeMap<Uuid, Vec<TrackFailure>>, Error> {
+        let stmt = conn.prepare(&format!("SELECT {} FROM track_failures
+                                          WHERE track_id = ANY($1)
+                                          ORDER BY created_at DESC, id DESC",
+                                         TrackFailure::props_str("")))?;
+        let rows = stmt.query(&[track_ids])?;
+        let mut items: BTreeMap<Uuid, Vec<TrackFailure>> = BTreeMap::new();
+        for row in rows.iter() {
+            let failure = TrackFailure::row_to_item(row);
+            let failures = items.entry(failure.track_id).or_insert(vec![]);
+            if (failures.len() as i64) < HISTORY_SIZE {
+                failures.push(failure);
+            }
+        }
+        Ok(items)
+    }
+}
@@ -3,12 +3,13 @@ extern crate pink_spider;
 
 use std::time::Instant;
 use chrono::Duration;
-use pink_spider::model::Playlist;
+use pink_spider::model::{Playlist, conn};
 
 pub fn main() {
     println!("Start crawling playlists...");
     let now = Instant::now();
-    let mut playlists = Playlist::find_actives();
+    let conn = conn().unwrap();
+    let mut playlists = Playlist::find_actives(&*conn);
     println!("[pl_cralwer]{} playlists", playlists.len());
     let total = playlists.len();
     let mut index = 0;
@@ -19,7 +20,7 @@ pub fn main() {
                  playlist.provider,
                  playlist.id,
                  playlist.title);
-        let tracks = match playlist.fetch_tracks() {
+        let tracks = match playlist.fetch_tracks(&*conn) {
             Ok(tracks) => tracks,
             Err(e)     => {
                 println!("[pl_cralwer]{}", e);
@@ -0,0 +1,314 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use iron::prelude::*;
+use iron::method::Method;
+use iron::BeforeMiddleware;
+use error::Error;
+use auth::{CurrentApiKey, required_scope, has_force};
+use model::Scope;
+use get_env;
+
+/// The buckets of the least recently seen clients are dropped when there are more clients than this.
+static MAX_BUCKETS: usize = 10000;
+/// A bucket is refilled in a minute, so the buckets of the clients that are idle longer than this are dropped.
+static IDLE_SECS: u64 = 60;
+
+/// The kinds of requests that have separate budgets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cost {
+    /// Reading items from the database.
+    Read,
+    /// Scraping pages and creating or updating items, that call the apis of the providers.
+    Expensive,
+}
+
+/// The number of requests that a client can make in a burst, and in a minute on average.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Budget {
+    pub per_minute: u32,
+}
+
+/// A token bucket. A request takes a token, and the tokens are refilled at the rate of the budget.
+#[derive(Debug, Clone)]
+pub struct Bucket {
+    tokens:     f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    pub fn new(budget: &Budget, now: Instant) -> Bucket {
+        Bucket {
+            tokens:     budget.per_minute as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, budget: &Budget, now: Instant) {
+        if now > self.updated_at {
+            let elapsed     = now.duration_since(self.updated_at);
+            let secs        = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+            self.tokens     = (self.tokens + secs * budget.per_minute as f64 / 60.0).min(budget.per_minute as f64);
+            self.updated_at = now;
+        }
+    }
+
+    /// This function takes a token, or returns the seconds to wait for the next token.
+    pub fn take(&mut self, budget: &Budget, now: Instant) -> Result<(), u64> {
+        self.refill(budget, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - self.tokens) * 60.0 / budget.per_minute as f64).ceil() as u64)
+        }
+    }
+}
+
+/// How the clients are told apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientKey {
+    /// By the ip addresses. Every request is limited, including the ones with invalid api keys.
+    Address,
+    /// By the api keys. The requests without valid keys aren't limited.
+    ApiKey,
+}
+
+type BucketKey = (String, Cost);
+
+/// The buckets of the clients in the order that they are seen, so that the least recently seen
+/// ones can be dropped without scanning all of them.
+struct Buckets {
+    items: HashMap<BucketKey, (Bucket, Instant, u64)>,
+    order: BTreeMap<(Instant, u64), BucketKey>,
+    seq:   u64,
+}
+
+impl Buckets {
+    fn new() -> Buckets {
+        Buckets {
+            items: HashMap::new(),
+            order: BTreeMap::new(),
+            seq:   0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn take(&mut self, key: BucketKey, budget: &Budget, now: Instant) -> Result<(), u64> {
+        let mut bucket = match self.items.remove(&key) {
+            Some((bucket, seen_at, seq)) => {
+                self.order.remove(&(seen_at, seq));
+                bucket
+            },
+            None => Bucket::new(budget, now),
+        };
+        let result = bucket.take(budget, now);
+        self.seq += 1;
+        self.order.insert((now, self.seq), key.clone());
+        self.items.insert(key, (bucket, now, self.seq));
+        result
+    }
+
+    /// This function drops the idle buckets, and the least recently seen ones over `max`.
+    fn evict(&mut self, max: usize, now: Instant) {
+        let idle = Duration::from_secs(IDLE_SECS);
+        loop {
+            let oldest = match self.order.keys().next() {
+                Some(&(seen_at, seq)) => (seen_at, seq),
+                None                  => return,
+            };
+            let is_idle = now > oldest.0 && now.duration_since(oldest.0) >= idle;
+            if !is_idle && self.order.len() <= max {
+                return;
+            }
+            if let Some(key) = self.order.remove(&oldest) {
+                self.items.remove(&key);
+            }
+        }
+    }
+}
+
+/// A middleware that limits the requests of each client with token buckets.
+///
+/// It's linked twice. The one by `ClientKey::Address` is linked before `Authenticator`,
+/// so that the requests with invalid api keys are limited before the keys are looked up.
+/// The one by `ClientKey::ApiKey` is linked after it, and limits each key across the addresses.
+/// Expensive requests have a separate, smaller budget,
+/// so that a client can't use up the quotas of the providers with them.
+/// A budget of 0 requests per minute doesn't limit the requests.
+pub struct RateLimiter {
+    pub client_key:    ClientKey,
+    pub read:          Budget,
+    pub expensive:     Budget,
+    /// Whether the address of the client is taken from `X-Forwarded-For`, e.g. behind the router of heroku.
+    pub trust_forward: bool,
+    max_buckets:       usize,
+    buckets:           Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(client_key: ClientKey, read: Budget, expensive: Budget, trust_forward: bool) -> RateLimiter {
+        RateLimiter {
+            client_key:    client_key,
+            read:          read,
+            expensive:     expensive,
+            trust_forward: trust_forward,
+            max_buckets:   MAX_BUCKETS,
+            buckets:       Mutex::new(Buckets::new()),
+        }
+    }
+
+    /// `READ_RATE_LIMIT` and `EXPENSIVE_RATE_LIMIT` are the requests per minute,
+    /// and `TRUST_FORWARDED_FOR=true` takes the addresses of the clients from `X-Forwarded-For`.
+    pub fn from_env(client_key: ClientKey) -> RateLimiter {
+        let budget = |key: &str, default: u32| Budget {
+            per_minute: get_env::var(key)
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default),
+        };
+        let trust_forward = get_env::var("TRUST_FORWARDED_FOR")
+            .map(|v| v.trim() == "true")
+            .unwrap_or(false);
+        RateLimiter::new(client_key,
+                         budget("READ_RATE_LIMIT", 300),
+                         budget("EXPENSIVE_RATE_LIMIT", 10),
+                         trust_forward)
+    }
+
+    fn budget(&self, cost: Cost) -> Budget {
+        match cost {
+            Cost::Read      => self.read,
+            Cost::Expensive => self.expensive,
+        }
+    }
+
+    /// This function takes a token of `client` for `cost`, or returns the seconds to wait.
+    pub fn take(&self, client: &str, cost: Cost, now: Instant) -> Result<(), u64> {
+        let budget = self.budget(cost);
+        if budget.per_minute == 0 {
+            return Ok(());
+        }
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let result = buckets.take((client.to_string(), cost), &budget, now);
+        buckets.evict(self.max_buckets, now);
+        result
+    }
+
+    fn client(&self, req: &Request) -> Option<String> {
+        match self.client_key {
+            ClientKey::Address => Some(self.address(req)),
+            ClientKey::ApiKey  => req.extensions.get::<CurrentApiKey>().map(|api_key| format!("key:{}", api_key.id)),
+        }
+    }
+
+    fn address(&self, req: &Request) -> String {
+        if self.trust_forward {
+            let forwarded = req.headers.get_raw("X-Forwarded-For")
+                .and_then(|values| values.last())
+                .and_then(|value| String::from_utf8(value.clone()).ok())
+                .and_then(|value| value.rsplit(',').next().map(|v| v.trim().to_string()));
+            if let Some(ip) = forwarded {
+                return format!("ip:{}", ip);
+            }
+        }
+        format!("ip:{}", req.remote_addr.ip())
+    }
+}
+
+impl BeforeMiddleware for RateLimiter {
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        let cost = {
+            let path  = req.url.path();
+            let force = req.url.query().map(|q| has_force(q)).unwrap_or(false);
+            match request_cost(&req.method, &path, force) {
+                Some(cost) => cost,
+                None       => return Ok(()),
+            }
+        };
+        let client = match self.client(req) {
+            Some(client) => client,
+            None         => return Ok(()),
+        };
+        self.take(&client, cost, Instant::now())
+            .map_err(|secs| IronError::from(Error::TooManyRequests(secs)))
+    }
+}
+
+/// This function tells the cost of a request, or None if the request isn't limited.
+/// `playlistify` and `resolve` scrape the pages of unseen urls, so they are expensive even without `force`.
+pub fn request_cost(method: &Method, path: &[&str], force: bool) -> Option<Cost> {
+    let last = path.last().map(|p| *p).unwrap_or("");
+    match required_scope(method, path, force) {
+        None                                                  => None,
+        Some(_) if last == "playlistify" || last == "resolve" => Some(Cost::Expensive),
+        Some(Scope::Read)                                     => Some(Cost::Read),
+        Some(_)                                               => Some(Cost::Expensive),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bucket() {
+        let budget     = Budget { per_minute: 60 };
+        let now        = Instant::now();
+        let mut bucket = Bucket::new(&budget, now);
+        for _ in 0..60 {
+            assert_eq!(bucket.take(&budget, now), Ok(()));
+        }
+        assert_eq!(bucket.take(&budget, now), Err(1));
+        assert_eq!(bucket.take(&budget, now + Duration::from_millis(500)), Err(1));
+        assert_eq!(bucket.take(&budget, now + Duration::from_secs(1)), Ok(()));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(ClientKey::Address, Budget { per_minute: 2 }, Budget { per_minute: 1 }, false);
+        let now     = Instant::now();
+        assert_eq!(limiter.take("ip:127.0.0.1", Cost::Expensive, now), Ok(()));
+        assert_eq!(limiter.take("ip:127.0.0.1", Cost::Expensive, now), Err(60));
+        assert_eq!(limiter.take("ip:127.0.0.1", Cost::Read, now), Ok(()));
+        assert_eq!(limiter.take("ip:127.0.0.2", Cost::Expensive, now), Ok(()));
+
+        let limiter = RateLimiter::new(ClientKey::Address, Budget { per_minute: 0 }, Budget { per_minute: 0 }, false);
+        for _ in 0..10 {
+            assert_eq!(limiter.take("ip:127.0.0.1", Cost::Expensive, now), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_evict() {
+        let mut limiter = RateLimiter::new(ClientKey::Address, Budget { per_minute: 1 }, Budget { per_minute: 1 }, false);
+        limiter.max_buckets = 2;
+        let now = Instant::now();
+        assert_eq!(limiter.take("ip:127.0.0.1", Cost::Read, now), Ok(()));
+        assert_eq!(limiter.take("ip:127.0.0.2", Cost::Read, now + Duration::from_secs(1)), Ok(()));
+        assert!(limiter.take("ip:127.0.0.1", Cost::Read, now + Duration::from_secs(2)).is_err());
+        assert_eq!(limiter.take("ip:127.0.0.3", Cost::Read, now + Duration::from_secs(3)), Ok(()));
+        // 127.0.0.2 is the least recently seen, so it's dropped over the limit.
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+        assert!(limiter.take("ip:127.0.0.1", Cost::Read, now + Duration::from_secs(4)).is_err());
+
+        // All the buckets are idle, so they are dropped.
+        assert_eq!(limiter.take("ip:127.0.0.4", Cost::Read, now + Duration::from_secs(100)), Ok(()));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_request_cost() {
+        assert_eq!(request_cost(&Method::Get, &["web", "index.html"], false), None);
+        assert_eq!(request_cost(&Method::Get, &["v1", "tracks"], false), Some(Cost::Read));
+        assert_eq!(request_cost(&Method::Post, &["v1", "tracks", ".mget"], false), Some(Cost::Read));
+        assert_eq!(request_cost(&Method::Get, &["v1", "playlistify"], false), Some(Cost::Expensive));
+        assert_eq!(request_cost(&Method::Get, &["v1", "resolve"], false), Some(Cost::Expensive));
+        assert_eq!(request_cost(&Method::Post, &["v1", "feeds"], false), Some(Cost::Expensive));
+    }
+}
@@ -0,0 +1,51 @@
+use reqwest::header::{Connection, Range};
+use regex::Regex;
+use http;
+use error::{Error, ProviderError};
+use model::Provider;
+use audio_tag;
+
+/// Only the head of a file is downloaded, which is enough for the tags
+/// as long as they don't have large embedded pictures.
+static RANGE_SIZE: u64 = 256 * 1024;
+
+pub static AUDIO_FILE: &'static str = r"(?i)^https?://[^?#]+\.(mp3|m4a|aac|ogg|oga|opus|flac)([?#].*)?$";
+
+pub static AUDIO_MIME_PREFIX: &'static str = "audio/";
+
+pub fn is_audio_file_url(url: &str) -> bool {
+    Regex::new(AUDIO_FILE).map(|re| re.is_match(url)).unwrap_or(false)
+}
+
+/// This function returns the file name of a url as a fallback title.
+pub fn file_name(url: &str) -> String {
+    let path = url.split(|c| c == '?' || c == '#').next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path).to_string()
+}
+
+/// This function downloads the head of an audio file and reads its tags.
+pub fn fetch_tag(url: &str) -> Result<audio_tag::Tag, Error> {
+    let res = http::get(url)
+        .header(Connection::close())
+        .header(Range::bytes(0, RANGE_SIZE - 1))
+        .send()
+        .map_err(|e| Error::Provider(Provider::Raw, e))?;
+    http::check_status(Provider::Raw, res.status, res.retry_after)?;
+    audio_tag::parse(&res.body, res.content_length).ok_or_else(|| {
+        Error::Provider(Provider::Raw, ProviderError::Parse(format!("unsupported audio file: {}", url)))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_audio_file_url, file_name};
+
+    #[test]
+    fn test_is_audio_file_url() {
+        assert!(is_audio_file_url("http://example.com/podcast/ep01.mp3"));
+        assert!(is_audio_file_url("https://example.com/mix.M4A?dl=1"));
+        assert!(!is_audio_file_url("https://example.com/mp3/index.html"));
+        assert!(!is_audio_file_url("https://example.com/?file=ep01.mp3"));
+        assert_eq!(file_name("http://example.com/podcast/ep01.mp3?dl=1"), "ep01.mp3");
+    }
+}
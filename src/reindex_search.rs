@@ -0,0 +1,33 @@
+extern crate pink_spider;
+use pink_spider::model::{Model, Track, Entry, Artist, Page, Cursor, Query, Connection};
+use pink_spider::model::{conn};
+use pink_spider::error::Error;
+
+static PER_PAGE: i64 = 100;
+
+/// This function indexes all the items of a model, following the cursors of the pages.
+fn reindex<'a, T, F>(name: &str, conn: &Connection, index: F) where T: Model<'a>, F: Fn(&T, &Connection) -> Result<(), Error> {
+    let mut page  = Page::new(0, PER_PAGE);
+    let mut count = 0;
+    loop {
+        let items = T::find(conn, &page, &Query::new()).unwrap();
+        for item in items.items.iter() {
+            if let Err(e) = index(item, conn) {
+                print!("Failed to index a {}: {}\n", name, e);
+            }
+            count += 1;
+        }
+        print!("{} {}s are indexed\n", count, name);
+        match items.next_cursor.and_then(|c| Cursor::decode(&c).ok()) {
+            Some(cursor) => page = Page::after(cursor, PER_PAGE),
+            None         => break,
+        }
+    }
+}
+
+pub fn main() {
+    let conn = conn().unwrap();
+    reindex::<Track, _>("track", &*conn, |t, conn| t.update_search_index(conn));
+    reindex::<Entry, _>("entry", &*conn, |e, conn| e.update_search_index(conn));
+    reindex::<Artist, _>("artist", &*conn, |a, conn| a.update_search_index(conn));
+}
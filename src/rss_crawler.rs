@@ -6,22 +6,22 @@ extern crate serde_json;
 
 use std::time::Instant;
 use chrono::Duration;
-use pink_spider::model::{Model, Feed};
+use pink_spider::model::{Model, Feed, Page, Cursor, Query, conn};
 
 pub fn main() {
     println!("[rss_cralwer] Start crawling...");
     let now = Instant::now();
-    let mut page  = 0;
+    let conn      = conn().unwrap();
     let per_page  = 10;
-    let mut feeds = Feed::find(0, 0, None);
-    let total = feeds.total;
+    let mut feeds = Feed::find(&*conn, &Page::new(0, per_page), &Query::new()).unwrap();
+    let total = feeds.total.unwrap_or(0);
     let mut index = 0;
     println!("[rss_cralwer] {} feeds", total);
-    while feeds.page * feeds.per_page < total {
-        feeds = Feed::find(page, per_page, None);
+    loop {
+        let next_cursor = feeds.next_cursor.clone();
         for mut feed in feeds.items {
             println!("[rss_cralwer][{}/{}] Crawl {} ", index, total, feed.url);
-            match feed.crawl() {
+            match feed.crawl(&*conn) {
                 Ok(entries) => {
                     println!("[rss_cralwer] Found {} entries", entries.len());
                     println!("[rss_cralwer] Found {} tracks",
@@ -37,7 +37,11 @@ pub fn main() {
             }
             index += 1;
         }
-        page += 1;
+        // Crawling updates the feeds, so the offsets shift and the cursor is followed instead.
+        match next_cursor.and_then(|c| Cursor::decode(&c).ok()) {
+            Some(cursor) => feeds = Feed::find(&*conn, &Page::after(cursor, per_page), &Query::new()).unwrap(),
+            None         => break,
+        }
     }
     println!("[rss_cralwer] Complete crawling... total {} ms",
              Duration::from_std(now.elapsed()).unwrap().num_milliseconds());
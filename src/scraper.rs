@@ -9,7 +9,6 @@ use html5ever::rcdom::{RcDom, Handle};
 use html5ever::{parse_document, serialize, Attribute};
 use html5ever::tendril::stream::TendrilSink;
 use std::default::Default;
-use regex::Regex;
 use reqwest::header::{
     Connection,
     ConnectionOption,
@@ -18,17 +17,16 @@ use reqwest::header::{
 use http;
 use url::Url;
 
-use Provider;
 use Track;
 use Playlist;
 use Album;
+use model::{Model, Enclosure};
+use model::Connection as DbConnection;
 use opengraph;
-use apple_music;
-use youtube;
-use soundcloud;
-use spotify;
+use adapter;
+use adapter::{Enclosures, Identifier, ProviderAdapter};
+use raw;
 use error::Error;
-use model::Enclosure;
 use dom;
 use readability;
 use readability::Candidate;
@@ -42,9 +40,6 @@ lazy_static! {
     };
 }
 
-const EXPAND_YOUTUBE_PLAYLIST:    bool = true;
-const EXPAND_SOUNDCLOUD_PLAYLIST: bool = true;
-
 #[derive(Debug)]
 pub struct ScraperProduct {
     pub content:   String,
@@ -55,24 +50,66 @@ pub struct ScraperProduct {
     pub og_obj:    Option<opengraph::Object>,
 }
 
-pub fn scrape(url: &str) -> Result<ScraperProduct, Error> {
-    let client      = http::client();
-    let mut builder = client.get(url);
-    builder.header(Connection(vec![ConnectionOption::Close]));
+/// An enclosure that a url resolves to.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", content = "item", rename_all = "snake_case")]
+pub enum Resolved {
+    Track(Track),
+    Album(Album),
+    Playlist(Playlist),
+}
+
+/// This function resolves a url of a provider to a track, an album or a playlist
+/// without fetching the page of the url. The enclosure is created if needed.
+///
+/// The url is already decoded as a query parameter, so it isn't decoded again.
+/// The failures of the provider are returned, and gone enclosures are not found.
+pub fn resolve(conn: &DbConnection, url: &str) -> Result<Resolved, Error> {
+    let (adapter, identifier) = adapter::recognize(url).ok_or(Error::Unprocessable)?;
+    let (playlists, albums, tracks) = adapter.fetch_enclosures(conn, url, &identifier)?;
+    if let Some(mut playlist) = playlists.into_iter().next() {
+        if playlist.state.is_gone() {
+            return Err(Error::NotFound);
+        }
+        playlist.id = Playlist::find_or_create(conn, playlist.provider, playlist.identifier.to_string())?.id;
+        playlist.save(conn)?;
+        return Ok(Resolved::Playlist(playlist));
+    }
+    if let Some(mut album) = albums.into_iter().next() {
+        if album.state.is_gone() {
+            return Err(Error::NotFound);
+        }
+        album.id = Album::find_or_create(conn, album.provider, album.identifier.to_string())?.id;
+        album.save(conn)?;
+        return Ok(Resolved::Album(album));
+    }
+    if let Some(mut track) = tracks.into_iter().next() {
+        if track.state.is_gone() {
+            return Err(Error::NotFound);
+        }
+        track.id = Track::find_or_create(conn, track.provider, track.identifier.to_string())?.id;
+        track.save(conn)?;
+        return Ok(Resolved::Track(track));
+    }
+    Err(Error::NotFound)
+}
+
+pub fn scrape(conn: &DbConnection, url: &str) -> Result<ScraperProduct, Error> {
+    let mut request = http::get(url).header(Connection(vec![ConnectionOption::Close]));
     if *USER_AGENT != "" {
-        builder.header(UserAgent::new(USER_AGENT.to_string()));
+        request = request.header(UserAgent::new(USER_AGENT.to_string()));
     }
-    let mut res = builder.send()?;
-    if res.status().is_success() {
+    let res = request.send().map_err(|_| Error::BadRequest)?;
+    if res.is_success() {
         let url = Url::parse(url)?;
-        extract(&mut res, &url)
+        extract(conn, &mut &res.body[..], &url)
     } else {
-        println!("Failed to get entry html {}: {}", res.status(), url);
+        println!("Failed to get entry html {}: {}", res.status, url);
         Err(Error::NotFound)
     }
 }
 
-pub fn extract<R>(input: &mut R, url: &Url) -> Result<ScraperProduct, Error> where R: Read {
+pub fn extract<R>(conn: &DbConnection, input: &mut R, url: &Url) -> Result<ScraperProduct, Error> where R: Read {
     let mut dom = parse_document(RcDom::default(), Default::default())
         .from_utf8()
         .read_from(input)
@@ -85,7 +122,8 @@ pub fn extract<R>(input: &mut R, url: &Url) -> Result<ScraperProduct, Error> whe
     let mut og_props   = Vec::new();
     let handle = dom.document.clone();
     readability::preprocess(&mut dom, handle.clone());
-    walk(&mut dom,
+    walk(conn,
+         &mut dom,
          Path::new("/"),
          handle.clone(),
          &mut candidates,
@@ -137,7 +175,8 @@ pub fn escape_default(s: &str) -> String {
     s.chars().flat_map(|c| c.escape_default()).collect()
 }
 
-fn walk(mut dom:    &mut RcDom,
+fn walk(conn:       &DbConnection,
+        mut dom:    &mut RcDom,
         id:         &Path,
         handle:     Handle,
         candidates: &mut BTreeMap<String, Candidate>,
@@ -152,7 +191,7 @@ fn walk(mut dom:    &mut RcDom,
         Element { name: _, ref attrs, .. } => {
             let mut ps = extract_opengraph_metadata_from_tag(tag_name, &attrs.borrow());
             og_props.append(&mut ps);
-            let (ps, als, ts) = extract_enclosures_from_tag(tag_name, &attrs.borrow());
+            let (ps, als, ts) = extract_enclosures_from_tag(conn, tag_name, &attrs.borrow());
             for playlist in ps.iter().cloned() {
                 if !(playlists).iter().any(|p| playlist == *p) {
                     (*playlists).push(playlist)
@@ -192,7 +231,8 @@ fn walk(mut dom:    &mut RcDom,
     }
 
     for (i, child) in handle.children.borrow().iter().enumerate() {
-        walk(&mut dom,
+        walk(conn,
+             &mut dom,
              id.join(i.to_string()).as_path(),
              child.clone(),
              candidates,
@@ -222,16 +262,24 @@ fn find_or_create_candidate<'a>(id: &Path,
 }
 
 
-pub fn extract_enclosures_from_tag(tag_name: &str,
-                                   attrs: &Vec<Attribute>) -> (Vec<Playlist>, Vec<Album>, Vec<Track>) {
+pub fn extract_enclosures_from_tag(conn: &DbConnection,
+                                   tag_name: &str,
+                                   attrs: &Vec<Attribute>) -> Enclosures {
     if tag_name == "iframe" {
         match dom::attr("src", attrs).or(dom::attr("data-src", attrs)) {
-            Some(ref src) => extract_enclosures_from_url(src.to_string()),
+            Some(ref src) => extract_enclosures_from_url(conn, src.to_string()),
             None => (vec![], vec![], vec![])
         }
+    } else if tag_name == "audio" || tag_name == "source" {
+        match dom::attr("src", attrs) {
+            Some(ref src) if tag_name == "audio" || has_audio_type(attrs) =>
+                extract_enclosures_from_audio_src(conn, src.to_string()),
+            Some(ref src) => extract_enclosures_from_url(conn, src.to_string()),
+            None          => (vec![], vec![], vec![])
+        }
     } else if tag_name == "a" || tag_name == "link" {
         match dom::attr("href", attrs) {
-            Some(ref href) => extract_enclosures_from_url(href.to_string()),
+            Some(ref href) => extract_enclosures_from_url(conn, href.to_string()),
             None => (vec![], vec![], vec![])
         }
     } else {
@@ -270,223 +318,33 @@ fn extract_opengraph_prop<'a>(attr_name: &str, attrs: &Vec<Attribute>) -> Option
                   })
 }
 
-fn extract_identifier(value: &str, regex_str: &str) -> Option<String> {
-    match Regex::new(regex_str) {
-        Ok(re) => match re.captures(value) {
-            Some(cap) => {
-                let strs: Vec<&str> = cap[1].split('?').collect();
-                return Some(strs[0].to_string())
-            },
-            None => None
-        },
-        Err(_) => None
-    }
-}
-
-fn create_spotify_playlist(user_id: String, playlist_id: String) -> (Vec<Playlist>, Vec<Album>, Vec<Track>) {
-    let mut playlist = Playlist::new(Provider::Spotify, playlist_id);
-    let _ = playlist.set_owner_id(Some(user_id)).fetch_props();
-    (vec![playlist], vec![], vec![])
-}
-
-fn fetch_spotify_album(identifier: String) -> (Vec<Playlist>, Vec<Album>, Vec<Track>) {
-    match spotify::fetch_album(&identifier) {
-        Ok(album) => {
-            (vec![], vec![Album::from_sp_album(&album)], vec![])
-        },
-        Err(_) => (vec![], vec![Album::new(Provider::Spotify, identifier)], vec![]),
-    }
-}
-
-fn fetch_spotify_track(identifier: String) -> Vec<Track> {
-    match spotify::fetch_track(&identifier) {
-        Ok(t) => Track::from_sp_track(&t).map(|t| vec![t]).unwrap_or(vec![]),
-        Err(_) => vec![Track::new(Provider::Spotify, identifier)],
-    }
-}
-
-fn fetch_youtube_video(identifier: String) -> Vec<Track> {
-    match youtube::fetch_video(&identifier) {
-        Ok(video) => vec![Track::from_yt_video(&video)],
-        Err(_)    => vec![Track::new(Provider::YouTube, identifier).disable().clone()],
-    }
-}
-
-fn fetch_soundcloud_track(identifier: String) -> Vec<Track> {
-    match soundcloud::fetch_track(&identifier) {
-        Ok(track) => vec![Track::from_sc_track(&track)],
-        Err(_)    => vec![Track::new(Provider::SoundCloud, identifier).disable().clone()],
-    }
-}
-
-fn fetch_soundcloud_playlist(identifier: String) -> (Vec<Playlist>, Vec<Album>, Vec<Track>) {
-    match soundcloud::fetch_playlist(&identifier) {
-        Ok(playlist) => {
-            let tracks = if EXPAND_SOUNDCLOUD_PLAYLIST {
-                playlist.tracks
-                    .iter()
-                    .map(|ref t| Track::from_sc_track(t))
-                    .collect::<Vec<_>>()
-            } else {
-                vec![]
-            };
-            (vec![Playlist::from_sc_playlist(&playlist)], vec![], tracks)
-        },
-        Err(_)       => (vec![], vec![], vec![]),
+pub fn extract_enclosures_from_url(conn: &DbConnection, url: String) -> Enclosures {
+    let decoded = percent_decode(url.as_bytes()).decode_utf8_lossy().into_owned();
+    match adapter::recognize(&decoded) {
+        Some((adapter, identifier)) => fetch_enclosures(conn, adapter, &url, &identifier),
+        None                        => (vec![], vec![], vec![]),
     }
 }
 
-fn fetch_soundcloud_user(identifier: String) -> (Vec<Playlist>, Vec<Album>, Vec<Track>) {
-    match soundcloud::fetch_user_tracks(&identifier) {
-        Ok(tracks) => {
-            let tracks = tracks
-                .iter()
-                .map(|ref t| Track::from_sc_track(t))
-                .collect::<Vec<_>>();
-            (vec![], vec![], tracks)
-        },
-        Err(_)     => (vec![], vec![], vec![]),
-    }
+/// The failures of the providers don't stop scraping, and the adapters keep pending enclosures for them.
+fn fetch_enclosures(conn: &DbConnection, adapter: &ProviderAdapter, url: &str, identifier: &Identifier) -> Enclosures {
+    adapter.fetch_enclosures(conn, url, identifier)
+        .unwrap_or_else(|e| adapter.pending_enclosures(identifier, e))
 }
 
-fn fetch_youtube_playlist(id: &str) -> (Vec<Playlist>, Vec<Album>, Vec<Track>) {
-    let items = youtube::fetch_playlist_items(id)
-        .map(|res| res.items)
-        .unwrap_or(vec![]);
-    let tracks = if EXPAND_YOUTUBE_PLAYLIST {
-        items.iter()
-            .map(|ref i| Track::from_yt_playlist_item(i))
-            .collect::<Vec<_>>()
-    } else {
-        vec![]
-    };
-    let playlists = match youtube::fetch_playlist(id) {
-        Ok(res) => res.items.iter()
-            .map(|ref i| Playlist::from_yt_playlist(i, &items))
-            .collect::<Vec<_>>(),
-        Err(_)  => vec![],
-    };
-    (playlists, vec![], tracks)
+fn has_audio_type(attrs: &Vec<Attribute>) -> bool {
+    dom::attr("type", attrs).map(|t| t.starts_with(raw::AUDIO_MIME_PREFIX)).unwrap_or(false)
 }
 
-fn extract_enclosures_from_url(url: String) -> (Vec<Playlist>, Vec<Album>, Vec<Track>) {
-    let decoded = percent_decode(url.as_bytes()).decode_utf8_lossy().into_owned();
-
-    match extract_identifier(&decoded, apple_music::SONG_URL) {
-        Some(identifier) => {
-            let country = apple_music::country(&url);
-            if let Ok(song) = apple_music::fetch_song(&country, &identifier) {
-                return (vec![], vec![], vec![Track::from_am_song(&song)])
-            };
-        },
-        None => ()
-    }
-    match extract_identifier(&decoded, apple_music::ALBUM_URL) {
-        Some(identifier) => {
-            let country = apple_music::country(&url);
-            match apple_music::fetch_album(&country, &identifier) {
-                Ok(album) => return (vec![], vec![Album::from_am_album(&album)], vec![]),
-                Err(_) => (),
-            }
-            return (vec![], vec![], vec![])
-        },
-        None => ()
-    }
-    match extract_identifier(&decoded, apple_music::PLAYLIST_URL) {
-        Some(identifier) => {
-            let country = apple_music::country(&url);
-            match apple_music::fetch_playlist(&country, &identifier) {
-                Ok(playlist) => return (vec![Playlist::from_am_playlist(&playlist)], vec![], vec![]),
-                Err(_) => (),
-            }
-            return (vec![], vec![], vec![])
-        },
-        None => ()
-    }
-    match apple_music::parse_url_as_album(&decoded) {
-        Some((country, _, _, Some(song_id))) => {
-            if let Ok(song) = apple_music::fetch_song(&country, &song_id) {
-                return (vec![], vec![], vec![Track::from_am_song(&song)])
-            };
-            return (vec![], vec![], vec![])
-        },
-        Some((_, _, _, None)) => (),
-        None => ()
-    }
-    match apple_music::parse_url_as_playlist(&decoded) {
-        Some((country, _, identifier, _)) => {
-            match apple_music::fetch_playlist(&country, &identifier) {
-                Ok(playlist) => return (vec![Playlist::from_am_playlist(&playlist)], vec![], vec![]),
-                Err(_) => (),
-            }
-            return (vec![], vec![], vec![])
-        },
-        None => ()
-    }
-    match extract_identifier(&decoded, youtube::WATCH) {
-        Some(identifier) => return (vec![], vec![], fetch_youtube_video(identifier)),
-        None             => ()
-    }
-    match extract_identifier(&decoded, youtube::LIST) {
-        Some(identifier) => return fetch_youtube_playlist(&identifier),
-        None             => ()
-    }
-    match extract_identifier(&decoded, youtube::EMBED) {
-        Some(identifier) => return (vec![], vec![], fetch_youtube_video(identifier)),
-        None             => ()
-    }
-    match extract_identifier(&decoded, soundcloud::TRACK) {
-        Some(identifier) => return (vec![], vec![], fetch_soundcloud_track(identifier)),
-        None             => ()
-    }
-    match extract_identifier(&decoded, soundcloud::PLAYLIST) {
-        Some(identifier) => return fetch_soundcloud_playlist(identifier),
-        None             => ()
-    }
-    match extract_identifier(&decoded, soundcloud::USER) {
-        Some(identifier) => return fetch_soundcloud_user(identifier),
-        None             => ()
-    }
-    match extract_identifier(&decoded, spotify::TRACK_URI) {
-        Some(identifier) => return (vec![], vec![], fetch_spotify_track(identifier)),
-        None             => ()
-    }
-    match extract_identifier(&decoded, spotify::TRACK_OPEN) {
-        Some(identifier) => return (vec![], vec![], fetch_spotify_track(identifier)),
-        None             => ()
-    }
-    match extract_identifier(&decoded, spotify::TRACK_EMBED) {
-        Some(identifier) => return (vec![], vec![], fetch_spotify_track(identifier)),
-        None             => ()
-    }
-    match extract_identifier(&decoded, spotify::PLAYLIST_URI).and_then(
-        |uri| spotify::parse_uri_as_playlist(&uri)) {
-        Some((uid, pid)) => return create_spotify_playlist(uid, pid),
-        None => (),
-    }
-    match extract_identifier(&decoded, spotify::PLAYLIST_OPEN).and_then(
-        |url| spotify::parse_open_url_as_playlist(&url)) {
-        Some((uid, pid)) => return create_spotify_playlist(uid, pid),
-        None => ()
-    }
-    match extract_identifier(&decoded, spotify::PLAYLIST_EMBED).and_then(
-        |url| spotify::parse_embed_url_as_playlist(&url)) {
-        Some((uid, pid)) => return create_spotify_playlist(uid, pid),
-        None => ()
-    }
-    match extract_identifier(&decoded, spotify::ALBUM_URI) {
-        Some(identifier) => return fetch_spotify_album(identifier),
-        None             => ()
-    }
-    match extract_identifier(&decoded, spotify::ALBUM_OPEN) {
-        Some(identifier) => return fetch_spotify_album(identifier),
-        None             => ()
-    }
-    match extract_identifier(&decoded, spotify::ALBUM_EMBED) {
-        Some(identifier) => return fetch_spotify_album(identifier),
-        None             => ()
+/// The src of an audio tag is an audio file even if it has no extension,
+/// so it becomes a raw track unless a provider recognizes it.
+fn extract_enclosures_from_audio_src(conn: &DbConnection, src: String) -> Enclosures {
+    let decoded = percent_decode(src.as_bytes()).decode_utf8_lossy().into_owned();
+    match adapter::recognize(&decoded) {
+        Some((adapter, identifier)) => fetch_enclosures(conn, adapter, &src, &identifier),
+        None if Url::parse(&src).is_ok() => (vec![], vec![], vec![Track::from_raw_url(conn, &src)]),
+        None => (vec![], vec![], vec![]),
     }
-    return (vec![], vec![], vec![])
 }
 
 #[cfg(test)]
@@ -494,7 +352,8 @@ mod test {
     use std::fs::File;
     use url::Url;
     use super::extract;
-    use super::extract_identifier;
+    use model::conn;
+    use adapter::extract_identifier;
     use youtube;
     use soundcloud;
     use Provider;
@@ -530,7 +389,8 @@ mod test {
     fn test_scrape() {
         let url       = Url::parse("http://example.com/").unwrap();
         let mut f     = File::open("fixture/test.html").unwrap();
-        let product   = extract(&mut f, &url).unwrap();
+        let conn      = conn().unwrap();
+        let product   = extract(&*conn, &mut f, &url).unwrap();
         let playlists = product.playlists;
         let tracks    = product.tracks;
         assert_eq!(playlists.len(), 1);
@@ -0,0 +1,270 @@
+use std::char;
+use std::fmt;
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+use error::Error;
+use model::{Model, Connection, Track, Entry, Artist, PaginatedCollection, Page, SearchIndex, SearchHit};
+
+/// Words that are too common to tell the items from each other.
+static STOP_WORDS: [&'static str; 16] = ["a", "an", "and", "are", "as", "at", "by", "for",
+                                         "from", "in", "is", "it", "of", "on", "the", "to"];
+/// Long fields, e.g. the texts of entries, are indexed only up to this number of characters.
+static MAX_FIELD_LENGTH: usize = 10000;
+static MAX_TOKEN_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Script {
+    /// Scripts that separate words with spaces, e.g. English.
+    Word,
+    /// Scripts that don't separate words, e.g. Japanese.
+    Cjk,
+    Other,
+}
+
+/// Fullwidth alphanumerics are common in Japanese texts, so they are normalized to ASCII.
+fn normalize(c: char) -> char {
+    match c as u32 {
+        0xFF01...0xFF5E => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        0x3000          => ' ',
+        _               => c,
+    }
+}
+
+fn script(c: char) -> Script {
+    match c as u32 {
+        0x3040...0x30FF | // Hiragana and Katakana
+        0x3400...0x4DBF | // CJK Unified Ideographs Extension A
+        0x4E00...0x9FFF | // CJK Unified Ideographs
+        0xF900...0xFAFF | // CJK Compatibility Ideographs
+        0xFF66...0xFF9F | // Halfwidth Katakana
+        0xAC00...0xD7AF   // Hangul Syllables
+            => Script::Cjk,
+        _ if c.is_alphanumeric() => Script::Word,
+        _                        => Script::Other,
+    }
+}
+
+fn push_tokens(tokens: &mut Vec<String>, run: &[char], script: Script, unigrams: bool) {
+    match script {
+        Script::Word => {
+            let word: String = run.iter().collect();
+            if run.len() <= MAX_TOKEN_LENGTH && !STOP_WORDS.contains(&word.as_str()) {
+                tokens.push(word);
+            }
+        },
+        Script::Cjk if run.len() == 1 => tokens.push(run[0].to_string()),
+        Script::Cjk => {
+            for pair in run.windows(2) {
+                tokens.push(pair.iter().collect());
+            }
+            if unigrams {
+                for c in run.iter() {
+                    tokens.push(c.to_string());
+                }
+            }
+        },
+        Script::Other => (),
+    }
+}
+
+/// This function splits a text into tokens.
+///
+/// English and the other scripts with spaces are split into lowercased words.
+/// Japanese and the other scripts without spaces are split into bigrams of characters,
+/// so "東京事変" has "東京", "京事" and "事変", and a query "東京" matches it without a dictionary.
+pub fn tokenize(text: &str) -> Vec<String> {
+    split(text, false)
+}
+
+/// This function splits a text into the tokens to index.
+/// The characters of Japanese and the other scripts without spaces are also indexed one by one,
+/// so that a query of a character, e.g. "嵐", matches the longer texts that have it.
+pub fn index_tokens(text: &str) -> Vec<String> {
+    split(text, true)
+}
+
+fn split(text: &str, unigrams: bool) -> Vec<String> {
+    let mut tokens  = Vec::new();
+    let mut run     = Vec::new();
+    let mut current = Script::Other;
+    for c in text.chars().map(normalize).flat_map(|c| c.to_lowercase()) {
+        let s = script(c);
+        if s != current {
+            push_tokens(&mut tokens, &run, current, unigrams);
+            run.clear();
+            current = s;
+        }
+        if s != Script::Other {
+            run.push(c);
+        }
+    }
+    push_tokens(&mut tokens, &run, current, unigrams);
+    tokens
+}
+
+/// This function sums the weights of the tokens of the fields of an item.
+/// A token weighs the weight of its field each time it appears, so titles can weigh more than texts.
+pub fn weigh_tokens(fields: &[(Option<&str>, f64)]) -> BTreeMap<String, f64> {
+    let mut weights = BTreeMap::new();
+    for &(text, weight) in fields {
+        let text: String = text.unwrap_or("").chars().take(MAX_FIELD_LENGTH).collect();
+        for token in index_tokens(&text) {
+            *weights.entry(token).or_insert(0.0) += weight;
+        }
+    }
+    weights
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchType {
+    Track,
+    Entry,
+    Artist,
+}
+
+impl SearchType {
+    pub fn all() -> Vec<SearchType> {
+        vec![SearchType::Track, SearchType::Entry, SearchType::Artist]
+    }
+    pub fn parse(value: &str) -> Option<SearchType> {
+        match value {
+            "track"  => Some(SearchType::Track),
+            "entry"  => Some(SearchType::Entry),
+            "artist" => Some(SearchType::Artist),
+            _        => None,
+        }
+    }
+    /// This function parses a comma separated list of types, e.g. `track,entry`.
+    pub fn parse_list(value: &str) -> Result<Vec<SearchType>, Error> {
+        value.split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| SearchType::parse(v).ok_or(Error::Unprocessable))
+            .collect()
+    }
+}
+
+impl fmt::Display for SearchType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            SearchType::Track  => "track",
+            SearchType::Entry  => "entry",
+            SearchType::Artist => "artist",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum SearchItem {
+    Track(Track),
+    Entry(Entry),
+    Artist(Artist),
+}
+
+#[derive(Serialize, Debug)]
+pub struct SearchResult {
+    #[serde(rename = "type")]
+    pub item_type: String,
+    pub score:     f64,
+    pub item:      SearchItem,
+}
+
+fn find_items<'a, T, F>(conn: &Connection, hits: &[SearchHit], search_type: SearchType, id: F) -> Result<BTreeMap<Uuid, T>, Error>
+    where T: Model<'a>, F: Fn(&T) -> Uuid {
+    let item_type = search_type.to_string();
+    let ids: Vec<Uuid> = hits.iter()
+        .filter(|h| h.item_type == item_type)
+        .map(|h| h.item_id)
+        .collect();
+    if ids.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(T::mget(conn, ids)?.into_iter().map(|item| (id(&item), item)).collect())
+}
+
+/// This function searches the items of `types` that have all the tokens of `q`, ranked by their scores.
+/// The results can't be paged with cursors, so `page` is used as the offset.
+pub fn search(conn: &Connection, q: &str, types: &[SearchType], page: &Page) -> Result<PaginatedCollection<SearchResult>, Error> {
+    let tokens: Vec<String> = tokenize(q).into_iter().collect::<BTreeSet<String>>().into_iter().collect();
+    let item_types          = types.iter().map(|t| t.to_string()).collect();
+    let offset              = page.page * page.per_page;
+    let hits                = SearchIndex::search(conn, &tokens, &item_types, page.per_page, offset)?;
+    let mut tracks   = find_items(conn, &hits, SearchType::Track, |t: &Track| t.id)?;
+    let mut entries  = find_items(conn, &hits, SearchType::Entry, |e: &Entry| e.id)?;
+    let mut artists  = find_items(conn, &hits, SearchType::Artist, |a: &Artist| a.id)?;
+    let items = hits.into_iter().filter_map(|hit| {
+        let item = match SearchType::parse(&hit.item_type) {
+            Some(SearchType::Track)  => tracks.remove(&hit.item_id).map(SearchItem::Track),
+            Some(SearchType::Entry)  => entries.remove(&hit.item_id).map(SearchItem::Entry),
+            Some(SearchType::Artist) => artists.remove(&hit.item_id).map(SearchItem::Artist),
+            None                     => None,
+        };
+        item.map(|item| SearchResult {
+            item_type: hit.item_type,
+            score:     hit.score,
+            item:      item,
+        })
+    }).collect();
+    Ok(PaginatedCollection {
+        page:        page.page,
+        per_page:    page.per_page,
+        total:       None,
+        cursor:      None,
+        next_cursor: None,
+        items:       items,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use model::{Model, Enclosure, conn};
+    use Provider;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("The Beatles - Let It Be"), vec!["beatles", "let", "be"]);
+        assert_eq!(tokenize("東京事変"), vec!["東京", "京事", "事変"]);
+        assert_eq!(tokenize("椎名林檎 feat. ＭＩＹＡＶＩ"), vec!["椎名", "名林", "林檎", "feat", "miyavi"]);
+        assert_eq!(tokenize("愛"), vec!["愛"]);
+        assert_eq!(tokenize("  "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_index_tokens() {
+        assert_eq!(index_tokens("東京事変"), vec!["東京", "京事", "事変", "東", "京", "事", "変"]);
+        assert_eq!(index_tokens("愛 Love"), vec!["愛", "love"]);
+    }
+
+    #[test]
+    fn test_weigh_tokens() {
+        let weights = weigh_tokens(&[(Some("Rock and Roll"), 3.0), (None, 2.0), (Some("rock"), 1.0)]);
+        assert_eq!(weights.get("rock"), Some(&4.0));
+        assert_eq!(weights.get("roll"), Some(&3.0));
+        assert_eq!(weights.get("and"), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let conn       = conn().unwrap();
+        let mut artist = Artist::find_or_create(&*conn, Provider::Spotify, "test_search".to_string()).unwrap();
+        artist.name    = "嵐の夜 test_search".to_string();
+        artist.save(&*conn).unwrap();
+        let page = Page::new(0, 100);
+        for q in ["嵐", "の夜", "test_search"].iter() {
+            let results = search(&*conn, q, &[SearchType::Artist], &page).unwrap();
+            assert!(results.items.iter().any(|r| match r.item {
+                SearchItem::Artist(ref a) => a.id == artist.id,
+                _                         => false,
+            }), "{} doesn't match", q);
+        }
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(SearchType::parse_list("track, entry").unwrap(), vec![SearchType::Track, SearchType::Entry]);
+        assert!(SearchType::parse_list("track,album").is_err());
+    }
+}
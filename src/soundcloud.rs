@@ -1,8 +1,9 @@
-use std::io::Read;
 use reqwest::header::Connection;
-use serde_json;
+use serde::de::DeserializeOwned;
 use get_env;
 use http;
+use error::Error;
+use model::{Provider, State};
 
 static BASE_URL: &'static str = "https://api.soundcloud.com";
 
@@ -44,6 +45,44 @@ pub struct Track {
     pub artwork_url:   Option<String>,
     pub duration:      i32,
     pub stream_url:    String,
+    pub genre:         Option<String>,
+    pub tag_list:      Option<String>,
+    pub sharing:       Option<String>,
+    pub streamable:    Option<bool>,
+}
+
+impl Track {
+    pub fn genre_names(&self) -> Vec<String> {
+        self.genre.clone().into_iter().filter(|g| !g.trim().is_empty()).collect()
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.tag_list.clone().map(|t| parse_tag_list(&t)).unwrap_or(vec![])
+    }
+
+    pub fn state(&self) -> State {
+        if self.sharing.as_ref().map(|s| s == "private").unwrap_or(false) {
+            return State::Private;
+        }
+        match self.streamable {
+            Some(false) => State::Unavailable,
+            _           => State::Alive,
+        }
+    }
+}
+
+/// tag_list is separated by spaces, and a tag that has spaces is quoted,
+/// e.g. `house "deep house" techno`.
+pub fn parse_tag_list(tag_list: &str) -> Vec<String> {
+    tag_list.split('"')
+        .enumerate()
+        .flat_map(|(i, s)| if i % 2 == 1 {
+            vec![s.trim().to_string()]
+        } else {
+            s.split_whitespace().map(|t| t.to_string()).collect()
+        })
+        .filter(|t| !t.is_empty())
+        .collect()
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -56,37 +95,39 @@ pub struct User {
     pub avatar_url:    String,
 }
 
-pub fn fetch_track(id: &str) -> serde_json::Result<Track> {
+pub fn fetch_track(id: &str) -> Result<Track, Error> {
     let params = format!("client_id={}", *API_KEY);
     let url    = format!("{}/tracks/{}?{}", BASE_URL, id, params);
-    let mut res = http::client().get(&url)
-                                .header(Connection::close())
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    return serde_json::from_str(&body)
+    fetch(&url)
 }
 
-pub fn fetch_playlist(id: &str) -> serde_json::Result<Playlist> {
+pub fn fetch_playlist(id: &str) -> Result<Playlist, Error> {
     let params = format!("client_id={}", *API_KEY);
     let url    = format!("{}/playlists/{}?{}", BASE_URL, id, params);
-    let mut res = http::client().get(&url)
-                                .header(Connection::close())
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    return serde_json::from_str(&body)
+    fetch(&url)
 }
 
 
-pub fn fetch_user_tracks(id: &str) -> serde_json::Result<Vec<Track>> {
+pub fn fetch_user_tracks(id: &str) -> Result<Vec<Track>, Error> {
     let params = format!("client_id={}", *API_KEY);
     let url    = format!("{}/users/{}/tracks?{}", BASE_URL, id, params);
-    let mut res = http::client().get(&url)
-                                .header(Connection::close())
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    return  serde_json::from_str(&body)
+    fetch(&url)
+}
+
+fn fetch<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
+    http::get(url)
+        .header(Connection::close())
+        .fetch_json(Provider::SoundCloud)
 }
 
+#[cfg(test)]
+mod test {
+    use super::parse_tag_list;
+
+    #[test]
+    fn test_parse_tag_list() {
+        assert_eq!(parse_tag_list("house \"deep house\" techno"),
+                   vec!["house", "deep house", "techno"]);
+        assert_eq!(parse_tag_list(""), Vec::<String>::new());
+    }
+}
@@ -1,4 +1,3 @@
-use std::io::Read;
 use std::collections::BTreeMap;
 use std::sync::Mutex;
 use std::cmp::min;
@@ -12,12 +11,12 @@ use reqwest::header::{
     ContentType
 };
 use regex::Regex;
-use serde_json;
 use serde::{Deserialize, Deserializer};
-use serde::de::Error;
 use serde::de::DeserializeOwned;
 use get_env;
 use http;
+use error::{Error, ProviderError};
+use model::{Provider, State};
 
 static BASE_URL:           &'static str = "https://api.spotify.com/v1";
 pub static TRACK_URI:      &'static str = r"spotify:track:([a-zA-Z0-9_-]+)";
@@ -69,6 +68,17 @@ pub struct Track {
     pub uri:               String,
 }
 
+impl Track {
+    /// The requests don't have `market`, so `is_playable` isn't returned
+    /// and a track that can't be played anywhere has no markets.
+    pub fn state(&self) -> State {
+        if self.available_markets.is_empty() {
+            return State::Unavailable;
+        }
+        State::Alive
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Tracks {
     tracks: Vec<Track>,
@@ -120,6 +130,16 @@ pub struct Album {
     #[serde(default, deserialize_with = "nullable_string")]
     pub uri:               String,
     pub tracks:            Option<PagingObject<Track>>,
+    pub genres:            Option<Vec<String>>,
+}
+
+impl Album {
+    pub fn state(&self) -> State {
+        if self.available_markets.is_empty() {
+            return State::Unavailable;
+        }
+        State::Alive
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -134,6 +154,7 @@ pub struct Artist {
     #[serde(default, deserialize_with = "nullable_string")]
     pub uri:           String,
     pub images:        Option<Vec<Image>>,
+    pub genres:        Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -183,18 +204,18 @@ pub struct PagingObject<T> {
 }
 
 impl<T: DeserializeOwned> PagingObject<T> {
-    pub fn fetch_next(&self) -> serde_json::Result<PagingObject<T>> {
+    pub fn fetch_next(&self) -> Result<PagingObject<T>, Error> {
         if let Some(ref path) = self.next {
-            fetch_url(&path).and_then(|s| serde_json::from_str(&s))
+            fetch_url(&path)
         } else {
-            Err(serde_json::Error::missing_field("no value"))
+            Err(Error::NotFound)
         }
     }
-    pub fn fetch_previous(&self) -> serde_json::Result<PagingObject<T>> {
+    pub fn fetch_previous(&self) -> Result<PagingObject<T>, Error> {
         if let Some(ref path) = self.next {
-            fetch_url(&path).and_then(|s| serde_json::from_str(&s))
+            fetch_url(&path)
         } else {
-            Err(serde_json::Error::missing_field("no value"))
+            Err(Error::NotFound)
         }
     }
 }
@@ -235,17 +256,17 @@ pub fn parse_embed_url_as_playlist(url: &str) -> Option<(String, String)> {
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// let track = pink_spider::spotify::fetch_track("3n3Ppam7vgaVa1iaRUc9Lp").unwrap();
 ///
 /// assert_eq!(track.id.unwrap(), "3n3Ppam7vgaVa1iaRUc9Lp");
 /// ```
-pub fn fetch_track(id: &str) -> serde_json::Result<Track> {
+pub fn fetch_track(id: &str) -> Result<Track, Error> {
     let path = format!("/tracks/{}", id);
-    fetch(&path).and_then(|s| serde_json::from_str(&s))
+    fetch(&path)
 }
 
-pub fn fetch_tracks(ids: Vec<String>) -> serde_json::Result<Vec<Track>> {
+pub fn fetch_tracks(ids: Vec<String>) -> Result<Vec<Track>, Error> {
     let mut track_ids = ids;
     let mut tracks: Vec<Track> = vec![];
     loop {
@@ -261,66 +282,62 @@ pub fn fetch_tracks(ids: Vec<String>) -> serde_json::Result<Vec<Track>> {
     Ok(tracks)
 }
 
-pub fn fetch_tracks_by_ids(ids: Vec<String>) -> serde_json::Result<Vec<Track>> {
+pub fn fetch_tracks_by_ids(ids: Vec<String>) -> Result<Vec<Track>, Error> {
     let path = format!("/tracks?ids={}", ids.join(","));
-    let result: serde_json::Result<Tracks> = fetch(&path).and_then(|s| serde_json::from_str(&s));
-    result.map(|tracks| tracks.tracks)
+    fetch::<Tracks>(&path).map(|tracks| tracks.tracks)
 }
 
-pub fn fetch_playlist_tracks(user_id: &str, id: &str) -> serde_json::Result<PagingObject<PlaylistTrack>> {
+pub fn fetch_playlist_tracks(user_id: &str, id: &str) -> Result<PagingObject<PlaylistTrack>, Error> {
     let path = format!("/users/{}/playlists/{}/tracks", user_id, id);
-    fetch(&path).and_then(|s| serde_json::from_str(&s))
+    fetch(&path)
 }
 
 /// This function fetches a album info with spotify api.
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// let album = pink_spider::spotify::fetch_album("7exbVQgdNqseHtGCf6mZk5").unwrap();
 ///
 /// assert_eq!(album.id, "7exbVQgdNqseHtGCf6mZk5");
 /// ```
-pub fn fetch_album(id: &str) -> serde_json::Result<Album> {
+pub fn fetch_album(id: &str) -> Result<Album, Error> {
     let path = format!("/albums/{}", id);
-    fetch(&path).and_then(|s| serde_json::from_str(&s))
+    fetch(&path)
 }
 
 /// This function fetches a playlist info with spotify api.
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// let playlist = pink_spider::spotify::fetch_playlist("spincoaster", "182jSXyIDGLOYwE7PLhxjI").unwrap();
 ///
 /// assert_eq!(playlist.id, "182jSXyIDGLOYwE7PLhxjI");
 ///
 /// assert_eq!(playlist.tracks.total, 101);
 /// ```
-pub fn fetch_playlist(user_id: &str, id: &str) -> serde_json::Result<Playlist> {
+pub fn fetch_playlist(user_id: &str, id: &str) -> Result<Playlist, Error> {
     let path = format!("/users/{}/playlists/{}", user_id, id);
-    fetch(&path).and_then(|s| serde_json::from_str(&s))
+    fetch(&path)
 }
 
-pub fn fetch_artist(id: &str) -> serde_json::Result<Artist> {
+pub fn fetch_artist(id: &str) -> Result<Artist, Error> {
     let path = format!("/artists/{}", id);
-    fetch(&path).and_then(|s| serde_json::from_str(&s))
+    fetch(&path)
 }
 
-pub fn fetch_artists(ids: Vec<String>) -> serde_json::Result<Vec<Artist>> {
+pub fn fetch_artists(ids: Vec<String>) -> Result<Vec<Artist>, Error> {
     let path = format!("/artists?ids={}", ids.join(","));
-    let result: serde_json::Result<Artists> = fetch(&path).and_then(|s| {
-        serde_json::from_str(&s)
-    });
-    result.map(|artists| artists.artists)
+    fetch::<Artists>(&path).map(|artists| artists.artists)
 }
 
-fn fetch(path: &str) -> serde_json::Result<String> {
+fn fetch<T: DeserializeOwned>(path: &str) -> Result<T, Error> {
     let url = format!("{}{}", BASE_URL, path);
     fetch_url(&url)
 }
 
-fn fetch_url(url: &str) -> serde_json::Result<String> {
+fn fetch_url<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
     let token       = update_token_if_needed()?;
     let mut headers = Headers::new();
     headers.set(
@@ -331,23 +348,20 @@ fn fetch_url(url: &str) -> serde_json::Result<String> {
         )
     );
     headers.set(Connection::close());
-    let mut res = http::client().get(url)
-                                .headers(headers)
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    Ok(body)
+    http::get(url)
+        .headers(headers)
+        .fetch_json(Provider::Spotify)
 }
 
 /// This function fetches a oauth token info with spotify api.
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// let token = pink_spider::spotify::fetch_token();
 /// assert!(token.is_ok());
 /// ```
-pub fn fetch_token() -> serde_json::Result<Token> {
+pub fn fetch_token() -> Result<Token, Error> {
     let url         = "https://accounts.spotify.com/api/token";
     let mut headers = Headers::new();
     headers.set(
@@ -360,13 +374,15 @@ pub fn fetch_token() -> serde_json::Result<Token> {
     );
     headers.set(ContentType("application/x-www-form-urlencoded".parse().unwrap()));
     headers.set(Connection::close());
-    let mut res = http::client().post(url)
-                                .body("grant_type=client_credentials")
-                                .headers(headers)
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    serde_json::from_str::<Token>(&body)
+    // spotify responds invalid credentials with 400 invalid_client
+    http::post(url)
+        .body("grant_type=client_credentials")
+        .headers(headers)
+        .fetch_json(Provider::Spotify)
+        .map_err(|e| match e {
+            Error::Provider(p, ProviderError::Status(400)) => Error::Provider(p, ProviderError::Unauthorized),
+            e => e,
+        })
 }
 
 pub fn get_valid_token() -> Option<Token> {
@@ -384,7 +400,7 @@ pub fn get_valid_token() -> Option<Token> {
     )
 }
 
-pub fn update_token_if_needed() -> serde_json::Result<Token> {
+pub fn update_token_if_needed() -> Result<Token, Error> {
     match get_valid_token() {
         Some(token) => Ok(token),
         None => match fetch_token() {
@@ -397,8 +413,7 @@ pub fn update_token_if_needed() -> serde_json::Result<Token> {
                 *t = Some(token.clone());
                 Ok(token)
             },
-            Err(_) =>
-                Err(serde_json::error::Error::custom("missing token".to_string()))
+            Err(e) => Err(e),
         },
     }
 }
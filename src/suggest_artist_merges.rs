@@ -0,0 +1,55 @@
+extern crate pink_spider;
+
+use std::env;
+use std::collections::BTreeMap;
+use pink_spider::matcher;
+use pink_spider::model::{Model, Connection, Artist, CanonicalArtist, conn};
+
+/// The pairs whose names are similar at least this are suggested.
+/// The pairs are merged with --merge only if their names are the same without stripping the suffixes,
+/// because a suffix can be a part of a name, e.g. "Roxy Music" and "Roxy".
+const SUGGESTION_THRESHOLD: f64 = 0.85;
+
+pub fn main() {
+    let merge   = env::args().any(|arg| arg == "--merge");
+    let conn    = conn().unwrap();
+    let artists = Artist::find_all(&*conn);
+    println!("len {}\n", artists.len());
+    let mut buckets: BTreeMap<char, Vec<&Artist>> = BTreeMap::new();
+    for artist in artists.iter() {
+        if let Some(c) = matcher::artist_name_key(&artist.name).chars().next() {
+            buckets.entry(c).or_insert(vec![]).push(artist);
+        }
+    }
+    for bucket in buckets.values() {
+        for (i, a) in bucket.iter().enumerate() {
+            for b in bucket[i + 1..].iter() {
+                if a.provider == b.provider {
+                    continue;
+                }
+                if a.canonical_artist_id.is_some() && a.canonical_artist_id == b.canonical_artist_id {
+                    continue;
+                }
+                let score = matcher::artist_name_similarity(&a.name, &b.name);
+                if score < SUGGESTION_THRESHOLD {
+                    continue;
+                }
+                println!("{:.2} {} {}:{:?} <-> {} {}:{:?}",
+                         score, a.id, a.provider, a.name, b.id, b.provider, b.name);
+                if merge && matcher::is_same_artist_name(&a.name, &b.name) {
+                    merge_artists(&*conn, a, b);
+                }
+            }
+        }
+    }
+}
+
+fn merge_artists(conn: &Connection, a: &Artist, b: &Artist) {
+    // canonical artist ids might be changed by the previous merges
+    let result = Artist::mget(conn, vec![a.id, b.id])
+        .and_then(|artists| CanonicalArtist::merge(conn, &artists));
+    match result {
+        Ok(canonical_artist) => println!("  merged into {} {:?}", canonical_artist.id, canonical_artist.name),
+        Err(e)               => println!("  failed to merge: {:?}", e),
+    }
+}
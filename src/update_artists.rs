@@ -6,51 +6,55 @@ use pink_spider::model::{Track, Album, Artist, Provider};
 use pink_spider::model::Enclosure;
 use pink_spider::model::State;
 use pink_spider::model::Model;
+use pink_spider::model::{Connection, conn};
 
 pub fn main() {
-    let mut tracks = Track::find_by_provider(&Provider::AppleMusic);
+    let conn = conn().unwrap();
+    let mut tracks = Track::find_by_provider(&*conn, &Provider::AppleMusic);
     for track in tracks.iter_mut().filter(|a| a.state == State::Alive) {
         println!("update {:?} {:?}", track.identifier, track.title);
         match apple_music::fetch_song(&country(&track.url),
                                       &track.identifier) {
-            Ok(am_song) => track.update_with_am_song(&am_song),
+            Ok(am_song) => { track.update_with_am_song(&*conn, &am_song); },
             Err(e)      => {
                 println!("{:?}", e);
-                track.disable()
+                if e.is_not_found() {
+                    track.disable();
+                }
             },
         };
-        let _ = track.save();
+        let _ = track.save(&*conn);
     }
 
-    update_artists()
+    update_artists(&*conn)
 }
 
-pub fn update_artists() {
-    let artists = Artist::find_all();
+pub fn update_artists(conn: &Connection) {
+    let artists = Artist::find_all(conn);
     println!("len {}\n", artists.len());
     for mut artist in artists {
         println!("{:?} {:?} {:?}", artist.identifier, artist.provider, artist.name);
         match artist.provider {
-            Provider::AppleMusic => update_apple_music(&mut artist),
-            Provider::Spotify => update_artist(&mut artist),
-            Provider::YouTube => update_artist(&mut artist),
-            Provider::SoundCloud => update_artist(&mut artist),
+            Provider::AppleMusic => update_apple_music(conn, &mut artist),
+            Provider::Spotify => update_artist(conn, &mut artist),
+            Provider::YouTube => update_artist(conn, &mut artist),
+            Provider::SoundCloud => update_artist(conn, &mut artist),
             _ => (),
         }
     }
 }
 
-pub fn update_artist(artist: &mut Artist) {
-    let _ = artist.fetch_props();
+pub fn update_artist(conn: &Connection, artist: &mut Artist) {
+    let _ = artist.fetch_props(conn);
 }
 
-pub fn update_apple_music(artist: &mut Artist) {
-    let country = country_of_am_artist(artist);
+pub fn update_apple_music(conn: &Connection, artist: &mut Artist) {
+    let country = country_of_am_artist(conn, artist);
     match apple_music::search_artists(&country, &artist.name) {
         Ok(items) => {
             println!("  {:?} {:?} {}", artist.identifier, country, items.len());
             if items.len() == 1 {
-                update_apple_music_artist(artist, &country, &items[0].id);
+                update_apple_music_artist(conn, artist, &country, &items[0].id);
                 //                    println!("Updated {:?}", artist);
                 return
             }
@@ -58,7 +62,7 @@ pub fn update_apple_music(artist: &mut Artist) {
                 item.attributes.name.to_lowercase() == artist.name.to_lowercase()
             }).collect::<Vec<&apple_music::Artist>>();
             if items.len() > 0 {
-                update_apple_music_artist(artist, &country, &items[0].id);
+                update_apple_music_artist(conn, artist, &country, &items[0].id);
                 println!("  Updated {:?}", artist.identifier);
                 return
             }
@@ -68,10 +72,10 @@ pub fn update_apple_music(artist: &mut Artist) {
     }
 }
 
-pub fn update_apple_music_artist(artist: &mut Artist, country: &str, id: &str) {
+pub fn update_apple_music_artist(conn: &Connection, artist: &mut Artist, country: &str, id: &str) {
     artist.identifier = id.to_string();
     match apple_music::fetch_artist(country, id) {
-        Ok(item) => artist.update_with_am_artist(&item),
+        Ok(item) => artist.update_with_am_artist(conn, &item),
         Err(e)   => {
             println!("{:?}", e);
             artist
@@ -79,9 +83,9 @@ pub fn update_apple_music_artist(artist: &mut Artist, country: &str, id: &str) {
     };
 }
 
-pub fn country_of_am_artist(artist: &Artist) -> String {
-    let tracks = Track::find_by_artist(artist.id);
-    let albums = Album::find_by_artist(artist.id);
+pub fn country_of_am_artist(conn: &Connection, artist: &Artist) -> String {
+    let tracks = Track::find_by_artist(conn, artist.id);
+    let albums = Album::find_by_artist(conn, artist.id);
     if tracks.len() > 0 {
         return apple_music::country(&tracks[0].url);
     }
@@ -1,14 +1,15 @@
-use std::io::Read;
 use reqwest::header::Connection;
 use std::collections::BTreeMap;
-use serde_json;
-use serde::de::Error;
+use serde::de::DeserializeOwned;
 
 use get_env;
 use http;
+use error::{Error, ProviderError};
+use model::{Provider, State};
 
 static BASE_URL:    &'static str = "https://www.googleapis.com/youtube/v3";
 static MAX_RESULTS: i32          = 50;
+static MUSIC_CATEGORY: &'static str = "Music";
 lazy_static! {
     static ref API_KEY: String = {
         get_env::var("YOUTUBE_API_KEY").unwrap_or("".to_string())
@@ -18,6 +19,7 @@ lazy_static! {
 pub static EMBED: &'static str = r"www.youtube.com/embed/([a-zA-Z0-9_-].+)";
 pub static LIST:  &'static str = r"www.youtube.com/embed/videoseries\?list=([a-zA-Z0-9_-]+)";
 pub static WATCH: &'static str = r"www.youtube.com/watch\?v=([a-zA-Z0-9_-]+)";
+pub static SHORT: &'static str = r"youtu.be/([a-zA-Z0-9_-]+)";
 
 pub trait HasThumbnail {
     fn get_thumbnails(&self) -> BTreeMap<String, Thumbnail>;
@@ -128,10 +130,12 @@ pub struct VideoResponse {
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Video {
-    pub kind:    String,
-    pub etag:    String,
-    pub id:      String,
-    pub snippet: VideoSnippet,
+    pub kind:           String,
+    pub etag:           String,
+    pub id:             String,
+    pub snippet:        VideoSnippet,
+    pub status:         Option<VideoStatus>,
+    pub contentDetails: Option<VideoContentDetails>,
 }
 
 #[allow(non_snake_case)]
@@ -148,6 +152,26 @@ pub struct VideoSnippet {
     pub liveBroadcastContent: String,
 }
 
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VideoStatus {
+    pub uploadStatus:  String,
+    pub privacyStatus: String,
+    pub embeddable:    Option<bool>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VideoContentDetails {
+    pub regionRestriction: Option<RegionRestriction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegionRestriction {
+    pub allowed: Option<Vec<String>>,
+    pub blocked: Option<Vec<String>>,
+}
+
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ChannelResponse {
@@ -193,66 +217,147 @@ impl HasThumbnail for VideoSnippet {
     }
 }
 
+impl VideoSnippet {
+    /// YouTube returns only the id of a category,
+    /// so the names of the standard categories are mapped here.
+    pub fn category_name(&self) -> Option<String> {
+        let name = match self.categoryId.as_ref() {
+            "1"  => "Film & Animation",
+            "2"  => "Autos & Vehicles",
+            "10" => MUSIC_CATEGORY,
+            "15" => "Pets & Animals",
+            "17" => "Sports",
+            "19" => "Travel & Events",
+            "20" => "Gaming",
+            "22" => "People & Blogs",
+            "23" => "Comedy",
+            "24" => "Entertainment",
+            "25" => "News & Politics",
+            "26" => "Howto & Style",
+            "27" => "Education",
+            "28" => "Science & Technology",
+            "29" => "Nonprofits & Activism",
+            _    => return None,
+        };
+        Some(name.to_string())
+    }
+
+    /// Most of the videos are in the "Music" category, so it tells nothing
+    /// and is dropped from the genres as the root genre of Apple Music is.
+    pub fn genre_names(&self) -> Vec<String> {
+        self.category_name().into_iter().filter(|n| n.as_str() != MUSIC_CATEGORY).collect()
+    }
+}
+
+impl Video {
+    /// This function maps the status of the video to the state of the track.
+    /// The status and the content details are fetched only by `fetch_video`.
+    pub fn state(&self) -> State {
+        if let Some(ref status) = self.status {
+            match status.uploadStatus.as_ref() {
+                "deleted" | "rejected" => return State::Removed,
+                "failed"               => return State::Unavailable,
+                _                      => (),
+            }
+            if status.privacyStatus == "private" {
+                return State::Private;
+            }
+            if status.embeddable == Some(false) {
+                return State::Unavailable;
+            }
+        }
+        match self.contentDetails.as_ref().and_then(|d| d.regionRestriction.as_ref()) {
+            Some(_) => State::RegionRestricted,
+            None    => State::Alive,
+        }
+    }
+}
+
+impl PlaylistItemSnippet {
+    /// YouTube keeps the deleted and the private videos in playlists,
+    /// and tells them only by these titles.
+    pub fn state(&self) -> State {
+        match self.title.as_ref() {
+            "Deleted video" => State::Removed,
+            "Private video" => State::Private,
+            _               => State::Alive,
+        }
+    }
+}
+
 impl HasThumbnail for ChannelSnippet {
     fn get_thumbnails(&self) -> BTreeMap<String, Thumbnail> {
         self.thumbnails.clone().unwrap_or(BTreeMap::new())
     }
 }
 
-pub fn fetch_playlist(id: &str) -> serde_json::Result<PlaylistResponse> {
+pub fn fetch_playlist(id: &str) -> Result<PlaylistResponse, Error> {
     let params = format!("key={}&part=snippet&id={}&maxResults={}",
                          *API_KEY,
                          id,
                          MAX_RESULTS);
     let url     = format!("{}/{}?{}", BASE_URL, "playlists", params);
-    let mut res = http::client().get(&url)
-                                .header(Connection::close())
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    serde_json::from_str(&body)
+    fetch(&url)
 }
 
-pub fn fetch_playlist_items(id: &str) -> serde_json::Result<PlaylistItemResponse> {
+pub fn fetch_playlist_items(id: &str) -> Result<PlaylistItemResponse, Error> {
     let params = format!("key={}&part=snippet&playlistId={}&maxResults={}",
                          *API_KEY,
                          id,
                          MAX_RESULTS);
     let url    = format!("{}/{}?{}", BASE_URL, "playlistItems", params);
-    let mut res = http::client().get(&url)
-                                .header(Connection::close())
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    serde_json::from_str(&body)
+    fetch(&url)
 }
 
-pub fn fetch_video(id: &str) -> serde_json::Result<Video> {
-    let params = format!("key={}&part=snippet&id={}", *API_KEY, id);
+pub fn fetch_video(id: &str) -> Result<Video, Error> {
+    let params = format!("key={}&part=snippet,status,contentDetails&id={}", *API_KEY, id);
     let url    = format!("{}/{}?{}", BASE_URL, "videos", params);
-    let mut res = http::client().get(&url)
-                                .header(Connection::close())
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    let vr: VideoResponse = serde_json::from_str(&body)?;
+    let vr: VideoResponse = fetch(&url)?;
     if vr.items.len() > 0 {
         return Ok(vr.items[0].clone());
     }
-    Err(serde_json::error::Error::custom("track not found".to_string()))
+    Err(Error::Provider(Provider::YouTube, ProviderError::NotFound))
 }
 
-pub fn fetch_channel(id: &str) -> serde_json::Result<Channel> {
+pub fn fetch_channel(id: &str) -> Result<Channel, Error> {
     let params = format!("key={}&part=snippet&id={}", *API_KEY, id);
     let url    = format!("{}/{}?{}", BASE_URL, "channels", params);
-    let mut res = http::client().get(&url)
-                                .header(Connection::close())
-                                .send().unwrap();
-    let mut body = String::new();
-    res.read_to_string(&mut body).unwrap();
-    let vr: ChannelResponse = serde_json::from_str(&body)?;
+    let vr: ChannelResponse = fetch(&url)?;
     if vr.items.len() > 0 {
         return Ok(vr.items[0].clone());
     }
-    Err(serde_json::error::Error::custom("channel not found".to_string()))
+    Err(Error::Provider(Provider::YouTube, ProviderError::NotFound))
+}
+
+fn fetch<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
+    http::get(url)
+        .header(Connection::close())
+        .fetch_json(Provider::YouTube)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn snippet(category_id: &str) -> VideoSnippet {
+        VideoSnippet {
+            title:                "title".to_string(),
+            description:          "".to_string(),
+            publishedAt:          "2018-06-01T10:20:30.000Z".to_string(),
+            channelId:            "UCxxxxxxxxxxxxxxxxxxxxxx".to_string(),
+            channelTitle:         "channel".to_string(),
+            thumbnails:           None,
+            tags:                 None,
+            categoryId:           category_id.to_string(),
+            liveBroadcastContent: "none".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_genre_names() {
+        assert_eq!(snippet("10").category_name(), Some("Music".to_string()));
+        assert_eq!(snippet("10").genre_names(), Vec::<String>::new());
+        assert_eq!(snippet("24").genre_names(), vec!["Entertainment".to_string()]);
+        assert_eq!(snippet("0").genre_names(), Vec::<String>::new());
+    }
 }
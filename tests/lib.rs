@@ -7,8 +7,9 @@ fn it_works() {
 
 #[test]
 fn fetch_apple_music_track() {
+    let conn = pink_spider::model::conn().unwrap();
     let song = pink_spider::apple_music::fetch_song("jp", "1160715431").unwrap();
-    let track = pink_spider::model::Track::from_am_song(&song);
+    let track = pink_spider::model::Track::from_am_song(&*conn, &song);
     assert_eq!(track.identifier, "1160715431");
     assert_eq!(track.title, "A Short Film");
     assert_eq!(track.owner_name.unwrap_or_default(), "LILI LIMIT");
@@ -20,8 +21,9 @@ fn fetch_apple_music_track() {
 
 #[test]
 fn fetch_apple_music_album() {
+    let conn = pink_spider::model::conn().unwrap();
     let am_album = pink_spider::apple_music::fetch_album("jp", "1160715126").unwrap();
-    let album = pink_spider::model::Album::from_am_album(&am_album);
+    let album = pink_spider::model::Album::from_am_album(&*conn, &am_album);
     assert_eq!(album.identifier, "1160715126");
     assert_eq!(album.title, "a.k.a");
     assert_eq!(album.owner_name.unwrap_or_default(), "LILI LIMIT");
@@ -33,8 +35,9 @@ fn fetch_apple_music_album() {
 
 #[test]
 fn fetch_spotify_track() {
+    let conn = pink_spider::model::conn().unwrap();
     let sp_track = pink_spider::spotify::fetch_track("3n3Ppam7vgaVa1iaRUc9Lp").unwrap();
-    let track = pink_spider::model::Track::from_sp_track(&sp_track).unwrap();
+    let track = pink_spider::model::Track::from_sp_track(&*conn, &sp_track).unwrap();
     assert_eq!(track.identifier, "3n3Ppam7vgaVa1iaRUc9Lp");
     assert_eq!(track.title, "Mr. Brightside");
     assert_eq!(track.owner_name.unwrap_or_default(), "The Killers");
@@ -46,8 +49,9 @@ fn fetch_spotify_track() {
 
 #[test]
 fn fetch_spotify_album() {
+    let conn = pink_spider::model::conn().unwrap();
     let sp_album = pink_spider::spotify::fetch_album("4OHNH3sDzIxnmUADXzv2kT").unwrap();
-    let album = pink_spider::model::Album::from_sp_album(&sp_album);
+    let album = pink_spider::model::Album::from_sp_album(&*conn, &sp_album);
     assert_eq!(album.identifier, "4OHNH3sDzIxnmUADXzv2kT");
     assert_eq!(album.title, "Hot Fuss");
     assert_eq!(album.owner_name.unwrap_or_default(), "The Killers");
@@ -60,8 +64,9 @@ fn fetch_spotify_album() {
 
 #[test]
 fn fetch_soundcloud_track() {
+    let conn = pink_spider::model::conn().unwrap();
     let sc_track = pink_spider::soundcloud::fetch_track("371851634").unwrap();
-    let track = pink_spider::model::Track::from_sc_track(&sc_track);
+    let track = pink_spider::model::Track::from_sc_track(&*conn, &sc_track);
     assert_eq!(track.identifier, "371851634");
     assert_eq!(track.title, "Down Wit That");
     assert_eq!(track.owner_name.unwrap_or_default(), "\"Chance The Rapper\"");
@@ -73,8 +78,9 @@ fn fetch_soundcloud_track() {
 
 #[test]
 fn fetch_youtube_track() {
+    let conn = pink_spider::model::conn().unwrap();
     let yt_video = pink_spider::youtube::fetch_video("Wr5f6hpYxmE").unwrap();
-    let track = pink_spider::model::Track::from_yt_video(&yt_video);
+    let track = pink_spider::model::Track::from_yt_video(&*conn, &yt_video);
     assert_eq!(track.identifier, "Wr5f6hpYxmE");
     assert_eq!(track.title, "Cornelius 『あなたがいるなら』If You're Here");
     assert_eq!(track.owner_name.unwrap_or_default(), "corneliusofficial");
@@ -28,8 +28,10 @@ import ArtistList           from './containers/ArtistList';
 import ArtistDetail         from './containers/ArtistDetail';
 import reducers             from './reducers';
 import rootSaga             from './sagas';
+import { installApiKey }    from './utils/apiKey';
 
 injectTapEventPlugin();
+installApiKey();
 
 const history = createHistory();
 const sagaMiddleware = createSagaMiddleware();
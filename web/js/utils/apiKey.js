@@ -0,0 +1,39 @@
+/* global localStorage, window */
+
+import axios from 'axios';
+
+const storageKey = 'pink-spider.apiKey';
+
+export const getApiKey    = () => localStorage.getItem(storageKey);
+export const setApiKey    = key => localStorage.setItem(storageKey, key);
+export const removeApiKey = () => localStorage.removeItem(storageKey);
+
+/**
+ * Updating items needs an api key with the write scope.
+ * The key is asked when the api rejects a request, and kept in localStorage.
+ */
+export const installApiKey = (client = axios) => {
+  client.interceptors.request.use((config) => {
+    const key = getApiKey();
+    if (key) {
+      return Object.assign({}, config, {
+        headers: Object.assign({}, config.headers, { Authorization: `Bearer ${key}` }),
+      });
+    }
+    return config;
+  });
+  client.interceptors.response.use(response => response, (error) => {
+    const { config, response } = error;
+    const rejected = response && (response.status === 401 || response.status === 403);
+    if (!rejected || config.retriedWithApiKey) {
+      return Promise.reject(error);
+    }
+    removeApiKey();
+    const key = window.prompt('API key (scope: write)');
+    if (!key) {
+      return Promise.reject(error);
+    }
+    setApiKey(key.trim());
+    return client.request(Object.assign({}, config, { retriedWithApiKey: true }));
+  });
+};